@@ -0,0 +1,250 @@
+// SPDX-FileCopyrightText: 2022 Profian Inc. <opensource@profian.com>
+// SPDX-License-Identifier: Apache-2.0
+
+use super::Result;
+
+use std::fmt::Write as _;
+use std::fs::{self, File};
+use std::io::{self, copy, sink, BufRead, BufReader, Read, Seek, Write};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex, PoisonError};
+use std::time::SystemTime;
+
+use drawbridge_type::digest::{Algorithm, Algorithms, ContentDigest};
+use drawbridge_type::Meta;
+
+use anyhow::Context;
+use tempfile::NamedTempFile;
+
+const CONTENTS: &str = "contents";
+const VALIDATORS: &str = "validators";
+
+/// Returns the lowercase hexadecimal SHA-256 hash in `hash`, if any.
+fn hex_name(hash: &ContentDigest) -> Option<String> {
+    hash.get(&Algorithm::Sha256).map(|hash| {
+        hash.iter().fold(String::with_capacity(64), |mut name, b| {
+            _ = write!(name, "{b:02x}");
+            name
+        })
+    })
+}
+
+/// On-disk cache of downloaded contents, which may be shared by multiple clients and processes.
+///
+/// Contents are stored in the `contents` subdirectory of the cache, named by the hexadecimal
+/// encoding of their SHA-256 hash, and verified against their digest every time they are read.
+/// Once the total size of the contents exceeds the configured maximum, least recently used ones
+/// are evicted.
+///
+/// `ETag`s and contents of revalidated listings, like tags of a repository, are stored in the
+/// `validators` subdirectory. These count towards the maximum size and are evicted along with
+/// contents.
+#[derive(Debug)]
+pub struct Cache {
+    root: PathBuf,
+    max_size: u64,
+    eviction: Mutex<()>,
+}
+
+impl Cache {
+    /// Opens the cache at `root`, creating it if it does not exist yet, holding at most
+    /// `max_size` bytes of contents and validators.
+    pub fn new(root: impl Into<PathBuf>, max_size: u64) -> Result<Self> {
+        let root = root.into();
+        for dir in [CONTENTS, VALIDATORS] {
+            let dir = root.join(dir);
+            fs::create_dir_all(&dir)
+                .with_context(|| format!("failed to create `{}`", dir.display()))?;
+        }
+        Ok(Self {
+            root,
+            max_size,
+            eviction: Mutex::new(()),
+        })
+    }
+
+    /// Returns the directory of the cache.
+    pub fn root(&self) -> &Path {
+        &self.root
+    }
+
+    /// Returns the maximum total size of cached contents and validators.
+    pub fn max_size(&self) -> u64 {
+        self.max_size
+    }
+
+    fn content_path(&self, hash: &ContentDigest) -> Option<PathBuf> {
+        hex_name(hash).map(|name| self.root.join(CONTENTS).join(name))
+    }
+
+    fn validator_path(&self, path: &str) -> Result<PathBuf> {
+        let (_, hash) = Algorithms::default()
+            .read_sync(path.as_bytes())
+            .context("failed to compute path digest")?;
+        let name = hex_name(&hash).context("path digest lacks a SHA-256 hash")?;
+        Ok(self.root.join(VALIDATORS).join(name))
+    }
+
+    /// Returns a reader of cached contents described by `meta`, if present and valid.
+    ///
+    /// Cached contents not matching `meta` are removed from the cache.
+    pub(super) fn get(&self, Meta { hash, size, .. }: &Meta) -> Result<Option<impl Read>> {
+        let Some(path) = self.content_path(hash) else {
+            return Ok(None);
+        };
+        let mut file = match File::options().read(true).write(true).open(&path) {
+            Ok(file) => file,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(None),
+            Err(e) => {
+                return Err(e).with_context(|| format!("failed to open `{}`", path.display()))
+            }
+        };
+        let valid = match copy(
+            &mut hash.clone().verifier((&mut file).take(size + 1)),
+            &mut sink(),
+        ) {
+            Ok(n) => n == *size,
+            Err(e) if e.kind() == io::ErrorKind::InvalidData => false,
+            Err(e) => {
+                return Err(e).with_context(|| format!("failed to read `{}`", path.display()))
+            }
+        };
+        if !valid {
+            drop(file);
+            // Another client may have removed or replaced the entry concurrently
+            _ = fs::remove_file(&path);
+            return Ok(None);
+        }
+        // Access time updates are commonly disabled, hence track usage by modification time
+        file.set_modified(SystemTime::now())
+            .with_context(|| format!("failed to touch `{}`", path.display()))?;
+        file.rewind()
+            .with_context(|| format!("failed to rewind `{}`", path.display()))?;
+        Ok(Some(file.take(*size)))
+    }
+
+    /// Wraps `rdr` streaming contents described by `meta`, such that contents are inserted into
+    /// the cache once `rdr` reaches the end of them. `rdr` must verify the contents.
+    pub(super) fn insert_from<R: Read>(self: &Arc<Self>, meta: &Meta, rdr: R) -> CacheReader<R> {
+        let file = self
+            .content_path(&meta.hash)
+            .filter(|_| meta.size <= self.max_size)
+            .and_then(|path| {
+                let file = NamedTempFile::with_prefix_in(".drawbridge-", path.parent()?).ok()?;
+                Some((file, path))
+            });
+        CacheReader {
+            cache: self.clone(),
+            rdr,
+            file,
+        }
+    }
+
+    /// Removes least recently used contents and validators until their total size does not
+    /// exceed the maximum.
+    fn evict(&self) -> Result<()> {
+        let _lock = self.eviction.lock().unwrap_or_else(PoisonError::into_inner);
+        let mut entries = vec![];
+        let mut total = 0;
+        for dir in [CONTENTS, VALIDATORS] {
+            let dir = self.root.join(dir);
+            for entry in
+                fs::read_dir(&dir).with_context(|| format!("failed to read `{}`", dir.display()))?
+            {
+                let entry = entry.with_context(|| format!("failed to read `{}`", dir.display()))?;
+                if entry.file_name().to_string_lossy().starts_with('.') {
+                    continue;
+                }
+                // Entries may be removed concurrently
+                let Ok(meta) = entry.metadata() else {
+                    continue;
+                };
+                total += meta.len();
+                entries.push((
+                    meta.modified().unwrap_or(SystemTime::UNIX_EPOCH),
+                    meta.len(),
+                    entry.path(),
+                ));
+            }
+        }
+        entries.sort_unstable();
+        for (_, size, path) in entries {
+            if total <= self.max_size {
+                break;
+            }
+            _ = fs::remove_file(path);
+            total -= size;
+        }
+        Ok(())
+    }
+
+    /// Returns the `ETag` and contents of the listing at `path` stored by
+    /// [Self::insert_validator], if any.
+    pub(super) fn validator(&self, path: &str) -> Result<Option<(String, Vec<u8>)>> {
+        let path = self.validator_path(path)?;
+        let mut rdr = match File::options().read(true).write(true).open(&path) {
+            Ok(file) => BufReader::new(file),
+            Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(None),
+            Err(e) => {
+                return Err(e).with_context(|| format!("failed to open `{}`", path.display()))
+            }
+        };
+        let mut etag = String::new();
+        let mut buf = vec![];
+        _ = rdr
+            .read_line(&mut etag)
+            .and_then(|_| rdr.read_to_end(&mut buf))
+            .with_context(|| format!("failed to read `{}`", path.display()))?;
+        rdr.get_ref()
+            .set_modified(SystemTime::now())
+            .with_context(|| format!("failed to touch `{}`", path.display()))?;
+        Ok(etag.strip_suffix('\n').map(|etag| (etag.to_string(), buf)))
+    }
+
+    /// Stores `etag` and contents `buf` of the listing at `path`, unless they exceed the maximum
+    /// size of the cache.
+    pub(super) fn insert_validator(&self, path: &str, etag: &str, buf: &[u8]) -> Result<()> {
+        if (etag.len() + 1 + buf.len()) as u64 > self.max_size {
+            return Ok(());
+        }
+        let path = self.validator_path(path)?;
+        let dir = self.root.join(VALIDATORS);
+        let mut file = NamedTempFile::with_prefix_in(".drawbridge-", &dir)
+            .with_context(|| format!("failed to create temporary file in `{}`", dir.display()))?;
+        writeln!(file, "{etag}")
+            .and_then(|_| file.write_all(buf))
+            .context("failed to write validator")?;
+        _ = file
+            .persist(&path)
+            .with_context(|| format!("failed to persist `{}`", path.display()))?;
+        self.evict()
+    }
+}
+
+/// Reader returned by [Cache::insert_from], which copies contents into the cache while they
+/// are read.
+#[derive(Debug)]
+pub(super) struct CacheReader<R> {
+    cache: Arc<Cache>,
+    rdr: R,
+    file: Option<(NamedTempFile, PathBuf)>,
+}
+
+impl<R: Read> Read for CacheReader<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let n = self.rdr.read(buf)?;
+        if n > 0 {
+            if let Some((ref mut file, _)) = self.file {
+                if file.write_all(&buf[..n]).is_err() {
+                    // Caching is best-effort, contents are still returned to the reader
+                    self.file = None;
+                }
+            }
+        } else if let Some((file, path)) = self.file.take() {
+            if file.persist(path).is_ok() {
+                _ = self.cache.evict();
+            }
+        }
+        Ok(n)
+    }
+}
@@ -1,48 +1,182 @@
 // SPDX-FileCopyrightText: 2022 Profian Inc. <opensource@profian.com>
 // SPDX-License-Identifier: Apache-2.0
 
-use super::{scope, Client, Result, Scope};
+use super::{
+    check_limit, check_media_type, scope, Client, Limits, Progress, ProgressReader, Result, Scope,
+    Url,
+};
 
-use std::io::{copy, Read, Write};
+use std::fs::File;
+use std::io::{copy, sink, ErrorKind, Read, Seek, SeekFrom, Write};
 use std::marker::PhantomData;
+use std::num::NonZeroUsize;
 use std::str::FromStr;
+use std::sync::{Arc, PoisonError};
 
 use drawbridge_type::digest::{Algorithms, ContentDigest};
-use drawbridge_type::Meta;
+use drawbridge_type::{Meta, TreePath};
 
 use anyhow::{anyhow, bail, ensure, Context};
-use http::header::{CONTENT_LENGTH, CONTENT_TYPE};
+use http::header::{
+    CONTENT_LENGTH, CONTENT_RANGE, CONTENT_TYPE, ETAG, IF_MATCH, IF_NONE_MATCH, IF_RANGE, LINK,
+    RANGE,
+};
 use http::StatusCode;
 use mime::Mime;
 use ureq::serde::{Deserialize, Serialize};
 use ureq::{Request, Response};
 
-fn parse_header<T>(req: &Response, name: &str) -> Result<T>
+/// Headers of responses, which are parsed alike by blocking and asynchronous clients.
+pub(super) trait ResponseHeaders {
+    /// Returns the value of the header `name`, if any.
+    fn header_value(&self, name: &str) -> Option<&str>;
+
+    /// Returns all values of the header `name`.
+    fn header_values(&self, name: &str) -> Vec<&str>;
+}
+
+impl ResponseHeaders for Response {
+    fn header_value(&self, name: &str) -> Option<&str> {
+        self.header(name)
+    }
+
+    fn header_values(&self, name: &str) -> Vec<&str> {
+        self.all(name)
+    }
+}
+
+pub(super) fn parse_header<T>(res: &impl ResponseHeaders, name: &str) -> Result<T>
 where
     T: FromStr,
     T::Err: 'static + Sync + Send + std::error::Error,
 {
-    req.header(name)
+    res.header_value(name)
         .ok_or_else(|| anyhow!("missing `{name}` header"))?
         .parse()
         .context(format!("failed to parse `{name}` header"))
 }
 
+/// Parses the metadata of the contents described by the headers of `res`.
+pub(super) fn parse_meta(res: &impl ResponseHeaders) -> Result<Meta> {
+    Ok(Meta {
+        hash: parse_header(res, "Content-Digest")?,
+        size: parse_header(res, CONTENT_LENGTH.as_str())?,
+        mime: parse_header(res, CONTENT_TYPE.as_str())?,
+    })
+}
+
+/// Parses the metadata of the contents of a response to a `GET` request and fails if they exceed
+/// `limit` or are not of one of the `accept` media types.
+pub(super) fn parse_get_meta(
+    res: &impl ResponseHeaders,
+    limit: u64,
+    accept: &[&str],
+) -> Result<Meta> {
+    let meta = parse_meta(res)?;
+    check_limit(meta.size, limit)?;
+    check_media_type(&meta.mime, accept)?;
+    Ok(meta)
+}
+
+/// Returns the URL of the next page of the listing at `url`, which the server advertises in a
+/// `Link` header of `res`.
+pub(super) fn parse_next_page(res: &impl ResponseHeaders, url: &Url) -> Result<Option<Url>> {
+    res.header_values(LINK.as_str())
+        .into_iter()
+        .flat_map(|v| v.split(','))
+        .find_map(parse_next_link)
+        .map(|link| url.join(link).context("failed to construct next page URL"))
+        .transpose()
+}
+
+/// Returns the error describing a response with status `code` and body `msg`, if any.
+pub(super) fn status_error(code: u16, msg: Option<String>) -> anyhow::Error {
+    match msg {
+        Some(msg) if !msg.is_empty() => {
+            anyhow!(msg).context(format!("request failed with status code `{code}`"))
+        }
+        _ => anyhow!("request failed with status code `{code}`"),
+    }
+}
+
+/// Returns the `Authorization` header of requests authorized by `token`.
+pub(super) fn authorization(token: &str) -> (&'static str, String) {
+    ("Authorization", format!("Bearer {token}"))
+}
+
+/// Returns the headers of requests fetching contents of one of the `accept` media types, which
+/// are authorized by `token`, if any.
+pub(super) fn fetch_headers(token: Option<&str>, accept: &[&str]) -> Vec<(&'static str, String)> {
+    token
+        .map(authorization)
+        .into_iter()
+        .chain((!accept.is_empty()).then(|| ("Accept", accept.join(", "))))
+        .collect()
+}
+
+/// Returns the headers of `PUT` requests creating contents with `hash` of media type `mime`.
+pub(super) fn create_headers(hash: &ContentDigest, mime: &Mime) -> [(&'static str, String); 2] {
+    [
+        ("Content-Digest", hash.to_string()),
+        ("Content-Type", mime.to_string()),
+    ]
+}
+
+fn parse_get_response(res: Response, limit: u64, accept: &[&str]) -> Result<(Meta, impl Read)> {
+    let meta = parse_get_meta(&res, limit, accept)?;
+    match StatusCode::from_u16(res.status()) {
+        Ok(StatusCode::OK) => {
+            let rdr = meta
+                .hash
+                .clone()
+                .verifier(res.into_reader().take(meta.size));
+            Ok((meta, rdr))
+        }
+        _ => bail!("unexpected status code: {}", res.status()),
+    }
+}
+
+fn parse_head_response(res: Response) -> Result<(Meta, Response)> {
+    match StatusCode::from_u16(res.status()) {
+        Ok(StatusCode::OK) => Ok((parse_meta(&res)?, res)),
+        _ => bail!("unexpected status code: {}", res.status()),
+    }
+}
+
+/// Returns `true` if `meta` describes the same contents as `expected`, i.e. if sizes and media
+/// types are equal and all hashes in `expected` match those in `meta`.
+pub(super) fn meta_matches(meta: &Meta, expected: &Meta) -> bool {
+    meta.size == expected.size
+        && meta.mime.essence_str() == expected.mime.essence_str()
+        && !expected.hash.is_empty()
+        && expected
+            .hash
+            .iter()
+            .all(|(algo, hash)| meta.hash.get(algo).map(|h| h.as_ref()) == Some(hash.as_ref()))
+}
+
+/// Returns the target of a `Link` header value if its relation type is `next`.
+fn parse_next_link(link: &str) -> Option<&str> {
+    let (target, params) = link.trim().strip_prefix('<')?.split_once('>')?;
+    params
+        .split(';')
+        .filter_map(|param| param.trim().strip_prefix("rel="))
+        .any(|rel| rel.trim_matches('"').split(' ').any(|rel| rel == "next"))
+        .then_some(target)
+}
+
 #[derive(Clone, Debug)]
 pub struct Entity<'a, C: Scope, E: Scope> {
     client: &'a Client<C>,
     path: String,
+    node: Option<TreePath>,
+    accept: &'static [&'static str],
     phantom: PhantomData<E>,
 }
 
 fn parse_ureq_error(e: ureq::Error) -> anyhow::Error {
     match e {
-        ureq::Error::Status(code, msg) => match msg.into_string() {
-            Ok(msg) if !msg.is_empty() => {
-                anyhow!(msg).context(format!("request failed with status code `{code}`"))
-            }
-            _ => anyhow!("request failed with status code `{code}`"),
-        },
+        ureq::Error::Status(code, msg) => status_error(code, msg.into_string().ok()),
 
         ureq::Error::Transport(e) => anyhow::Error::new(e).context("transport layer failure"),
     }
@@ -53,6 +187,8 @@ impl<'a, C: Scope> Entity<'a, C, C> {
         Self {
             client,
             path: Default::default(),
+            node: None,
+            accept: &[],
             phantom: PhantomData,
         }
     }
@@ -64,6 +200,8 @@ impl<'a> Entity<'a, scope::Unknown, scope::Unknown> {
         Entity {
             client: self.client,
             path: self.path,
+            node: self.node,
+            accept: self.accept,
             phantom: PhantomData,
         }
     }
@@ -75,24 +213,171 @@ impl<'a, C: Scope, E: Scope> Entity<'a, C, E> {
         Entity {
             client: self.client,
             path: format!("{}/{}", self.path, path),
+            node: None,
+            accept: &[],
             phantom: PhantomData,
         }
     }
 
-    pub(super) fn create_request(&self, hash: &ContentDigest, mime: &Mime) -> Result<Request> {
+    /// Restricts the media types of contents of the entity accepted by the client to `accept`,
+    /// which are requested in `Accept` headers and checked against the `Content-Type` of
+    /// responses.
+    pub(super) fn accept(self, accept: &'static [&'static str]) -> Self {
+        Self { accept, ..self }
+    }
+
+    /// Marks the entity as the tree node at `path`, whose transfers are reported to the progress
+    /// observer of the client.
+    pub(super) fn tree_node(self, path: &TreePath) -> Self {
+        Self {
+            node: Some(path.clone()),
+            ..self
+        }
+    }
+
+    /// Returns the progress observer of the client, if any.
+    pub(super) fn progress(&self) -> Option<&Arc<dyn Progress>> {
+        self.client.progress.as_ref()
+    }
+
+    /// Wraps `rdr` to report bytes read from it as transferred bytes of the tree node.
+    fn track<R>(&self, rdr: R) -> ProgressReader<R> {
+        ProgressReader {
+            progress: self.progress().cloned().zip(self.node.clone()),
+            rdr,
+        }
+    }
+
+    /// Notifies the progress observer about the start of the transfer of the tree node described
+    /// by `meta`, runs `f` transferring it and notifies the observer about its completion.
+    pub(super) fn track_node<T>(&self, meta: &Meta, f: impl FnOnce() -> Result<T>) -> Result<T> {
+        let Some((progress, path)) = self.progress().zip(self.node.as_ref()) else {
+            return f();
+        };
+        progress.start(path, meta);
+        let v = f()?;
+        progress.finish(path);
+        Ok(v)
+    }
+
+    /// Returns the response size limits of the client.
+    pub(super) fn limits(&self) -> Limits {
+        self.client.limits
+    }
+
+    /// Returns the maximum amount of tree nodes processed concurrently by the client.
+    pub(super) fn parallelism(&self) -> NonZeroUsize {
+        self.client.parallelism
+    }
+
+    /// Returns the URL of the entity.
+    pub(super) fn url(&self) -> Result<Url> {
+        self.client.url(&self.path)
+    }
+
+    /// Sends `req` without a body, retrying transient failures. `req` must be idempotent.
+    #[allow(clippy::result_large_err)]
+    fn call(&self, req: Request) -> Result<Response, ureq::Error> {
+        self.client.retry(|| req.clone().call())
+    }
+
+    /// Sends `req` with `data`, retrying transient failures. `req` must be idempotent.
+    #[allow(clippy::result_large_err)]
+    fn send_bytes(&self, req: Request, data: &[u8]) -> Result<Response, ureq::Error> {
+        self.client.retry(|| req.clone().send_bytes(data))
+    }
+
+    /// Creates the entity with contents described by `meta` using `send`, which sends a `PUT`
+    /// request and is retried on transient failures. Returns `true` if the entity was created
+    /// and `false` if it existed already.
+    ///
+    /// An attempt failing after the server created the entity causes the retried request to
+    /// conflict with the entity, so conflicts after retries are reported as created entity, if
+    /// the entity exists with contents matching `meta`.
+    #[allow(clippy::result_large_err)]
+    fn create_retried(
+        &self,
+        meta: &Meta,
+        mut send: impl FnMut() -> Result<Response, ureq::Error>,
+    ) -> Result<bool> {
+        let mut attempts = 0;
+        let res = match self.client.retry(|| {
+            attempts += 1;
+            send()
+        }) {
+            Ok(res) => res,
+            Err(e @ ureq::Error::Status(409, _)) if attempts > 1 => {
+                return match self.try_head()? {
+                    Some(existing) if meta_matches(&existing, meta) => Ok(true),
+                    _ => Err(parse_ureq_error(e)),
+                };
+            }
+            Err(e) => return Err(parse_ureq_error(e)),
+        };
+        match StatusCode::from_u16(res.status()) {
+            Ok(StatusCode::CREATED) => Ok(true),
+            Ok(StatusCode::OK) => Ok(false),
+            _ => bail!("unexpected status code: {}", res.status()),
+        }
+    }
+
+    fn authorized_request(&self, method: &str) -> Result<Request> {
         let token = self.client.token.as_ref().ok_or_else(|| {
             anyhow!("endpoint requires authorization, but no token was configured")
         })?;
         let url = self.client.url(&self.path)?;
+        let (name, value) = authorization(token);
         Ok(self
             .client
             .inner
-            .put(url.as_str())
-            .set("Authorization", &format!("Bearer {token}"))
-            .set("Content-Digest", &hash.to_string())
-            .set(CONTENT_TYPE.as_str(), mime.as_ref()))
+            .request(method, url.as_str())
+            .set(name, &value))
+    }
+
+    pub(super) fn create_request(&self, hash: &ContentDigest, mime: &Mime) -> Result<Request> {
+        Ok(create_headers(hash, mime)
+            .iter()
+            .fold(self.authorized_request("PUT")?, |req, (name, value)| {
+                req.set(name, value)
+            }))
+    }
+
+    /// Sends an authorized `PUT` request without a body and decodes the JSON response.
+    #[allow(single_use_lifetimes)]
+    pub(super) fn put_empty_json<T>(&self) -> Result<T>
+    where
+        for<'de> T: Deserialize<'de>,
+    {
+        let res = self
+            .send_bytes(self.authorized_request("PUT")?, &[])
+            .map_err(parse_ureq_error)?;
+        match StatusCode::from_u16(res.status()) {
+            Ok(StatusCode::OK) => res.into_json().context("failed to decode JSON"),
+            _ => bail!("unexpected status code: {}", res.status()),
+        }
+    }
+
+    /// Deletes the entity.
+    pub(super) fn delete(&self) -> Result<()> {
+        self.delete_with(&[])
+    }
+
+    /// Deletes the entity sending additional `headers`.
+    pub(super) fn delete_with(&self, headers: &[(&str, &str)]) -> Result<()> {
+        let res = headers
+            .iter()
+            .fold(self.authorized_request("DELETE")?, |req, (name, value)| {
+                req.set(name, value)
+            })
+            .call()
+            .map_err(parse_ureq_error)?;
+        match StatusCode::from_u16(res.status()) {
+            Ok(StatusCode::NO_CONTENT) => Ok(()),
+            _ => bail!("unexpected status code: {}", res.status()),
+        }
     }
 
+    #[allow(clippy::result_large_err)]
     pub(super) fn create_bytes(&self, mime: &Mime, data: impl AsRef<[u8]>) -> Result<bool> {
         let data = data.as_ref();
         let (n, hash) = Algorithms::default()
@@ -103,15 +388,13 @@ impl<'a, C: Scope, E: Scope> Entity<'a, C, E> {
             "invalid amount of bytes read, expected {}, read {n}",
             data.len(),
         );
-        let res = self
-            .create_request(&hash, mime)?
-            .send_bytes(data)
-            .map_err(parse_ureq_error)?;
-        match StatusCode::from_u16(res.status()) {
-            Ok(StatusCode::CREATED) => Ok(true),
-            Ok(StatusCode::OK) => Ok(false),
-            _ => bail!("unexpected status code: {}", res.status()),
-        }
+        let req = self.create_request(&hash, mime)?;
+        let meta = Meta {
+            hash,
+            size: n,
+            mime: mime.clone(),
+        };
+        self.create_retried(&meta, || req.clone().send_bytes(data))
     }
 
     pub(super) fn create_json(&self, mime: &Mime, val: &impl Serialize) -> Result<bool> {
@@ -119,6 +402,27 @@ impl<'a, C: Scope, E: Scope> Entity<'a, C, E> {
         self.create_bytes(mime, buf)
     }
 
+    /// Replaces the contents of the existing entity by `val` encoded as JSON.
+    pub(super) fn update_json(&self, mime: &Mime, val: &impl Serialize) -> Result<()> {
+        let buf = serde_json::to_vec(val).context("failed to encode value to JSON")?;
+        let (n, hash) = Algorithms::default()
+            .read_sync(&buf[..])
+            .context("failed to compute content digest")?;
+        ensure!(
+            n == buf.len() as u64,
+            "invalid amount of bytes read, expected {}, read {n}",
+            buf.len(),
+        );
+        let req = self
+            .create_request(&hash, mime)?
+            .set(IF_MATCH.as_str(), "*");
+        let res = self.send_bytes(req, &buf).map_err(parse_ureq_error)?;
+        match StatusCode::from_u16(res.status()) {
+            Ok(StatusCode::OK) => Ok(()),
+            _ => bail!("unexpected status code: {}", res.status()),
+        }
+    }
+
     pub(super) fn create_from(
         &self,
         Meta { hash, size, mime }: &Meta,
@@ -127,7 +431,7 @@ impl<'a, C: Scope, E: Scope> Entity<'a, C, E> {
         let res = self
             .create_request(hash, mime)?
             .set(CONTENT_LENGTH.as_str(), &size.to_string())
-            .send(rdr)
+            .send(self.track(rdr))
             .map_err(parse_ureq_error)?;
         match StatusCode::from_u16(res.status()) {
             Ok(StatusCode::CREATED) => Ok(true),
@@ -136,36 +440,174 @@ impl<'a, C: Scope, E: Scope> Entity<'a, C, E> {
         }
     }
 
-    pub fn get(&self, limit: u64) -> Result<(Meta, impl Read)> {
+    /// Like [Self::create_from], but retries transient failures, seeking `rdr` back to its initial
+    /// position before each attempt.
+    #[allow(clippy::result_large_err)]
+    pub(super) fn create_from_seekable(&self, meta: &Meta, rdr: impl Read + Seek) -> Result<bool> {
+        let mut rdr = self.track(rdr);
+        let start = rdr
+            .stream_position()
+            .context("failed to determine reader position")?;
+        let req = self
+            .create_request(&meta.hash, &meta.mime)?
+            .set(CONTENT_LENGTH.as_str(), &meta.size.to_string());
+        self.create_retried(meta, || {
+            _ = rdr.seek(SeekFrom::Start(start))?;
+            req.clone().send(&mut rdr)
+        })
+    }
+
+    /// Creates the entity with contents of media type `mime` read from `rdr`, which are verified
+    /// by the server. Returns `None` if the server does not support the endpoint, that is if it
+    /// responds with 405 or with 404, while `supported` reports that the endpoint should exist.
+    pub(super) fn create_stream(
+        &self,
+        mime: &Mime,
+        rdr: impl Read,
+        supported: impl FnOnce() -> Result<bool>,
+    ) -> Result<Option<bool>> {
+        match self
+            .authorized_request("PUT")?
+            .set(CONTENT_TYPE.as_str(), mime.as_ref())
+            .send(rdr)
+        {
+            Ok(res) => match StatusCode::from_u16(res.status()) {
+                Ok(StatusCode::CREATED) => Ok(Some(true)),
+                Ok(StatusCode::OK) => Ok(Some(false)),
+                _ => bail!("unexpected status code: {}", res.status()),
+            },
+            Err(ureq::Error::Status(405, _)) => Ok(None),
+            Err(e @ ureq::Error::Status(404, _)) => {
+                if supported()? {
+                    Ok(None)
+                } else {
+                    Err(parse_ureq_error(e))
+                }
+            }
+            Err(e) => Err(parse_ureq_error(e)),
+        }
+    }
+
+    /// Creates the entity referring to contents described by `meta` already stored on the
+    /// server, without uploading them.
+    #[allow(clippy::result_large_err)]
+    pub(super) fn create_from_blob(&self, meta: &Meta) -> Result<bool> {
+        let req = self.create_request(&meta.hash, &meta.mime)?;
+        self.create_retried(meta, || req.clone().send_bytes(&[]))
+    }
+
+    /// Returns `true` if the server holds contents with `hash` at the entity.
+    pub(super) fn has_digest(&self, hash: &ContentDigest) -> Result<bool> {
+        let req = self
+            .head_request()?
+            .set("Content-Digest", &hash.to_string());
+        match self.call(req) {
+            Ok(res) if res.status() == StatusCode::OK => Ok(true),
+            Ok(res) => bail!("unexpected status code: {}", res.status()),
+            Err(ureq::Error::Status(404, _)) => Ok(false),
+            Err(e) => Err(parse_ureq_error(e)).context("HEAD request failed"),
+        }
+    }
+
+    fn get_request(&self) -> Result<Request> {
+        self.get_request_url(&self.client.url(&self.path)?)
+    }
+
+    fn get_request_url(&self, url: &Url) -> Result<Request> {
+        Ok(self
+            .fetch_request(self.client.inner.get(url.as_str()))
+            .set("Accept-Encoding", ""))
+    }
+
+    fn head_request(&self) -> Result<Request> {
         let url = self.client.url(&self.path)?;
-        let mut req = self.client.inner.get(url.as_str());
-        if let Some(ref token) = self.client.token {
-            req = req.set("Authorization", &format!("Bearer {token}"))
+        Ok(self.fetch_request(self.client.inner.head(url.as_str())))
+    }
+
+    /// Sets the headers of `req` fetching the entity.
+    fn fetch_request(&self, req: Request) -> Request {
+        fetch_headers(self.client.token.as_deref(), self.accept)
+            .iter()
+            .fold(req, |req, (name, value)| req.set(name, value))
+    }
+
+    fn head_response(&self) -> Result<(Meta, Response)> {
+        let res = self
+            .call(self.head_request()?)
+            .map_err(parse_ureq_error)
+            .context("HEAD request failed")?;
+        parse_head_response(res)
+    }
+
+    /// Returns metadata of the entity without fetching its contents.
+    pub fn head(&self) -> Result<Meta> {
+        self.head_response().map(|(meta, _)| meta)
+    }
+
+    /// Returns metadata of the entity without fetching its contents or `None` if it does not
+    /// exist.
+    pub(super) fn try_head(&self) -> Result<Option<Meta>> {
+        match self.call(self.head_request()?) {
+            Ok(res) => parse_head_response(res).map(|(meta, _)| Some(meta)),
+            Err(ureq::Error::Status(404, _)) => Ok(None),
+            Err(e) => Err(parse_ureq_error(e)).context("HEAD request failed"),
         }
-        let res = req
-            .set("Accept-Encoding", "")
-            .call()
+    }
+
+    /// Creates the entity using `create`, unless it already exists with contents matching
+    /// `meta`. Returns `true` if the entity was created and `false` if the existing one was
+    /// reused. Fails if the entity exists with different contents.
+    pub(super) fn create_or_reuse(
+        &self,
+        meta: &Meta,
+        create: impl FnOnce() -> Result<bool>,
+    ) -> Result<bool> {
+        match self.try_head()? {
+            None => create(),
+            Some(existing) if meta_matches(&existing, meta) => Ok(false),
+            Some(_) => bail!("entity exists with different contents"),
+        }
+    }
+
+    /// Returns the value of header `name` of the response to a HEAD request for the entity.
+    pub(super) fn head_header<T>(&self, name: &str) -> Result<T>
+    where
+        T: FromStr,
+        T::Err: 'static + Sync + Send + std::error::Error,
+    {
+        self.head_response()
+            .and_then(|(_, res)| parse_header(&res, name))
+    }
+
+    fn fetch(&self, limit: u64) -> Result<(Meta, impl 'static + Send + Read)> {
+        let res = self
+            .call(self.get_request()?)
             .map_err(parse_ureq_error)
             .context("GET request failed")?;
+        parse_get_response(res, limit, self.accept)
+    }
 
-        let hash: ContentDigest = parse_header(&res, "Content-Digest")?;
-        let mime = parse_header(&res, CONTENT_TYPE.as_str())?;
-        let size = parse_header(&res, CONTENT_LENGTH.as_str())?;
-        ensure!(
-            size <= limit,
-            "response size of `{size}` exceeds the limit of `{limit}`"
-        );
-        match StatusCode::from_u16(res.status()) {
-            Ok(StatusCode::OK) => Ok((
-                Meta {
-                    hash: hash.clone(),
-                    size,
-                    mime,
-                },
-                hash.verifier(res.into_reader().take(size)),
-            )),
-            _ => bail!("unexpected status code: {}", res.status()),
+    /// Fetches contents of the entity, which are verified against their digest while reading.
+    ///
+    /// If the client has a [Cache](super::Cache), the digest of the contents is determined by a
+    /// `HEAD` request and cached contents are returned if present. Otherwise, downloaded contents
+    /// are inserted into the cache once read completely. Bytes read from tree nodes are reported to
+    /// the progress observer of the client.
+    pub fn get(&self, limit: u64) -> Result<(Meta, impl Read)> {
+        let Some(ref cache) = self.client.cache else {
+            let (meta, rdr) = self.fetch(limit)?;
+            let rdr: Box<dyn Read + Send> = Box::new(rdr);
+            return Ok((meta, self.track(rdr)));
+        };
+        let meta = self.head()?;
+        check_limit(meta.size, limit)?;
+        check_media_type(&meta.mime, self.accept)?;
+        if let Some(rdr) = cache.get(&meta).context("failed to read cache")? {
+            return Ok((meta, self.track(Box::new(rdr))));
         }
+        let (meta, rdr) = self.fetch(limit)?;
+        let rdr = cache.insert_from(&meta, rdr);
+        Ok((meta, self.track(Box::new(rdr))))
     }
 
     pub fn get_to(&self, limit: u64, dst: &mut impl Write) -> Result<Meta> {
@@ -178,6 +620,63 @@ impl<'a, C: Scope, E: Scope> Entity<'a, C, E> {
         Ok(meta)
     }
 
+    /// Downloads the contents of the entity into `dst`, resuming from the end of any contents
+    /// `dst` already holds, and verifies the digest of the complete contents.
+    pub fn resume_to(&self, limit: u64, dst: &mut File) -> Result<Meta> {
+        let (meta @ Meta { size, .. }, head) = self.head_response()?;
+        check_limit(size, limit)?;
+        let offset = dst.seek(SeekFrom::End(0))?;
+        ensure!(
+            offset <= size,
+            "destination holds more data than the entity, expected at most {size}, got {offset}"
+        );
+        if offset < size {
+            let mut req = self
+                .get_request()?
+                .set(RANGE.as_str(), &format!("bytes={offset}-"));
+            if let Some(etag) = head.header(ETAG.as_str()) {
+                req = req.set(IF_RANGE.as_str(), etag);
+            }
+            let res = self
+                .call(req)
+                .map_err(parse_ureq_error)
+                .context("GET request failed")?;
+            let offset = match StatusCode::from_u16(res.status()) {
+                Ok(StatusCode::PARTIAL_CONTENT) => {
+                    let range = res
+                        .header(CONTENT_RANGE.as_str())
+                        .ok_or_else(|| anyhow!("missing `{CONTENT_RANGE}` header"))?;
+                    ensure!(
+                        range == format!("bytes {offset}-{}/{size}", size - 1),
+                        "unexpected content range `{range}`"
+                    );
+                    offset
+                }
+                Ok(StatusCode::OK) => {
+                    dst.set_len(0)?;
+                    dst.rewind()?;
+                    0
+                }
+                _ => bail!("unexpected status code: {}", res.status()),
+            };
+            _ = copy(&mut res.into_reader().take(size - offset), dst)?;
+        }
+
+        dst.rewind()?;
+        let n = match copy(
+            &mut meta.hash.clone().verifier((&mut *dst).take(size + 1)),
+            &mut sink(),
+        ) {
+            Err(e) if e.kind() == ErrorKind::InvalidData => bail!("content digest mismatch"),
+            res => res?,
+        };
+        ensure!(
+            n == size,
+            "invalid amount of bytes read, expected {size}, read {n}"
+        );
+        Ok(meta)
+    }
+
     #[allow(single_use_lifetimes)]
     pub fn get_json<T>(&self, limit: u64) -> Result<(Meta, T)>
     where
@@ -188,9 +687,98 @@ impl<'a, C: Scope, E: Scope> Entity<'a, C, E> {
         Ok((meta, v))
     }
 
+    /// Like [Self::get_json], but revalidates contents fetched by a previous call using their
+    /// `ETag`, in which case the contents are not transferred again if they did not change.
+    /// Validators are persisted in the [Cache](super::Cache) of the client, if any, keyed by the
+    /// entity URL, and held in a bounded amount of memory otherwise.
+    #[allow(single_use_lifetimes)]
+    pub fn get_json_cached<T>(&self, limit: u64) -> Result<T>
+    where
+        for<'de> T: Deserialize<'de>,
+    {
+        let cached = match self.client.cache {
+            Some(ref cache) => cache
+                .validator(self.url()?.as_str())
+                .context("failed to read cache")?,
+            None => self
+                .client
+                .validators
+                .lock()
+                .unwrap_or_else(PoisonError::into_inner)
+                .get(&self.path),
+        };
+        let mut req = self.get_request()?;
+        if let Some((ref etag, _)) = cached {
+            req = req.set(IF_NONE_MATCH.as_str(), etag);
+        }
+        let res = self
+            .call(req)
+            .map_err(parse_ureq_error)
+            .context("GET request failed")?;
+        let buf = match (StatusCode::from_u16(res.status()), cached) {
+            (Ok(StatusCode::NOT_MODIFIED), Some((_, buf))) => buf,
+            _ => {
+                let etag = res.header(ETAG.as_str()).map(ToString::to_string);
+                let (Meta { size, .. }, mut rdr) = parse_get_response(res, limit, self.accept)?;
+                let mut buf = vec![];
+                let n = copy(&mut rdr, &mut buf).context("I/O failure")?;
+                ensure!(
+                    n == size,
+                    "invalid amount of bytes read, expected {size}, read {n}"
+                );
+                match (etag, &self.client.cache) {
+                    (Some(etag), Some(cache)) => cache
+                        .insert_validator(self.url()?.as_str(), &etag, &buf)
+                        .context("failed to write cache")?,
+                    (Some(etag), None) => self
+                        .client
+                        .validators
+                        .lock()
+                        .unwrap_or_else(PoisonError::into_inner)
+                        .insert(self.path.clone(), etag, buf.clone()),
+                    (None, _) => {}
+                }
+                buf
+            }
+        };
+        serde_json::from_slice(&buf).context("failed to decode JSON")
+    }
+
+    /// Fetches contents at `url`, which are streamed without a known length or digest, and
+    /// returns their media type and a reader.
+    pub(super) fn get_stream(&self, url: &Url) -> Result<(Mime, impl Read)> {
+        let res = self
+            .call(self.get_request_url(url)?)
+            .map_err(parse_ureq_error)
+            .context("GET request failed")?;
+        match StatusCode::from_u16(res.status()) {
+            Ok(StatusCode::OK) => Ok((
+                parse_header(&res, CONTENT_TYPE.as_str())?,
+                res.into_reader(),
+            )),
+            _ => bail!("unexpected status code: {}", res.status()),
+        }
+    }
+
+    /// Fetches a page of a JSON-encoded listing at `url` and returns it along with the URL of the
+    /// next page, which the server advertises in a `Link` header.
+    #[allow(single_use_lifetimes)]
+    pub(super) fn get_json_page<T>(&self, url: &Url, limit: u64) -> Result<(T, Option<Url>)>
+    where
+        for<'de> T: Deserialize<'de>,
+    {
+        let res = self
+            .call(self.get_request_url(url)?)
+            .map_err(parse_ureq_error)
+            .context("GET request failed")?;
+        let next = parse_next_page(&res, url)?;
+        let (_, rdr) = parse_get_response(res, limit, self.accept)?;
+        let v = serde_json::from_reader(rdr).context("failed to decode JSON")?;
+        Ok((v, next))
+    }
+
     pub fn get_bytes(&self, limit: u64) -> Result<(Meta, Vec<u8>)> {
-        let (meta @ Meta { size, .. }, rdr) = self.get(limit)?;
-        let mut rdr = rdr.take(limit);
+        let (meta @ Meta { size, .. }, mut rdr) = self.get(limit)?;
         let mut buf =
             Vec::with_capacity(size.try_into().context("failed to convert u64 to usize")?);
         let n = copy(&mut rdr, &mut buf).context("I/O failure")?;
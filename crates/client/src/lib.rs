@@ -28,14 +28,26 @@
     variant_size_differences
 )]
 
+mod cache;
 mod entity;
+mod limits;
+mod media_type;
+#[cfg(feature = "async")]
+pub mod nonblocking;
+mod progress;
 mod repo;
+mod retry;
 mod tag;
 mod tree;
 mod user;
 
+pub use cache::*;
 pub use entity::*;
+pub use limits::*;
+pub use media_type::*;
+pub use progress::*;
 pub use repo::*;
+pub use retry::*;
 pub use tag::*;
 pub use tree::*;
 pub use user::*;
@@ -45,10 +57,15 @@ pub use drawbridge_type as types;
 
 pub use anyhow::{Context, Result};
 pub use mime;
+pub use semver;
 pub use url::Url;
 
+use std::collections::{HashMap, VecDeque};
 use std::marker::PhantomData;
-use std::sync::Arc;
+use std::num::NonZeroUsize;
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::Duration;
 
 use drawbridge_type::{RepositoryContext, TagContext, TreeContext, UserContext};
 
@@ -60,7 +77,7 @@ use rustls_pki_types::PrivateKeyDer;
 pub const API_VERSION: &str = "0.1.0";
 
 mod private {
-    pub trait Scope: Copy + Clone {}
+    pub trait Scope: Copy + Clone + Send + Sync {}
 }
 
 pub trait Scope: private::Scope {}
@@ -101,11 +118,58 @@ pub mod scope {
     impl Scope for Unknown {}
 }
 
+/// Maximum total size of paths, `ETag`s and contents held by [Validators].
+const MAX_VALIDATORS_SIZE: usize = 8 * 1024 * 1024;
+
+/// `ETag`s and contents of previously fetched representations, keyed by path, which are held by
+/// clients without a [Cache]. Least recently inserted ones are evicted once their total size
+/// exceeds [MAX_VALIDATORS_SIZE].
+#[derive(Debug, Default)]
+struct Validators {
+    entries: HashMap<String, (String, Vec<u8>)>,
+    order: VecDeque<String>,
+    size: usize,
+}
+
+impl Validators {
+    fn get(&self, path: &str) -> Option<(String, Vec<u8>)> {
+        self.entries.get(path).cloned()
+    }
+
+    fn insert(&mut self, path: String, etag: String, buf: Vec<u8>) {
+        if let Some((etag, buf)) = self.entries.remove(&path) {
+            self.size -= path.len() + etag.len() + buf.len();
+            self.order.retain(|p| p != &path);
+        }
+        let size = path.len() + etag.len() + buf.len();
+        if size > MAX_VALIDATORS_SIZE {
+            return;
+        }
+        self.size += size;
+        while self.size > MAX_VALIDATORS_SIZE {
+            let Some(evicted) = self.order.pop_front() else {
+                break;
+            };
+            if let Some((etag, buf)) = self.entries.remove(&evicted) {
+                self.size -= evicted.len() + etag.len() + buf.len();
+            }
+        }
+        self.order.push_back(path.clone());
+        _ = self.entries.insert(path, (etag, buf));
+    }
+}
+
 #[derive(Clone, Debug)]
 pub struct Client<S = scope::Root> {
     inner: ureq::Agent,
     root: Url,
     token: Option<String>,
+    validators: Arc<Mutex<Validators>>,
+    cache: Option<Arc<Cache>>,
+    progress: Option<Arc<dyn Progress>>,
+    limits: Limits,
+    parallelism: NonZeroUsize,
+    retry: RetryPolicy,
     scope: PhantomData<S>,
 }
 
@@ -123,6 +187,22 @@ impl<S: Scope> Client<S> {
             .parse()
             .context("failed to construct URL")
     }
+
+    /// Calls `send` until it succeeds or fails with an error, which must not be retried according
+    /// to the retry policy of the client. `send` must perform an idempotent request.
+    #[allow(clippy::result_large_err)]
+    fn retry<T>(&self, mut send: impl FnMut() -> Result<T, ureq::Error>) -> Result<T, ureq::Error> {
+        let mut retries = 0;
+        loop {
+            let err = match send() {
+                Ok(v) => return Ok(v),
+                Err(e) => e,
+            };
+            let delay = self.retry.delay(retries, &err).ok_or(err)?;
+            thread::sleep(delay);
+            retries += 1;
+        }
+    }
 }
 
 impl Client<scope::Root> {
@@ -160,6 +240,13 @@ pub struct ClientBuilder<S: Scope = scope::Root> {
     roots: Option<RootCertStore>,
     token: Option<String>,
     user_agent: Option<String>,
+    parallelism: NonZeroUsize,
+    timeout_connect: Option<Duration>,
+    timeout_read: Option<Duration>,
+    retry: RetryPolicy,
+    cache: Option<Arc<Cache>>,
+    progress: Option<Arc<dyn Progress>>,
+    limits: Limits,
     scope: PhantomData<S>,
 }
 
@@ -177,6 +264,13 @@ impl<S: Scope> Clone for ClientBuilder<S> {
             roots: self.roots.clone(),
             token: self.token.clone(),
             user_agent: self.user_agent.clone(),
+            parallelism: self.parallelism,
+            timeout_connect: self.timeout_connect,
+            timeout_read: self.timeout_read,
+            retry: self.retry,
+            cache: self.cache.clone(),
+            progress: self.progress.clone(),
+            limits: self.limits,
             scope: self.scope,
         }
     }
@@ -190,6 +284,13 @@ impl<S: Scope> ClientBuilder<S> {
             roots: None,
             token: None,
             user_agent: None,
+            parallelism: NonZeroUsize::MIN,
+            timeout_connect: None,
+            timeout_read: None,
+            retry: Default::default(),
+            cache: None,
+            progress: None,
+            limits: Default::default(),
             scope: PhantomData,
         }
     }
@@ -201,6 +302,59 @@ impl<S: Scope> ClientBuilder<S> {
         }
     }
 
+    /// Sets the maximum amount of tree nodes uploaded or downloaded concurrently, which
+    /// defaults to 1.
+    pub fn parallelism(self, parallelism: NonZeroUsize) -> Self {
+        Self {
+            parallelism,
+            ..self
+        }
+    }
+
+    /// Sets the timeout for establishing connections.
+    pub fn timeout_connect(self, timeout: Duration) -> Self {
+        Self {
+            timeout_connect: Some(timeout),
+            ..self
+        }
+    }
+
+    /// Sets the timeout for individual reads of responses.
+    pub fn timeout_read(self, timeout: Duration) -> Self {
+        Self {
+            timeout_read: Some(timeout),
+            ..self
+        }
+    }
+
+    /// Sets the policy for retrying idempotent requests, which defaults to
+    /// [RetryPolicy::default].
+    pub fn retry_policy(self, retry: RetryPolicy) -> Self {
+        Self { retry, ..self }
+    }
+
+    /// Sets the [Cache] consulted before downloading contents and used to persist revalidated
+    /// listings. Caches are only supported by blocking clients.
+    pub fn cache(self, cache: Cache) -> Self {
+        Self {
+            cache: Some(Arc::new(cache)),
+            ..self
+        }
+    }
+
+    /// Sets the response size limits of clients, which default to [Limits::default].
+    pub fn limits(self, limits: Limits) -> Self {
+        Self { limits, ..self }
+    }
+
+    /// Sets the observer notified about the progress of tree uploads and downloads.
+    pub fn progress(self, progress: Arc<dyn Progress>) -> Self {
+        Self {
+            progress: Some(progress),
+            ..self
+        }
+    }
+
     pub fn credentials(
         self,
         cert: Vec<CertificateDer<'static>>,
@@ -226,9 +380,10 @@ impl<S: Scope> ClientBuilder<S> {
         }
     }
 
-    pub fn build_scoped(self) -> Result<Client<S>> {
+    /// Returns the TLS configuration and `User-Agent` of clients built by the builder.
+    fn connection_config(&mut self) -> Result<(rustls::ClientConfig, String)> {
         let tls = rustls::ClientConfig::builder().with_root_certificates(
-            if let Some(roots) = self.roots {
+            if let Some(roots) = self.roots.take() {
                 roots
             } else {
                 RootCertStore {
@@ -236,34 +391,73 @@ impl<S: Scope> ClientBuilder<S> {
                 }
             },
         );
-        let tls = if let Some((cert, key)) = self.credentials {
+        let tls = if let Some((cert, key)) = self.credentials.take() {
             tls.with_client_auth_cert(cert, key)?
         } else {
             tls.with_no_client_auth()
         };
 
-        let user_agent = self.user_agent.unwrap_or_else(|| {
+        let user_agent = self.user_agent.take().unwrap_or_else(|| {
             format!("{}/{}", env!("CARGO_CRATE_NAME"), env!("CARGO_PKG_VERSION"))
         });
+        Ok((tls, user_agent))
+    }
 
+    pub fn build_scoped(mut self) -> Result<Client<S>> {
+        let (tls, user_agent) = self.connection_config()?;
+        let mut agent = ureq::AgentBuilder::new()
+            .tls_config(Arc::new(tls))
+            .user_agent(&user_agent);
+        if let Some(timeout) = self.timeout_connect {
+            agent = agent.timeout_connect(timeout);
+        }
+        if let Some(timeout) = self.timeout_read {
+            agent = agent.timeout_read(timeout);
+        }
         Ok(Client {
-            inner: ureq::AgentBuilder::new()
-                .tls_config(Arc::new(tls))
-                .user_agent(&user_agent)
-                .build(),
+            inner: agent.build(),
             root: self.url,
             token: self.token,
+            validators: Default::default(),
+            cache: self.cache,
+            progress: self.progress,
+            limits: self.limits,
+            parallelism: self.parallelism,
+            retry: self.retry,
             scope: self.scope,
         })
     }
+
+    /// Builds an asynchronous client, see [nonblocking::Client]. Fails if a [Cache] is set,
+    /// which asynchronous clients do not support.
+    #[cfg(feature = "async")]
+    pub fn build_async_scoped(mut self) -> Result<nonblocking::Client<S>> {
+        anyhow::ensure!(
+            self.cache.is_none(),
+            "caches are not supported by asynchronous clients"
+        );
+        let (tls, user_agent) = self.connection_config()?;
+        Ok(nonblocking::Client::new(Arc::new(tls), user_agent, self))
+    }
 }
 
 impl ClientBuilder<scope::Root> {
-    pub fn build(self) -> Result<Client<scope::Root>> {
-        let url = self
-            .url
+    /// Returns the URL of the API root of the server.
+    fn api_url(&self) -> Result<Url> {
+        self.url
             .join(&format!("api/v{API_VERSION}"))
-            .context("failed to construct URL")?;
+            .context("failed to construct URL")
+    }
+
+    pub fn build(self) -> Result<Client<scope::Root>> {
+        let url = self.api_url()?;
         Self { url, ..self }.build_scoped()
     }
+
+    /// Builds an asynchronous client, see [nonblocking::Client].
+    #[cfg(feature = "async")]
+    pub fn build_async(self) -> Result<nonblocking::Client<scope::Root>> {
+        let url = self.api_url()?;
+        Self { url, ..self }.build_async_scoped()
+    }
 }
@@ -0,0 +1,71 @@
+// SPDX-FileCopyrightText: 2022 Profian Inc. <opensource@profian.com>
+// SPDX-License-Identifier: Apache-2.0
+
+use super::Result;
+
+use std::fmt::{self, Display};
+
+/// Maximum sizes in bytes of responses accepted by the client for each type of entity.
+///
+/// Responses exceeding their limit are rejected with [LimitExceeded] before their contents are
+/// read.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct Limits {
+    /// Maximum size of user records
+    pub user: u64,
+
+    /// Maximum size of repository configs
+    pub repository: u64,
+
+    /// Maximum size of tag entries, including tag resolutions
+    pub tag: u64,
+
+    /// Maximum size of listings of repositories and tags or a single page of them
+    pub listing: u64,
+
+    /// Maximum size of tree nodes downloaded by checkouts and archive downloads
+    pub node: u64,
+}
+
+impl Default for Limits {
+    fn default() -> Self {
+        Self {
+            user: 64 * 1024,
+            repository: 64 * 1024,
+            tag: 1024 * 1024,
+            listing: 16 * 1024 * 1024,
+            node: u64::MAX,
+        }
+    }
+}
+
+/// Error returned if the size of a response exceeds the limit of the client.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct LimitExceeded {
+    /// Size of the response
+    pub size: u64,
+
+    /// Limit exceeded by the response
+    pub limit: u64,
+}
+
+impl Display for LimitExceeded {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "response size of `{}` exceeds the limit of `{}`",
+            self.size, self.limit
+        )
+    }
+}
+
+impl std::error::Error for LimitExceeded {}
+
+/// Fails with [LimitExceeded] if `size` exceeds `limit`.
+pub(super) fn check_limit(size: u64, limit: u64) -> Result<()> {
+    if size > limit {
+        Err(LimitExceeded { size, limit }.into())
+    } else {
+        Ok(())
+    }
+}
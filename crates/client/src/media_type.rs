@@ -0,0 +1,58 @@
+// SPDX-FileCopyrightText: 2022 Profian Inc. <opensource@profian.com>
+// SPDX-License-Identifier: Apache-2.0
+
+use super::Result;
+
+use std::fmt::{self, Display};
+
+use drawbridge_jose::jws::Jws;
+use drawbridge_jose::MediaTyped;
+use drawbridge_type::{TreeDirectory, TreeEntry};
+
+use mime::Mime;
+
+/// Media type of JSON-encoded users, repositories and listings
+pub(super) const JSON_TYPES: &[&str] = &["application/json"];
+
+/// Media types of unsigned and signed tag entries
+pub(super) const TAG_TYPES: &[&str] = &[TreeEntry::<()>::TYPE, Jws::TYPE];
+
+/// Media type of tree directories
+pub(super) const DIRECTORY_TYPES: &[&str] = &[TreeDirectory::<()>::TYPE];
+
+/// Error returned if the media type of a response is not one the client accepts.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct UnexpectedMediaType {
+    /// Media type of the response
+    pub mime: Mime,
+
+    /// Media types accepted by the client
+    pub expected: Vec<String>,
+}
+
+impl Display for UnexpectedMediaType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "unexpected media type `{}`, expected one of `{}`",
+            self.mime,
+            self.expected.join("`, `")
+        )
+    }
+}
+
+impl std::error::Error for UnexpectedMediaType {}
+
+/// Fails with [UnexpectedMediaType] if `accept` is not empty and does not contain the essence of
+/// `mime`.
+pub(super) fn check_media_type(mime: &Mime, accept: &[&str]) -> Result<()> {
+    if accept.is_empty() || accept.contains(&mime.essence_str()) {
+        Ok(())
+    } else {
+        Err(UnexpectedMediaType {
+            mime: mime.clone(),
+            expected: accept.iter().map(ToString::to_string).collect(),
+        }
+        .into())
+    }
+}
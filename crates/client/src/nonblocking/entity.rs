@@ -0,0 +1,707 @@
+// SPDX-FileCopyrightText: 2022 Profian Inc. <opensource@profian.com>
+// SPDX-License-Identifier: Apache-2.0
+
+use super::{scope, Client, Result, Scope, Url};
+use crate::{
+    authorization, check_limit, create_headers, fetch_headers, meta_matches, parse_get_meta,
+    parse_header, parse_meta, parse_next_page, status_error, Limits, Progress, ProgressReader,
+    ResponseHeaders,
+};
+
+use std::future::Future;
+use std::io::{ErrorKind, SeekFrom};
+use std::marker::PhantomData;
+use std::num::NonZeroUsize;
+use std::str::FromStr;
+use std::sync::{Arc, PoisonError};
+
+use drawbridge_type::digest::{Algorithms, ContentDigest};
+use drawbridge_type::{Meta, TreePath};
+
+use anyhow::{anyhow, bail, ensure, Context};
+use async_std::fs::File;
+use futures::io::{copy, sink, BufReader};
+use futures::{AsyncRead, AsyncReadExt, AsyncSeekExt, AsyncWrite};
+use http_types::headers::{CONTENT_RANGE, CONTENT_TYPE, ETAG, IF_MATCH};
+use http_types::headers::{IF_NONE_MATCH, IF_RANGE};
+use http_types::{Body, Method, Request, Response, StatusCode};
+use mime::Mime;
+use ureq::serde::de::DeserializeOwned;
+use ureq::serde::Serialize;
+
+impl ResponseHeaders for Response {
+    fn header_value(&self, name: &str) -> Option<&str> {
+        self.header(name).map(|v| v.last().as_str())
+    }
+
+    fn header_values(&self, name: &str) -> Vec<&str> {
+        self.header(name)
+            .into_iter()
+            .flat_map(|v| v.iter())
+            .map(|v| v.as_str())
+            .collect()
+    }
+}
+
+/// Returns the error describing the response `res` with an error status code.
+async fn parse_error(mut res: Response) -> anyhow::Error {
+    let code = res.status().into();
+    status_error(code, res.body_string().await.ok())
+}
+
+/// Returns `res` if its status code is not an error status code and the error describing it
+/// otherwise.
+async fn check_status(res: Response) -> Result<Response> {
+    if res.status().is_client_error() || res.status().is_server_error() {
+        Err(parse_error(res).await)
+    } else {
+        Ok(res)
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct Entity<'a, C: Scope, E: Scope> {
+    client: &'a Client<C>,
+    path: String,
+    node: Option<TreePath>,
+    accept: &'static [&'static str],
+    phantom: PhantomData<E>,
+}
+
+impl<'a, C: Scope> Entity<'a, C, C> {
+    pub fn new(client: &'a Client<C>) -> Self {
+        Self {
+            client,
+            path: Default::default(),
+            node: None,
+            accept: &[],
+            phantom: PhantomData,
+        }
+    }
+}
+
+impl<'a> Entity<'a, scope::Unknown, scope::Unknown> {
+    /// Changes the scope of the entity.
+    pub fn scope<O: Scope>(self) -> Entity<'a, scope::Unknown, O> {
+        Entity {
+            client: self.client,
+            path: self.path,
+            node: self.node,
+            accept: self.accept,
+            phantom: PhantomData,
+        }
+    }
+}
+
+impl<'a, C: Scope, E: Scope> Entity<'a, C, E> {
+    /// Returns a child [Entity] rooted at `path`.
+    pub fn child<O: Scope>(&self, path: &str) -> Entity<'a, C, O> {
+        Entity {
+            client: self.client,
+            path: format!("{}/{}", self.path, path),
+            node: None,
+            accept: &[],
+            phantom: PhantomData,
+        }
+    }
+
+    /// Restricts the media types of contents of the entity accepted by the client to `accept`,
+    /// see [crate::Entity].
+    pub(super) fn accept(self, accept: &'static [&'static str]) -> Self {
+        Self { accept, ..self }
+    }
+
+    /// Marks the entity as the tree node at `path`, whose transfers are reported to the progress
+    /// observer of the client.
+    pub(super) fn tree_node(self, path: &TreePath) -> Self {
+        Self {
+            node: Some(path.clone()),
+            ..self
+        }
+    }
+
+    /// Returns the progress observer of the client, if any.
+    pub(super) fn progress(&self) -> Option<&Arc<dyn Progress>> {
+        self.client.progress.as_ref()
+    }
+
+    /// Wraps `rdr` to report bytes read from it as transferred bytes of the tree node.
+    fn track<R>(&self, rdr: R) -> ProgressReader<R> {
+        ProgressReader {
+            progress: self.progress().cloned().zip(self.node.clone()),
+            rdr,
+        }
+    }
+
+    /// Notifies the progress observer about the start of the transfer of the tree node described
+    /// by `meta`, awaits `f` transferring it and notifies the observer about its completion.
+    pub(super) async fn track_node<T>(
+        &self,
+        meta: &Meta,
+        f: impl Future<Output = Result<T>>,
+    ) -> Result<T> {
+        let Some((progress, path)) = self.progress().zip(self.node.as_ref()) else {
+            return f.await;
+        };
+        progress.start(path, meta);
+        let v = f.await?;
+        progress.finish(path);
+        Ok(v)
+    }
+
+    /// Returns the response size limits of the client.
+    pub(super) fn limits(&self) -> Limits {
+        self.client.limits
+    }
+
+    /// Returns the maximum amount of tree nodes processed concurrently by the client.
+    pub(super) fn parallelism(&self) -> NonZeroUsize {
+        self.client.parallelism
+    }
+
+    /// Returns the URL of the entity.
+    pub(super) fn url(&self) -> Result<Url> {
+        self.client.url(&self.path)
+    }
+
+    fn request(&self, method: Method, url: Url, headers: &[(&str, String)]) -> Request {
+        let mut req = Request::new(method, url);
+        for (name, value) in headers {
+            _ = req.insert_header(*name, value.as_str());
+        }
+        req
+    }
+
+    fn authorized_request(&self, method: Method) -> Result<Request> {
+        let token = self.client.token.as_ref().ok_or_else(|| {
+            anyhow!("endpoint requires authorization, but no token was configured")
+        })?;
+        Ok(self.request(method, self.url()?, &[authorization(token)]))
+    }
+
+    fn create_request(&self, hash: &ContentDigest, mime: &Mime) -> Result<Request> {
+        let mut req = self.authorized_request(Method::Put)?;
+        for (name, value) in create_headers(hash, mime) {
+            _ = req.insert_header(name, value);
+        }
+        Ok(req)
+    }
+
+    fn fetch_request(&self, method: Method, url: Url) -> Request {
+        let headers = fetch_headers(self.client.token.as_deref(), self.accept);
+        self.request(method, url, &headers)
+    }
+
+    fn get_request(&self) -> Result<Request> {
+        Ok(self.fetch_request(Method::Get, self.url()?))
+    }
+
+    fn head_request(&self) -> Result<Request> {
+        Ok(self.fetch_request(Method::Head, self.url()?))
+    }
+
+    /// Creates the entity with contents described by `meta` sending requests with bodies built
+    /// by `body`, which are retried on transient failures. Returns `true` if the entity was
+    /// created and `false` if it existed already.
+    ///
+    /// An attempt failing after the server created the entity causes the retried request to
+    /// conflict with the entity, so conflicts after retries are reported as created entity, if
+    /// the entity exists with contents matching `meta`.
+    async fn create_retried(
+        &self,
+        meta: &Meta,
+        mut body: impl FnMut() -> Result<Body>,
+    ) -> Result<bool> {
+        let mut attempts = 0;
+        let res = self
+            .client
+            .call(|| {
+                attempts += 1;
+                let mut req = self.create_request(&meta.hash, &meta.mime)?;
+                req.set_body(body()?);
+                Ok(req)
+            })
+            .await?;
+        match res.status() {
+            StatusCode::Created => Ok(true),
+            StatusCode::Ok => Ok(false),
+            StatusCode::Conflict if attempts > 1 => match self.try_head().await? {
+                Some(existing) if meta_matches(&existing, meta) => Ok(true),
+                _ => Err(parse_error(res).await),
+            },
+            status if status.is_client_error() || status.is_server_error() => {
+                Err(parse_error(res).await)
+            }
+            status => bail!("unexpected status code: {status}"),
+        }
+    }
+
+    /// Sends an authorized `PUT` request without a body and decodes the JSON response.
+    pub(super) async fn put_empty_json<T: DeserializeOwned>(&self) -> Result<T> {
+        let res = self
+            .client
+            .call(|| {
+                let mut req = self.authorized_request(Method::Put)?;
+                req.set_body(Body::empty());
+                Ok(req)
+            })
+            .await?;
+        let mut res = check_status(res).await?;
+        match res.status() {
+            StatusCode::Ok => res
+                .body_json()
+                .await
+                .map_err(|e| anyhow!(e.into_inner()).context("failed to decode JSON")),
+            status => bail!("unexpected status code: {status}"),
+        }
+    }
+
+    /// Deletes the entity.
+    pub(super) async fn delete(&self) -> Result<()> {
+        self.delete_with(&[]).await
+    }
+
+    /// Deletes the entity sending additional `headers`.
+    pub(super) async fn delete_with(&self, headers: &[(&str, &str)]) -> Result<()> {
+        let mut req = self.authorized_request(Method::Delete)?;
+        for (name, value) in headers {
+            _ = req.insert_header(*name, *value);
+        }
+        let res = check_status(self.client.send(req).await?).await?;
+        match res.status() {
+            StatusCode::NoContent => Ok(()),
+            status => bail!("unexpected status code: {status}"),
+        }
+    }
+
+    pub(super) async fn create_bytes(&self, mime: &Mime, data: impl AsRef<[u8]>) -> Result<bool> {
+        let data = data.as_ref();
+        let (n, hash) = Algorithms::default()
+            .read_sync(data)
+            .context("failed to compute content digest")?;
+        ensure!(
+            n == data.len() as u64,
+            "invalid amount of bytes read, expected {}, read {n}",
+            data.len(),
+        );
+        let meta = Meta {
+            hash,
+            size: n,
+            mime: mime.clone(),
+        };
+        self.create_retried(&meta, || Ok(Body::from_bytes(data.to_vec())))
+            .await
+    }
+
+    pub(super) async fn create_json(&self, mime: &Mime, val: &impl Serialize) -> Result<bool> {
+        let buf = serde_json::to_vec(val).context("failed to encode value to JSON")?;
+        self.create_bytes(mime, buf).await
+    }
+
+    /// Replaces the contents of the existing entity by `val` encoded as JSON.
+    pub(super) async fn update_json(&self, mime: &Mime, val: &impl Serialize) -> Result<()> {
+        let buf = serde_json::to_vec(val).context("failed to encode value to JSON")?;
+        let (n, hash) = Algorithms::default()
+            .read_sync(&buf[..])
+            .context("failed to compute content digest")?;
+        ensure!(
+            n == buf.len() as u64,
+            "invalid amount of bytes read, expected {}, read {n}",
+            buf.len(),
+        );
+        let res = self
+            .client
+            .call(|| {
+                let mut req = self.create_request(&hash, mime)?;
+                _ = req.insert_header(IF_MATCH, "*");
+                req.set_body(Body::from_bytes(buf.clone()));
+                Ok(req)
+            })
+            .await?;
+        let res = check_status(res).await?;
+        match res.status() {
+            StatusCode::Ok => Ok(()),
+            status => bail!("unexpected status code: {status}"),
+        }
+    }
+
+    /// Creates the entity with contents described by `meta` streamed from `rdr`, which are
+    /// verified by the server.
+    pub(super) async fn create_from(
+        &self,
+        meta @ Meta { hash, mime, .. }: &Meta,
+        rdr: impl 'static + Send + Sync + Unpin + AsyncRead,
+    ) -> Result<bool> {
+        let mut req = self.create_request(hash, mime)?;
+        req.set_body(self.body_from(meta, rdr)?);
+        let res = check_status(self.client.send(req).await?).await?;
+        match res.status() {
+            StatusCode::Created => Ok(true),
+            StatusCode::Ok => Ok(false),
+            status => bail!("unexpected status code: {status}"),
+        }
+    }
+
+    /// Like [Self::create_from], but retries transient failures streaming contents from readers
+    /// opened by `open`.
+    pub(super) async fn create_from_retried<R>(
+        &self,
+        meta: &Meta,
+        mut open: impl FnMut() -> Result<R>,
+    ) -> Result<bool>
+    where
+        R: 'static + Send + Sync + Unpin + AsyncRead,
+    {
+        self.create_retried(meta, || self.body_from(meta, open()?))
+            .await
+    }
+
+    /// Returns a request body holding contents described by `meta` streamed from `rdr`, which
+    /// are reported to the progress observer of the client.
+    fn body_from(
+        &self,
+        Meta { size, .. }: &Meta,
+        rdr: impl 'static + Send + Sync + Unpin + AsyncRead,
+    ) -> Result<Body> {
+        let len = (*size)
+            .try_into()
+            .context("failed to convert u64 to usize")?;
+        let rdr = self.track(rdr.take(*size));
+        Ok(Body::from_reader(BufReader::new(rdr), Some(len)))
+    }
+
+    /// Creates the entity with contents of media type `mime` read from `rdr`, which are verified
+    /// by the server, see [crate::Entity].
+    pub(super) async fn create_stream<F>(
+        &self,
+        mime: &Mime,
+        rdr: impl 'static + Send + Sync + Unpin + AsyncRead,
+        supported: impl FnOnce() -> F,
+    ) -> Result<Option<bool>>
+    where
+        F: Future<Output = Result<bool>>,
+    {
+        let mut req = self.authorized_request(Method::Put)?;
+        _ = req.insert_header(CONTENT_TYPE, mime.as_ref());
+        req.set_body(Body::from_reader(BufReader::new(rdr), None));
+        let res = self.client.send(req).await?;
+        match res.status() {
+            StatusCode::Created => Ok(Some(true)),
+            StatusCode::Ok => Ok(Some(false)),
+            StatusCode::MethodNotAllowed => Ok(None),
+            StatusCode::NotFound => {
+                if supported().await? {
+                    Ok(None)
+                } else {
+                    Err(parse_error(res).await)
+                }
+            }
+            status if status.is_client_error() || status.is_server_error() => {
+                Err(parse_error(res).await)
+            }
+            status => bail!("unexpected status code: {status}"),
+        }
+    }
+
+    /// Creates the entity referring to contents described by `meta` already stored on the
+    /// server, without uploading them.
+    pub(super) async fn create_from_blob(&self, meta: &Meta) -> Result<bool> {
+        self.create_retried(meta, || Ok(Body::empty())).await
+    }
+
+    /// Returns `true` if the server holds contents with `hash` at the entity.
+    pub(super) async fn has_digest(&self, hash: &ContentDigest) -> Result<bool> {
+        let res = self
+            .client
+            .call(|| {
+                let mut req = self.head_request()?;
+                _ = req.insert_header("Content-Digest", hash.to_string());
+                Ok(req)
+            })
+            .await
+            .context("HEAD request failed")?;
+        match res.status() {
+            StatusCode::Ok => Ok(true),
+            StatusCode::NotFound => Ok(false),
+            status if status.is_client_error() || status.is_server_error() => {
+                Err(parse_error(res).await).context("HEAD request failed")
+            }
+            status => bail!("unexpected status code: {status}"),
+        }
+    }
+
+    /// Sends a `HEAD` request for the entity. Returns the response or `None` if the entity does
+    /// not exist.
+    async fn try_head_response(&self) -> Result<Option<(Meta, Response)>> {
+        let res = self
+            .client
+            .call(|| self.head_request())
+            .await
+            .context("HEAD request failed")?;
+        match res.status() {
+            StatusCode::Ok => Ok(Some((parse_meta(&res)?, res))),
+            StatusCode::NotFound => Ok(None),
+            status if status.is_client_error() || status.is_server_error() => {
+                Err(parse_error(res).await).context("HEAD request failed")
+            }
+            status => bail!("unexpected status code: {status}"),
+        }
+    }
+
+    async fn head_response(&self) -> Result<(Meta, Response)> {
+        self.try_head_response()
+            .await?
+            .ok_or_else(|| status_error(StatusCode::NotFound.into(), None))
+            .context("HEAD request failed")
+    }
+
+    /// Returns metadata of the entity without fetching its contents.
+    pub async fn head(&self) -> Result<Meta> {
+        self.head_response().await.map(|(meta, _)| meta)
+    }
+
+    /// Returns metadata of the entity without fetching its contents or `None` if it does not
+    /// exist.
+    pub(super) async fn try_head(&self) -> Result<Option<Meta>> {
+        Ok(self.try_head_response().await?.map(|(meta, _)| meta))
+    }
+
+    /// Creates the entity by awaiting `create`, unless it already exists with contents matching
+    /// `meta`. Returns `true` if the entity was created and `false` if the existing one was
+    /// reused. Fails if the entity exists with different contents.
+    pub(super) async fn create_or_reuse(
+        &self,
+        meta: &Meta,
+        create: impl Future<Output = Result<bool>>,
+    ) -> Result<bool> {
+        match self.try_head().await? {
+            None => create.await,
+            Some(existing) if meta_matches(&existing, meta) => Ok(false),
+            Some(_) => bail!("entity exists with different contents"),
+        }
+    }
+
+    /// Returns the value of header `name` of the response to a HEAD request for the entity.
+    pub(super) async fn head_header<T>(&self, name: &str) -> Result<T>
+    where
+        T: FromStr,
+        T::Err: 'static + Sync + Send + std::error::Error,
+    {
+        let (_, res) = self.head_response().await?;
+        parse_header(&res, name)
+    }
+
+    /// Fetches contents of the entity, which are verified against their digest while reading.
+    /// Bytes read from tree nodes are reported to the progress observer of the client.
+    pub async fn get(&self, limit: u64) -> Result<(Meta, impl Send + Sync + Unpin + AsyncRead)> {
+        let res = self
+            .client
+            .call(|| self.get_request())
+            .await
+            .context("GET request failed")?;
+        let mut res = check_status(res).await.context("GET request failed")?;
+        let meta = parse_get_meta(&res, limit, self.accept)?;
+        match res.status() {
+            StatusCode::Ok => {
+                let rdr = meta.hash.clone().verifier(res.take_body().take(meta.size));
+                Ok((meta, self.track(rdr)))
+            }
+            status => bail!("unexpected status code: {status}"),
+        }
+    }
+
+    pub async fn get_to(&self, limit: u64, dst: &mut (impl Unpin + AsyncWrite)) -> Result<Meta> {
+        let (meta @ Meta { size, .. }, rdr) = self.get(limit).await?;
+        let n = copy(rdr, dst).await?;
+        ensure!(
+            n == size,
+            "invalid amount of bytes read, expected {size}, read {n}"
+        );
+        Ok(meta)
+    }
+
+    /// Downloads the contents of the entity into `dst`, resuming from the end of any contents
+    /// `dst` already holds, and verifies the digest of the complete contents.
+    pub async fn resume_to(&self, limit: u64, dst: &mut File) -> Result<Meta> {
+        let (meta @ Meta { size, .. }, head) = self.head_response().await?;
+        check_limit(size, limit)?;
+        let offset = dst.seek(SeekFrom::End(0)).await?;
+        ensure!(
+            offset <= size,
+            "destination holds more data than the entity, expected at most {size}, got {offset}"
+        );
+        if offset < size {
+            let etag = head.header(ETAG).map(|v| v.last().as_str().to_string());
+            let res = self
+                .client
+                .call(|| {
+                    let mut req = self.get_request()?;
+                    _ = req.insert_header("Range", format!("bytes={offset}-"));
+                    if let Some(ref etag) = etag {
+                        _ = req.insert_header(IF_RANGE, etag.as_str());
+                    }
+                    Ok(req)
+                })
+                .await
+                .context("GET request failed")?;
+            let mut res = check_status(res).await.context("GET request failed")?;
+            let offset = match res.status() {
+                StatusCode::PartialContent => {
+                    let range = res
+                        .header(CONTENT_RANGE)
+                        .map(|v| v.last().as_str())
+                        .ok_or_else(|| anyhow!("missing `{CONTENT_RANGE}` header"))?;
+                    ensure!(
+                        range == format!("bytes {offset}-{}/{size}", size - 1),
+                        "unexpected content range `{range}`"
+                    );
+                    offset
+                }
+                StatusCode::Ok => {
+                    dst.set_len(0).await?;
+                    _ = dst.seek(SeekFrom::Start(0)).await?;
+                    0
+                }
+                status => bail!("unexpected status code: {status}"),
+            };
+            _ = copy(res.take_body().take(size - offset), dst).await?;
+        }
+
+        _ = dst.seek(SeekFrom::Start(0)).await?;
+        let n = match copy(
+            meta.hash.clone().verifier((&mut *dst).take(size + 1)),
+            &mut sink(),
+        )
+        .await
+        {
+            Err(e) if e.kind() == ErrorKind::InvalidData => bail!("content digest mismatch"),
+            res => res?,
+        };
+        ensure!(
+            n == size,
+            "invalid amount of bytes read, expected {size}, read {n}"
+        );
+        Ok(meta)
+    }
+
+    pub async fn get_json<T: DeserializeOwned>(&self, limit: u64) -> Result<(Meta, T)> {
+        let (meta, buf) = self.get_bytes(limit).await?;
+        let v = serde_json::from_slice(&buf).context("failed to decode JSON")?;
+        Ok((meta, v))
+    }
+
+    /// Like [Self::get_json], but revalidates contents fetched by a previous call using their
+    /// `ETag`, in which case the contents are not transferred again if they did not change.
+    /// Validators are held in a bounded amount of memory.
+    pub async fn get_json_cached<T: DeserializeOwned>(&self, limit: u64) -> Result<T> {
+        let cached = self
+            .client
+            .validators
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .get(&self.path);
+        let res = self
+            .client
+            .call(|| {
+                let mut req = self.get_request()?;
+                if let Some((ref etag, _)) = cached {
+                    _ = req.insert_header(IF_NONE_MATCH, etag.as_str());
+                }
+                Ok(req)
+            })
+            .await
+            .context("GET request failed")?;
+        let mut res = check_status(res).await.context("GET request failed")?;
+        let buf = match (res.status(), cached) {
+            (StatusCode::NotModified, Some((_, buf))) => buf,
+            (StatusCode::Ok, _) => {
+                let etag = res.header(ETAG).map(|v| v.last().as_str().to_string());
+                let meta = parse_get_meta(&res, limit, self.accept)?;
+                let buf = read_verified(&meta, res.take_body()).await?;
+                if let Some(etag) = etag {
+                    self.client
+                        .validators
+                        .lock()
+                        .unwrap_or_else(PoisonError::into_inner)
+                        .insert(self.path.clone(), etag, buf.clone());
+                }
+                buf
+            }
+            (status, _) => bail!("unexpected status code: {status}"),
+        };
+        serde_json::from_slice(&buf).context("failed to decode JSON")
+    }
+
+    /// Fetches contents at `url`, which are streamed without a known length or digest, and
+    /// returns their media type and a reader.
+    pub(super) async fn get_stream(
+        &self,
+        url: &Url,
+    ) -> Result<(Mime, impl Send + Sync + Unpin + AsyncRead)> {
+        let res = self
+            .client
+            .call(|| Ok(self.fetch_request(Method::Get, url.clone())))
+            .await
+            .context("GET request failed")?;
+        let mut res = check_status(res).await.context("GET request failed")?;
+        match res.status() {
+            StatusCode::Ok => Ok((parse_header(&res, CONTENT_TYPE.as_str())?, res.take_body())),
+            status => bail!("unexpected status code: {status}"),
+        }
+    }
+
+    /// Fetches a page of a JSON-encoded listing at `url` and returns it along with the URL of the
+    /// next page, which the server advertises in a `Link` header.
+    pub(super) async fn get_json_page<T: DeserializeOwned>(
+        &self,
+        url: &Url,
+        limit: u64,
+    ) -> Result<(T, Option<Url>)> {
+        let res = self
+            .client
+            .call(|| Ok(self.fetch_request(Method::Get, url.clone())))
+            .await
+            .context("GET request failed")?;
+        let mut res = check_status(res).await.context("GET request failed")?;
+        let next = parse_next_page(&res, url)?;
+        let meta = parse_get_meta(&res, limit, self.accept)?;
+        let buf = match res.status() {
+            StatusCode::Ok => read_verified(&meta, res.take_body()).await?,
+            status => bail!("unexpected status code: {status}"),
+        };
+        let v = serde_json::from_slice(&buf).context("failed to decode JSON")?;
+        Ok((v, next))
+    }
+
+    pub async fn get_bytes(&self, limit: u64) -> Result<(Meta, Vec<u8>)> {
+        let (meta, rdr) = self.get(limit).await?;
+        let buf = read_to_end(&meta, rdr).await?;
+        Ok((meta, buf))
+    }
+
+    pub async fn get_string(&self, limit: u64) -> Result<(Meta, String)> {
+        let (meta, buf) = self.get_bytes(limit).await?;
+        let s = String::from_utf8(buf).context("contents are not valid UTF-8")?;
+        Ok((meta, s))
+    }
+}
+
+/// Reads contents described by `meta` from `rdr` verifying them against their digest.
+async fn read_verified(meta: &Meta, rdr: impl Unpin + AsyncRead) -> Result<Vec<u8>> {
+    read_to_end(meta, meta.hash.clone().verifier(rdr.take(meta.size))).await
+}
+
+/// Reads contents described by `meta` from `rdr`.
+async fn read_to_end(Meta { size, .. }: &Meta, mut rdr: impl Unpin + AsyncRead) -> Result<Vec<u8>> {
+    let mut buf = Vec::with_capacity(
+        (*size)
+            .try_into()
+            .context("failed to convert u64 to usize")?,
+    );
+    let n = rdr.read_to_end(&mut buf).await.context("I/O failure")?;
+    ensure!(
+        n as u64 == *size,
+        "invalid amount of bytes read, expected {size}, read {n}"
+    );
+    Ok(buf)
+}
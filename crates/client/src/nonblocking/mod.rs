@@ -0,0 +1,268 @@
+// SPDX-FileCopyrightText: 2022 Profian Inc. <opensource@profian.com>
+// SPDX-License-Identifier: Apache-2.0
+
+//! Asynchronous variant of the client, which is available with the `async` feature.
+//!
+//! Clients are built by [ClientBuilder::build_async](super::ClientBuilder::build_async) and
+//! expose the same typed [Scope] structure and operations as the blocking
+//! [Client](super::Client). Requests are sent over HTTP/1.1 connections established per request,
+//! honoring the timeouts, retry policy, limits, parallelism and progress observer configured on
+//! the builder. Contents are streamed as [AsyncRead] and verified against
+//! their digests while reading.
+
+mod entity;
+mod repo;
+mod tag;
+mod tree;
+mod user;
+
+pub use entity::*;
+pub use repo::*;
+pub use tag::*;
+pub use tree::*;
+pub use user::*;
+
+use super::{scope, ClientBuilder, Limits, Progress, Result, RetryPolicy, Scope, Url, Validators};
+
+use std::future::Future;
+use std::io;
+use std::marker::PhantomData;
+use std::num::NonZeroUsize;
+use std::pin::Pin;
+use std::sync::{Arc, Mutex};
+use std::task::{Context as TaskContext, Poll};
+use std::time::Duration;
+
+use drawbridge_type::{RepositoryContext, TagContext, TreeContext, UserContext};
+
+use anyhow::{bail, Context};
+use async_std::net::TcpStream;
+use async_std::task::sleep;
+use futures::{AsyncRead, AsyncWrite};
+use futures_rustls::TlsConnector;
+use http_types::headers::USER_AGENT;
+use http_types::{Request, Response};
+use rustls_pki_types::ServerName;
+
+/// Awaits `f`, failing if it does not complete within `timeout`, if any.
+async fn within<T>(
+    timeout: Option<Duration>,
+    f: impl Future<Output = io::Result<T>>,
+) -> io::Result<T> {
+    match timeout {
+        Some(timeout) => async_std::io::timeout(timeout, f).await,
+        None => f.await,
+    }
+}
+
+/// Server, which requests are sent to.
+struct Endpoint {
+    host: String,
+    port: u16,
+    tls: bool,
+}
+
+impl Endpoint {
+    /// Returns the endpoint of the server at `url`.
+    fn new(url: &Url) -> Result<Self> {
+        let host = url.host_str().context("URL lacks a host")?.to_string();
+        let port = url.port_or_known_default().context("URL lacks a port")?;
+        let tls = match url.scheme() {
+            "https" => true,
+            "http" => false,
+            scheme => bail!("unsupported URL scheme `{scheme}`"),
+        };
+        Ok(Self { host, port, tls })
+    }
+}
+
+/// Stream failing reads, which do not complete within `timeout`, if any.
+struct Timeout<S> {
+    stream: S,
+    timeout: Option<Duration>,
+    timer: Option<Pin<Box<dyn Future<Output = ()> + Send + Sync>>>,
+}
+
+impl<S: AsyncRead + Unpin> AsyncRead for Timeout<S> {
+    fn poll_read(
+        mut self: Pin<&mut Self>,
+        cx: &mut TaskContext<'_>,
+        buf: &mut [u8],
+    ) -> Poll<io::Result<usize>> {
+        if let Poll::Ready(res) = Pin::new(&mut self.stream).poll_read(cx, buf) {
+            self.timer = None;
+            return Poll::Ready(res);
+        }
+        let Some(timeout) = self.timeout else {
+            return Poll::Pending;
+        };
+        let timer = self.timer.get_or_insert_with(|| Box::pin(sleep(timeout)));
+        if timer.as_mut().poll(cx).is_pending() {
+            return Poll::Pending;
+        }
+        self.timer = None;
+        Poll::Ready(Err(io::Error::new(
+            io::ErrorKind::TimedOut,
+            "timed out reading response",
+        )))
+    }
+}
+
+impl<S: AsyncWrite + Unpin> AsyncWrite for Timeout<S> {
+    fn poll_write(
+        mut self: Pin<&mut Self>,
+        cx: &mut TaskContext<'_>,
+        buf: &[u8],
+    ) -> Poll<io::Result<usize>> {
+        Pin::new(&mut self.stream).poll_write(cx, buf)
+    }
+
+    fn poll_flush(mut self: Pin<&mut Self>, cx: &mut TaskContext<'_>) -> Poll<io::Result<()>> {
+        Pin::new(&mut self.stream).poll_flush(cx)
+    }
+
+    fn poll_close(mut self: Pin<&mut Self>, cx: &mut TaskContext<'_>) -> Poll<io::Result<()>> {
+        Pin::new(&mut self.stream).poll_close(cx)
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct Client<S = scope::Root> {
+    tls: Arc<rustls::ClientConfig>,
+    root: Url,
+    token: Option<String>,
+    user_agent: String,
+    timeout_connect: Option<Duration>,
+    timeout_read: Option<Duration>,
+    validators: Arc<Mutex<Validators>>,
+    progress: Option<Arc<dyn Progress>>,
+    limits: Limits,
+    parallelism: NonZeroUsize,
+    retry: RetryPolicy,
+    scope: PhantomData<S>,
+}
+
+impl<S: Scope> Client<S> {
+    /// Returns a client configured by `builder` connecting using `tls` and sending `user_agent`.
+    pub(super) fn new(
+        tls: Arc<rustls::ClientConfig>,
+        user_agent: String,
+        builder: ClientBuilder<S>,
+    ) -> Self {
+        Self {
+            tls,
+            root: builder.url,
+            token: builder.token,
+            user_agent,
+            timeout_connect: builder.timeout_connect,
+            timeout_read: builder.timeout_read,
+            validators: Default::default(),
+            progress: builder.progress,
+            limits: builder.limits,
+            parallelism: builder.parallelism,
+            retry: builder.retry,
+            scope: PhantomData,
+        }
+    }
+
+    fn url(&self, path: &str) -> Result<Url> {
+        format!("{}{path}", self.root)
+            .parse()
+            .context("failed to construct URL")
+    }
+
+    /// Sends `req` to the server over a new connection.
+    async fn send(&self, req: Request) -> Result<Response> {
+        let endpoint = Endpoint::new(req.url())?;
+        // Connection futures are large, so they are boxed to keep futures of the client small
+        Box::pin(self.exchange(endpoint, req)).await
+    }
+
+    /// Sends `req` to the server at `endpoint` over a new connection.
+    async fn exchange(
+        &self,
+        Endpoint { host, port, tls }: Endpoint,
+        mut req: Request,
+    ) -> Result<Response> {
+        _ = req.insert_header(USER_AGENT, self.user_agent.as_str());
+        let tcp = within(
+            self.timeout_connect,
+            TcpStream::connect((host.as_str(), port)),
+        )
+        .await
+        .with_context(|| format!("failed to connect to `{host}:{port}`"))?;
+        if tls {
+            let name = ServerName::try_from(host).context("invalid server name")?;
+            let tls = within(
+                self.timeout_connect,
+                TlsConnector::from(self.tls.clone()).connect(name, tcp),
+            )
+            .await
+            .context("TLS handshake failed")?;
+            async_h1::connect(self.timeout(tls), req).await
+        } else {
+            async_h1::connect(self.timeout(tcp), req).await
+        }
+        .map_err(http_types::Error::into_inner)
+        .context("transport layer failure")
+    }
+
+    /// Wraps `stream` to fail reads exceeding the read timeout of the client.
+    fn timeout<T>(&self, stream: T) -> Timeout<T> {
+        Timeout {
+            stream,
+            timeout: self.timeout_read,
+            timer: None,
+        }
+    }
+
+    /// Sends requests built by `build` until the response must not be retried according to the
+    /// retry policy of the client or sending fails once retries are exhausted. `build` must
+    /// build an idempotent request.
+    async fn call(&self, mut build: impl FnMut() -> Result<Request>) -> Result<Response> {
+        let mut retries = 0;
+        loop {
+            let req = build()?;
+            let endpoint = Endpoint::new(req.url())?;
+            let delay = match Box::pin(self.exchange(endpoint, req)).await {
+                Ok(res) => {
+                    let retry_after = res.header("Retry-After").map(|v| v.last().as_str());
+                    match self
+                        .retry
+                        .delay_status(retries, res.status().into(), retry_after)
+                    {
+                        Some(delay) => delay,
+                        None => return Ok(res),
+                    }
+                }
+                Err(e) => self.retry.delay_transport(retries).ok_or(e)?,
+            };
+            sleep(delay).await;
+            retries += 1;
+        }
+    }
+}
+
+impl Client<scope::Root> {
+    pub fn user(&self, UserContext { name }: &UserContext) -> User<'_, scope::Root> {
+        User::new(Entity::new(self), name)
+    }
+
+    pub fn repository<'a>(
+        &'a self,
+        RepositoryContext { owner, name }: &'a RepositoryContext,
+    ) -> Repository<'a, scope::Root> {
+        self.user(owner).repository(name)
+    }
+
+    pub fn tag<'a>(
+        &'a self,
+        TagContext { repository, name }: &'a TagContext,
+    ) -> Tag<'a, scope::Root> {
+        self.repository(repository).tag(name)
+    }
+
+    pub fn tree<'a>(&'a self, TreeContext { tag, path }: &'a TreeContext) -> Node<'a, scope::Root> {
+        self.tag(tag).path(path)
+    }
+}
@@ -0,0 +1,144 @@
+// SPDX-FileCopyrightText: 2022 Profian Inc. <opensource@profian.com>
+// SPDX-License-Identifier: Apache-2.0
+
+use super::{scope, Entity, Result, Scope, Tag};
+use crate::JSON_TYPES;
+
+use std::ops::Deref;
+use std::vec;
+
+use drawbridge_type::digest::ContentDigest;
+use drawbridge_type::repository::CONFIRM_DELETE_HEADER;
+use drawbridge_type::{
+    RepositoryConfig, RepositoryContext, RepositoryName, TagName, TagQuery, TagResolution,
+};
+
+use futures::{stream, Stream};
+use mime::APPLICATION_JSON;
+use percent_encoding::{utf8_percent_encode, NON_ALPHANUMERIC};
+use semver::VersionReq;
+
+#[derive(Clone, Debug)]
+pub struct Repository<'a, S: Scope>(Entity<'a, S, scope::Repository>);
+
+impl<'a, S: Scope> Deref for Repository<'a, S> {
+    type Target = Entity<'a, S, scope::Repository>;
+
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
+impl<'a, S: Scope> From<Entity<'a, S, scope::Repository>> for Repository<'a, S> {
+    fn from(entity: Entity<'a, S, scope::Repository>) -> Self {
+        Self(entity)
+    }
+}
+
+impl<'a, S: Scope> Repository<'a, S> {
+    pub fn new(entity: Entity<'a, S, scope::User>, name: &RepositoryName) -> Repository<'a, S> {
+        Repository(entity.child(name.as_ref()))
+    }
+
+    pub async fn create(&self, conf: &RepositoryConfig) -> Result<bool> {
+        self.0.create_json(&APPLICATION_JSON, conf).await
+    }
+
+    /// Replaces the configuration of the existing repository.
+    pub async fn update(&self, conf: &RepositoryConfig) -> Result<()> {
+        self.0.update_json(&APPLICATION_JSON, conf).await
+    }
+
+    /// Deletes the repository along with all of its tags, confirming that `cx` identifies the
+    /// repository to delete.
+    pub async fn delete(&self, cx: &RepositoryContext) -> Result<()> {
+        self.0
+            .delete_with(&[(CONFIRM_DELETE_HEADER, &cx.to_string())])
+            .await
+    }
+
+    pub async fn get(&self) -> Result<RepositoryConfig> {
+        self.0
+            .clone()
+            .accept(JSON_TYPES)
+            .get_json(self.0.limits().repository)
+            .await
+            .map(|(_, v)| v)
+    }
+
+    /// Returns the tags of the repository, see [crate::Repository::tags].
+    pub async fn tags(&self) -> Result<Vec<TagName>> {
+        self.0
+            .child::<scope::Unknown>("_tag")
+            .accept(JSON_TYPES)
+            .get_json_cached(self.0.limits().listing)
+            .await
+    }
+
+    /// Returns a stream of the tags matching `query`, see [crate::Repository::query_tags].
+    /// Pages are fetched as the stream is polled.
+    pub fn query_tags(&self, query: &TagQuery) -> impl 'a + Stream<Item = Result<TagName>> {
+        let tags = self.0.child::<scope::Unknown>("_tag").accept(JSON_TYPES);
+        let next = tags.url().map(|mut url| {
+            _ = url.query_pairs_mut().extend_pairs(query.pairs());
+            url
+        });
+        let page: vec::IntoIter<TagName> = Default::default();
+        stream::unfold(
+            (tags, page, Some(next)),
+            |(tags, mut page, mut next)| async move {
+                loop {
+                    if let Some(name) = page.next() {
+                        return Some((Ok(name), (tags, page, next)));
+                    }
+                    let url = match next.take()? {
+                        Ok(url) => url,
+                        Err(e) => return Some((Err(e), (tags, page, None))),
+                    };
+                    let limit = tags.limits().listing;
+                    match tags.get_json_page::<Vec<_>>(&url, limit).await {
+                        Ok((names, url)) => {
+                            page = names.into_iter();
+                            next = url.map(Ok);
+                        }
+                        Err(e) => return Some((Err(e), (tags, page, None))),
+                    }
+                }
+            },
+        )
+    }
+
+    /// Returns the highest tag satisfying `req` along with its entry, omitting yanked and
+    /// pre-release tags.
+    pub async fn resolve(&self, req: &VersionReq) -> Result<TagResolution> {
+        self.resolve_request(req, false).await
+    }
+
+    /// Like [Self::resolve], but pre-release tags may satisfy `req` as well.
+    pub async fn resolve_pre_release(&self, req: &VersionReq) -> Result<TagResolution> {
+        self.resolve_request(req, true).await
+    }
+
+    async fn resolve_request(&self, req: &VersionReq, pre: bool) -> Result<TagResolution> {
+        let req = utf8_percent_encode(&req.to_string(), NON_ALPHANUMERIC).to_string();
+        self.0
+            .child::<scope::Unknown>(&format!("_resolve/{req}?pre={pre}"))
+            .accept(JSON_TYPES)
+            .get_json(self.0.limits().tag)
+            .await
+            .map(|(_, v)| v)
+    }
+
+    pub fn tag(&self, name: &TagName) -> Tag<'a, S> {
+        Tag::new(self.0.clone(), name)
+    }
+
+    /// Returns `true` if the repository holds contents with `hash`, see
+    /// [crate::Repository::has_blob].
+    pub async fn has_blob(&self, hash: &ContentDigest) -> Result<bool> {
+        self.0
+            .child::<scope::Unknown>("_blob")
+            .has_digest(hash)
+            .await
+    }
+}
@@ -0,0 +1,575 @@
+// SPDX-FileCopyrightText: 2022 Profian Inc. <opensource@profian.com>
+// SPDX-License-Identifier: Apache-2.0
+
+use super::{for_each_node, scope, Entity, Node, Repository, Result, Scope};
+use crate::{
+    archive_budget, archive_directory, archive_member_path, archive_member_size, archive_name,
+    check_limit, max_extended_header, meta_matches, verify_directory, Limits, Progress,
+    ProgressReader, DIRECTORY_TYPES, TAG_TYPES,
+};
+
+use std::collections::BTreeMap;
+use std::fs;
+use std::io::{self, ErrorKind, Seek};
+use std::ops::Deref;
+use std::path::{Path, PathBuf};
+use std::pin::Pin;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use std::task::{ready, Context as TaskContext, Poll};
+
+use drawbridge_jose::jws::Jws;
+use drawbridge_jose::MediaTyped;
+use drawbridge_type::tag::{ArchiveFormat, ARCHIVE_TYPE, MISSING_NODES_HEADER, SEALED_HEADER};
+use drawbridge_type::TreeContent::{Directory, File};
+use drawbridge_type::{Meta, TagEntry, TagName, TagYank, Tree, TreeDirectory, TreeEntry, TreePath};
+
+use anyhow::{bail, ensure, Context};
+use async_compression::futures::bufread::{GzipDecoder, ZstdDecoder};
+use async_std::task::spawn_blocking;
+use async_tar::{Archive, Builder, EntryType, Header};
+use futures::channel::mpsc;
+use futures::future::join;
+use futures::io::{copy, sink, BufReader, Cursor};
+use futures::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt, StreamExt, TryStreamExt};
+use ureq::serde::Serialize;
+
+/// Amount of archive chunks buffered between the archive writer and the upload.
+const ARCHIVE_BUFFER: usize = 16;
+
+/// Writer sending written chunks over a channel, whose receiver streams them as request body.
+struct ChannelWriter(mpsc::Sender<io::Result<Vec<u8>>>);
+
+impl AsyncWrite for ChannelWriter {
+    fn poll_write(
+        mut self: Pin<&mut Self>,
+        cx: &mut TaskContext<'_>,
+        buf: &[u8],
+    ) -> Poll<io::Result<usize>> {
+        ready!(self.0.poll_ready(cx)).map_err(|_| io::Error::from(ErrorKind::BrokenPipe))?;
+        self.0
+            .start_send(Ok(buf.to_vec()))
+            .map_err(|_| io::Error::from(ErrorKind::BrokenPipe))?;
+        Poll::Ready(Ok(buf.len()))
+    }
+
+    fn poll_flush(self: Pin<&mut Self>, _: &mut TaskContext<'_>) -> Poll<io::Result<()>> {
+        Poll::Ready(Ok(()))
+    }
+
+    fn poll_close(mut self: Pin<&mut Self>, _: &mut TaskContext<'_>) -> Poll<io::Result<()>> {
+        self.0.close_channel();
+        Poll::Ready(Ok(()))
+    }
+}
+
+/// Writes all nodes of `tree` to `wtr` as a tag tree archive, see [crate::Tag::create_archive].
+/// Written nodes are reported to `progress`.
+async fn write_archive(
+    tree: &Tree<fs::File>,
+    wtr: ChannelWriter,
+    progress: Option<Arc<dyn Progress>>,
+) -> Result<()> {
+    let mut archive = Builder::new(wtr);
+    if let Err(e) = append_archive(&mut archive, tree, progress).await {
+        // The builder finishes the archive when dropped, which must not wait for the upload
+        archive.get_mut().0.close_channel();
+        return Err(e);
+    }
+    archive
+        .into_inner()
+        .await
+        .context("failed to finish archive")?
+        .close()
+        .await
+        .context("failed to finish archive")
+}
+
+/// Appends all nodes of `tree` to `archive`, reporting them to `progress`.
+async fn append_archive(
+    archive: &mut Builder<ChannelWriter>,
+    tree: &Tree<fs::File>,
+    progress: Option<Arc<dyn Progress>>,
+) -> Result<()> {
+    for (path, TreeEntry { meta, content, .. }) in tree.iter() {
+        if let Some(ref progress) = progress {
+            progress.start(path, meta);
+        }
+        let name = archive_name(path);
+        let mut header = Header::new_gnu();
+        header.set_entry_type(EntryType::Regular);
+        header.set_mode(0o644);
+        header.set_size(meta.size);
+        let progress_node = progress.clone().map(|progress| (progress, path.clone()));
+        match content {
+            File(file) => {
+                let mut file = file.try_clone().context("failed to clone file handle")?;
+                file.rewind().context("failed to rewind file")?;
+                let rdr = ProgressReader {
+                    progress: progress_node,
+                    rdr: async_std::fs::File::from(file).take(meta.size),
+                };
+                archive.append_data(&mut header, &name, rdr).await
+            }
+            Directory(buf) => {
+                let rdr = ProgressReader {
+                    progress: progress_node,
+                    rdr: buf.as_slice(),
+                };
+                archive.append_data(&mut header, &name, rdr).await
+            }
+        }
+        .with_context(|| format!("failed to write `{name}` to archive"))?;
+        if let Some(ref progress) = progress {
+            progress.finish(path);
+        }
+    }
+    Ok(())
+}
+
+/// Reader failing once more bytes than the shared `budget` are read.
+struct BudgetReader<R> {
+    rdr: R,
+    budget: Arc<AtomicU64>,
+}
+
+impl<R: AsyncRead + Unpin> AsyncRead for BudgetReader<R> {
+    fn poll_read(
+        mut self: Pin<&mut Self>,
+        cx: &mut TaskContext<'_>,
+        buf: &mut [u8],
+    ) -> Poll<io::Result<usize>> {
+        let budget = self.budget.load(Ordering::Relaxed);
+        if budget == 0 && !buf.is_empty() {
+            return Poll::Ready(Err(io::Error::other(
+                "archive exceeds the size of the declared tree",
+            )));
+        }
+        let len = buf.len().min(budget.try_into().unwrap_or(usize::MAX));
+        let n = ready!(Pin::new(&mut self.rdr).poll_read(cx, &mut buf[..len]))?;
+        _ = self.budget.fetch_sub(n as u64, Ordering::Relaxed);
+        Poll::Ready(Ok(n))
+    }
+}
+
+/// Unpacks the tag tree archive read from `rdr` into `dest`, see [crate::Tag::download_archive].
+/// Returns the metadata of all unpacked nodes.
+async fn unpack_archive(
+    rdr: impl AsyncRead + Unpin,
+    root: Meta,
+    dest: &Path,
+    limits: Limits,
+) -> Result<BTreeMap<TreePath, Meta>> {
+    let max_header = max_extended_header(limits);
+    let budget = Arc::new(AtomicU64::new(archive_budget(&root)));
+    let mut expected = BTreeMap::from([(TreePath::ROOT, root)]);
+    let mut unpacked = BTreeMap::new();
+    let archive = Archive::new(BudgetReader {
+        rdr,
+        budget: budget.clone(),
+    });
+    // Extended headers are read by hand to bound their size, hence members are iterated raw
+    let mut entries = archive.entries_raw().context("failed to read archive")?;
+    let mut long_name = None;
+    let mut pax = None;
+    while let Some(entry) = entries.next().await {
+        let mut entry = entry.context("failed to read archive member")?;
+        let size = entry
+            .header()
+            .size()
+            .context("failed to read archive member size")?;
+        let ext = match entry.header().entry_type() {
+            EntryType::GNULongName => Some(&mut long_name),
+            EntryType::XHeader => Some(&mut pax),
+            _ => None,
+        };
+        if let Some(ext) = ext {
+            ensure!(
+                size <= max_header,
+                "archive member extended header exceeds {max_header} bytes"
+            );
+            let mut buf = vec![];
+            _ = entry
+                .read_to_end(&mut buf)
+                .await
+                .context("failed to read archive member extended header")?;
+            ensure!(
+                ext.replace(buf).is_none(),
+                "archive member has multiple extended headers of the same type"
+            );
+            continue;
+        }
+        let path = archive_member_path(long_name.take(), &entry.path_bytes())?;
+        let pax = pax.take();
+        let meta = expected
+            .remove(&path)
+            .with_context(|| format!("node `{path}` is not declared by its parent"))?;
+        check_limit(meta.size, limits.node)
+            .with_context(|| format!("node `{path}` is too large"))?;
+        let dst = dest.join(PathBuf::from(path.clone()));
+        match entry.header().entry_type() {
+            EntryType::Directory => {
+                ensure!(
+                    meta.mime.essence_str() == TreeDirectory::<()>::TYPE,
+                    "node `{path}` is a directory, but is not declared as one"
+                );
+                let buf = archive_directory(pax.as_deref(), &path)?;
+                for (name, TreeEntry { meta, .. }) in verify_directory(&path, &meta, &buf)? {
+                    let path = path.clone().into_iter().chain([name]).collect();
+                    let size = archive_member_size(&path, &meta);
+                    budget.store(
+                        budget.load(Ordering::Relaxed).saturating_add(size),
+                        Ordering::Relaxed,
+                    );
+                    _ = expected.insert(path, meta);
+                }
+                async_std::fs::create_dir(&dst)
+                    .await
+                    .with_context(|| format!("failed to create `{}`", dst.display()))?;
+            }
+            EntryType::Regular => {
+                ensure!(
+                    meta.mime.essence_str() != TreeDirectory::<()>::TYPE,
+                    "node `{path}` is a file, but is declared as a directory"
+                );
+                ensure!(
+                    size == meta.size,
+                    "file `{path}` size does not match its entry"
+                );
+                let mut file = async_std::fs::OpenOptions::new()
+                    .write(true)
+                    .create_new(true)
+                    .open(&dst)
+                    .await
+                    .with_context(|| format!("failed to create `{}`", dst.display()))?;
+                let n = copy(meta.hash.clone().verifier(&mut entry), &mut file)
+                    .await
+                    .with_context(|| format!("failed to unpack file `{path}`"))?;
+                file.flush()
+                    .await
+                    .with_context(|| format!("failed to write `{}`", dst.display()))?;
+                ensure!(
+                    n == meta.size,
+                    "file `{path}` size does not match its entry"
+                );
+            }
+            t => bail!("node `{path}` is stored as an unsupported archive member type {t:?}"),
+        }
+        _ = unpacked.insert(path, meta);
+    }
+    if let Some(path) = expected.keys().next() {
+        bail!("archive lacks node `{path}`")
+    }
+    Ok(unpacked)
+}
+
+/// Returns `true` if `path` holds a file with contents matching `meta`.
+async fn file_matches(path: &Path, meta: &Meta) -> Result<bool> {
+    let Ok(md) = async_std::fs::metadata(path).await else {
+        return Ok(false);
+    };
+    ensure!(md.is_file(), "`{}` is not a file", path.display());
+    if md.len() != meta.size {
+        return Ok(false);
+    }
+    let file = async_std::fs::File::open(path)
+        .await
+        .with_context(|| format!("failed to open `{}`", path.display()))?;
+    match copy(meta.hash.clone().verifier(file), &mut sink()).await {
+        Err(e) if e.kind() == ErrorKind::InvalidData => Ok(false),
+        res => res
+            .map(|_| true)
+            .with_context(|| format!("failed to read `{}`", path.display())),
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct Tag<'a, S: Scope>(Entity<'a, S, scope::Tag>, Repository<'a, S>);
+
+impl<'a, S: Scope> Deref for Tag<'a, S> {
+    type Target = Entity<'a, S, scope::Tag>;
+
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
+impl<'a, S: Scope> Tag<'a, S> {
+    pub fn new(entity: Entity<'a, S, scope::Repository>, name: &TagName) -> Self {
+        Tag(
+            entity.child(&format!("_tag/{name}")),
+            Repository::from(entity),
+        )
+    }
+
+    pub async fn create(&self, entry: &TagEntry<impl Serialize>) -> Result<bool> {
+        let mime = match entry {
+            TagEntry::Unsigned(..) => TreeEntry::<()>::TYPE,
+            TagEntry::Signed(..) => Jws::TYPE,
+        }
+        .parse()
+        .expect("failed to parse tag entry media type");
+        self.0.create_json(&mime, entry).await
+    }
+
+    /// Uploads all nodes of `tree` as a single archive, see [crate::Tag::create_archive].
+    pub async fn create_archive(&self, tree: &Tree<fs::File>) -> Result<Option<bool>> {
+        let mime = ARCHIVE_TYPE
+            .parse()
+            .expect("failed to parse archive media type");
+        let (tx, rx) = mpsc::channel(ARCHIVE_BUFFER);
+        let written = write_archive(tree, ChannelWriter(tx), self.0.progress().cloned());
+        let archive = self.0.child::<scope::Unknown>("archive");
+        let res = archive.create_stream(&mime, rx.into_async_read(), || async {
+            self.0.try_head().await.map(|meta| meta.is_some())
+        });
+        // Failures of the upload cause the writer to fail on the closed channel, so these are
+        // reported in favor of the writer failure.
+        match join(res, written).await {
+            (Ok(None), _) => Ok(None),
+            (Err(e), _) => Err(e),
+            (res, Ok(())) => res,
+            (_, Err(e)) => Err(e),
+        }
+    }
+
+    /// Creates the tag and uploads the tree at `path`, see
+    /// [crate::Tag::create_from_path_unsigned].
+    // TODO: Support signed tags
+    pub async fn create_from_path_unsigned(
+        &self,
+        path: impl AsRef<Path>,
+    ) -> Result<(bool, BTreeMap<TreePath, bool>)> {
+        let path = path.as_ref().to_path_buf();
+        // Hashing the local tree is blocking file system work
+        let tree = spawn_blocking(move || Tree::from_path_sync(path)).await?;
+        if let Some(progress) = self.0.progress() {
+            progress.total(tree.len(), tree.values().map(|entry| entry.meta.size).sum());
+        }
+        let root = tree.root();
+        let tag_created = match self.0.try_head().await? {
+            None => self.create(&TagEntry::Unsigned(root)).await?,
+            Some(_) => match self.get().await? {
+                TagEntry::Unsigned(TreeEntry { meta, .. }) if meta_matches(&meta, &root.meta) => {
+                    false
+                }
+                _ => bail!("tag exists with a different tree"),
+            },
+        };
+        if self.path(&TreePath::ROOT).try_head().await?.is_none() {
+            if let Some(created) = self.create_archive(&tree).await? {
+                let tree_created = tree.keys().map(|path| (path.clone(), created)).collect();
+                return Ok((tag_created, tree_created));
+            }
+        }
+        // Parents must be uploaded before their children, which are verified against them
+        let mut levels = BTreeMap::<_, Vec<_>>::new();
+        for (path, entry) in tree {
+            levels.entry(path.len()).or_default().push((path, entry));
+        }
+        let mut tree_created = BTreeMap::new();
+        for level in levels.into_values() {
+            tree_created.append(
+                &mut for_each_node(
+                    self.0.parallelism(),
+                    level,
+                    |path, TreeEntry { meta, content, .. }| async move {
+                        let node = self.path(&path);
+                        let create = async {
+                            match content {
+                                File(_) if meta.size > 0 && self.1.has_blob(&meta.hash).await? => {
+                                    node.create_from_blob(&meta).await
+                                }
+                                File(file) => {
+                                    node.create_from_retried(&meta, || {
+                                        let mut file = file
+                                            .try_clone()
+                                            .context("failed to clone file handle")?;
+                                        file.rewind().context("failed to rewind file")?;
+                                        Ok(async_std::fs::File::from(file))
+                                    })
+                                    .await
+                                }
+                                Directory(buf) => {
+                                    node.create_from_retried(&meta, || Ok(Cursor::new(buf.clone())))
+                                        .await
+                                }
+                            }
+                        };
+                        node.track_node(&meta, node.create_or_reuse(&meta, create))
+                            .await
+                    },
+                )
+                .await?,
+            );
+        }
+        Ok((tag_created, tree_created))
+    }
+
+    pub async fn get(&self) -> Result<TagEntry> {
+        self.0
+            .clone()
+            .accept(TAG_TYPES)
+            .get_json(self.0.limits().tag)
+            .await
+            .map(|(_, v)| v)
+    }
+
+    /// Downloads the tree of the tag as an archive in `format` and unpacks it into `dest`, see
+    /// [crate::Tag::download_archive].
+    // TODO: Support signed tags
+    pub async fn download_archive(
+        &self,
+        dest: impl AsRef<Path>,
+        format: ArchiveFormat,
+    ) -> Result<BTreeMap<TreePath, Meta>> {
+        let dest = dest.as_ref();
+        ensure!(!dest.exists(), "`{}` already exists", dest.display());
+
+        let root = match self.get().await? {
+            TagEntry::Unsigned(TreeEntry { meta, .. }) => meta,
+            TagEntry::Signed(_) => bail!("downloading trees of signed tags is not supported"),
+        };
+        let mut url = self.0.child::<scope::Unknown>("archive").url()?;
+        _ = url.query_pairs_mut().append_pair("format", format.as_str());
+        let (mime, rdr) = self.0.get_stream(&url).await?;
+        ensure!(
+            mime.essence_str() == format.media_type(),
+            "unexpected archive media type `{mime}`"
+        );
+        let rdr: Box<dyn AsyncRead + Send + Unpin> = match format {
+            ArchiveFormat::Tar => Box::new(rdr),
+            ArchiveFormat::TarGzip => Box::new(GzipDecoder::new(BufReader::new(rdr))),
+            ArchiveFormat::TarZstd => Box::new(ZstdDecoder::new(BufReader::new(rdr))),
+        };
+        unpack_archive(rdr, root, dest, self.0.limits())
+            .await
+            .inspect_err(|_| {
+                _ = fs::remove_dir_all(dest).or_else(|_| fs::remove_file(dest));
+            })
+    }
+
+    /// Checks out the tree of the tag into `dest`, see [crate::Tag::checkout].
+    // TODO: Support signed tags
+    pub async fn checkout(&self, dest: impl AsRef<Path>) -> Result<BTreeMap<TreePath, bool>> {
+        let dest = dest.as_ref();
+        let root = match self.get().await? {
+            TagEntry::Unsigned(TreeEntry { meta, .. }) => meta,
+            TagEntry::Signed(_) => bail!("checkout of signed tags is not supported"),
+        };
+
+        let mut written = BTreeMap::new();
+        let mut level = vec![(TreePath::ROOT, root)];
+        while !level.is_empty() {
+            let checked_out = for_each_node(self.0.parallelism(), level, |path, meta| async move {
+                self.path(&path)
+                    .track_node(&meta, self.checkout_node(dest, &path, &meta))
+                    .await
+            })
+            .await?;
+            level = vec![];
+            for (path, (created, children)) in checked_out {
+                level.extend(children);
+                _ = written.insert(path, created);
+            }
+        }
+        Ok(written)
+    }
+
+    /// Checks out the node at `path` described by `meta` into `dest`. Returns whether the node was
+    /// written to `dest` and, for directories, the paths and metadata of their entries.
+    async fn checkout_node(
+        &self,
+        dest: &Path,
+        path: &TreePath,
+        meta: &Meta,
+    ) -> Result<(bool, Vec<(TreePath, Meta)>)> {
+        let dst = dest.join(PathBuf::from(path.clone()));
+        let node = self.path(path);
+        let limit = self.0.limits().node;
+        if meta.mime.essence_str() == TreeDirectory::<()>::TYPE {
+            check_limit(meta.size, limit)
+                .with_context(|| format!("directory `{path}` is too large"))?;
+            let (_, buf) = (*node)
+                .clone()
+                .accept(DIRECTORY_TYPES)
+                .get_bytes(meta.size)
+                .await
+                .with_context(|| format!("failed to get directory `{path}`"))?;
+            let children = verify_directory(path, meta, &buf)?
+                .into_iter()
+                .map(|(name, TreeEntry { meta, .. })| {
+                    (path.clone().into_iter().chain([name]).collect(), meta)
+                })
+                .collect();
+            if async_std::fs::metadata(&dst)
+                .await
+                .is_ok_and(|md| md.is_dir())
+            {
+                return Ok((false, children));
+            }
+            async_std::fs::create_dir_all(&dst)
+                .await
+                .with_context(|| format!("failed to create `{}`", dst.display()))?;
+            Ok((true, children))
+        } else if file_matches(&dst, meta).await? {
+            Ok((false, vec![]))
+        } else {
+            check_limit(meta.size, limit).with_context(|| format!("file `{path}` is too large"))?;
+            let parent = dst.parent().unwrap_or(dest);
+            let (file, tmp) = tempfile::Builder::new()
+                .prefix(".drawbridge-")
+                .tempfile_in(parent)
+                .with_context(|| {
+                    format!("failed to create temporary file in `{}`", parent.display())
+                })?
+                .into_parts();
+            let (_, rdr) = node
+                .get(meta.size)
+                .await
+                .with_context(|| format!("failed to get file `{path}`"))?;
+            let mut file = async_std::fs::File::from(file);
+            let n = copy(meta.hash.clone().verifier(rdr), &mut file)
+                .await
+                .with_context(|| format!("failed to download file `{path}`"))?;
+            file.flush()
+                .await
+                .with_context(|| format!("failed to write `{}`", dst.display()))?;
+            ensure!(
+                n == meta.size,
+                "file `{path}` size does not match its entry"
+            );
+            tmp.persist(&dst)
+                .with_context(|| format!("failed to write `{}`", dst.display()))?;
+            Ok((true, vec![]))
+        }
+    }
+
+    /// Returns `true` if all tree nodes declared by the tag are uploaded.
+    pub async fn is_sealed(&self) -> Result<bool> {
+        self.0.head_header(SEALED_HEADER).await
+    }
+
+    /// Returns the amount of tree nodes, which are not uploaded yet, but declared by an uploaded
+    /// directory or, for the root, by the tag entry.
+    pub async fn missing_nodes(&self) -> Result<usize> {
+        self.0.head_header(MISSING_NODES_HEADER).await
+    }
+
+    /// Yanks the tag, which hides it from tag listings, and returns its yank state.
+    pub async fn yank(&self) -> Result<TagYank> {
+        self.0
+            .child::<scope::Unknown>("yank")
+            .put_empty_json()
+            .await
+    }
+
+    /// Deletes the tag.
+    pub async fn delete(&self) -> Result<()> {
+        self.0.delete().await
+    }
+
+    pub fn path(&self, path: &TreePath) -> Node<'a, S> {
+        Node::new(self.child("tree"), path)
+    }
+}
@@ -0,0 +1,101 @@
+// SPDX-FileCopyrightText: 2022 Profian Inc. <opensource@profian.com>
+// SPDX-License-Identifier: Apache-2.0
+
+use super::{scope, Entity, Result, Scope};
+use crate::TreeErrors;
+
+use std::collections::BTreeMap;
+use std::future::Future;
+use std::num::NonZeroUsize;
+use std::ops::Deref;
+
+use drawbridge_type::{Meta, TreeDirectory, TreeEntry, TreePath};
+
+use futures::{stream, AsyncRead, StreamExt};
+use mime::Mime;
+use ureq::serde::Serialize;
+
+/// Awaits `f` applied to all `nodes`, processing up to `parallelism` nodes concurrently.
+/// Returns the results keyed by path or [TreeErrors] holding all failures.
+pub(super) async fn for_each_node<T, R, F>(
+    parallelism: NonZeroUsize,
+    nodes: impl IntoIterator<Item = (TreePath, T)>,
+    f: impl Fn(TreePath, T) -> F,
+) -> Result<BTreeMap<TreePath, R>>
+where
+    F: Future<Output = Result<R>>,
+{
+    let results = stream::iter(nodes)
+        .map(|(path, node)| {
+            let res = f(path.clone(), node);
+            async move { (path, res.await) }
+        })
+        .buffer_unordered(parallelism.get())
+        .collect::<Vec<_>>()
+        .await;
+
+    let mut done = BTreeMap::new();
+    let mut failed = BTreeMap::new();
+    for (path, res) in results {
+        match res {
+            Ok(v) => _ = done.insert(path, v),
+            Err(e) => _ = failed.insert(path, e),
+        }
+    }
+    if failed.is_empty() {
+        Ok(done)
+    } else {
+        Err(TreeErrors(failed).into())
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct Node<'a, S: Scope>(Entity<'a, S, scope::Node>);
+
+impl<'a, S: Scope> Deref for Node<'a, S> {
+    type Target = Entity<'a, S, scope::Node>;
+
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
+impl<'a, S: Scope> Node<'a, S> {
+    pub fn new(entity: Entity<'a, S, scope::Node>, path: &TreePath) -> Self {
+        if path.is_empty() {
+            Self(entity.tree_node(path))
+        } else {
+            Self(entity.child(&path.to_string()).tree_node(path))
+        }
+    }
+
+    pub async fn create_bytes(&self, mime: &Mime, data: impl AsRef<[u8]>) -> Result<bool> {
+        self.0.create_bytes(mime, data).await
+    }
+
+    pub async fn create_json(&self, mime: &Mime, val: &impl Serialize) -> Result<bool> {
+        self.0.create_json(mime, val).await
+    }
+
+    /// Creates the node with contents described by `meta` streamed from `rdr`.
+    pub async fn create_from(
+        &self,
+        meta: &Meta,
+        rdr: impl 'static + Send + Sync + Unpin + AsyncRead,
+    ) -> Result<bool> {
+        self.0.create_from(meta, rdr).await
+    }
+
+    /// Creates the node referring to contents already stored in the repository, without
+    /// uploading them.
+    pub async fn create_from_blob(&self, meta: &Meta) -> Result<bool> {
+        self.0.create_from_blob(meta).await
+    }
+
+    pub async fn create_directory<C>(&self, dir: &TreeDirectory<TreeEntry<C>>) -> Result<bool> {
+        let mime = TreeDirectory::<C>::TYPE
+            .parse()
+            .expect("failed to parse tree directory media type");
+        self.create_json(&mime, dir).await
+    }
+}
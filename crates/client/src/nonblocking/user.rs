@@ -0,0 +1,62 @@
+// SPDX-FileCopyrightText: 2022 Profian Inc. <opensource@profian.com>
+// SPDX-License-Identifier: Apache-2.0
+
+use super::{scope, Entity, Repository, Result, Scope};
+use crate::JSON_TYPES;
+
+use std::ops::Deref;
+
+use drawbridge_type::{RepositoryEntry, RepositoryName, UserName, UserRecord};
+
+use mime::APPLICATION_JSON;
+
+#[derive(Clone, Debug)]
+#[repr(transparent)]
+pub struct User<'a, S: Scope>(Entity<'a, S, scope::User>);
+
+impl<'a, S: Scope> Deref for User<'a, S> {
+    type Target = Entity<'a, S, scope::User>;
+
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
+impl<'a, S: Scope> User<'a, S> {
+    pub fn new(entity: Entity<'a, S, scope::Root>, name: &UserName) -> Self {
+        User(entity.child(&name.to_string()))
+    }
+
+    pub async fn create(&self, conf: &UserRecord) -> Result<bool> {
+        self.0.create_json(&APPLICATION_JSON, conf).await
+    }
+
+    pub async fn get(&self) -> Result<UserRecord> {
+        self.0
+            .clone()
+            .accept(JSON_TYPES)
+            .get_json(self.0.limits().user)
+            .await
+            .map(|(_, v)| v)
+    }
+
+    /// Deletes the user, which must not own any repositories.
+    pub async fn delete(&self) -> Result<()> {
+        self.0.delete().await
+    }
+
+    /// Returns names and configs of repositories of the user sorted by name, see
+    /// [crate::User::repositories].
+    pub async fn repositories(&self) -> Result<Vec<RepositoryEntry>> {
+        self.0
+            .child::<scope::Unknown>("_repos")
+            .accept(JSON_TYPES)
+            .get_json(self.0.limits().listing)
+            .await
+            .map(|(_, v)| v)
+    }
+
+    pub fn repository(&self, name: &RepositoryName) -> Repository<'a, S> {
+        Repository::new(self.0.clone(), name)
+    }
+}
@@ -0,0 +1,78 @@
+// SPDX-FileCopyrightText: 2022 Profian Inc. <opensource@profian.com>
+// SPDX-License-Identifier: Apache-2.0
+
+use std::fmt::Debug;
+use std::io::{self, Read, Seek, SeekFrom};
+#[cfg(feature = "async")]
+use std::pin::Pin;
+use std::sync::Arc;
+#[cfg(feature = "async")]
+use std::task::{ready, Context, Poll};
+
+use drawbridge_type::{Meta, TreePath};
+
+/// Observer of the progress of tree uploads and downloads, which is notified by clients built
+/// with [ClientBuilder::progress](super::ClientBuilder::progress).
+///
+/// Tree nodes may be transferred concurrently, so notifications of different nodes may
+/// interleave. Bytes of a node may be reported again if its transfer is retried.
+#[allow(unused_variables)]
+pub trait Progress: Debug + Send + Sync {
+    /// Called once before uploading a tree consisting of `nodes` nodes of `bytes` bytes in total.
+    fn total(&self, nodes: usize, bytes: u64) {}
+
+    /// Called before transferring the node at `path` described by `meta`.
+    fn start(&self, path: &TreePath, meta: &Meta) {}
+
+    /// Called whenever `bytes` further bytes of the node at `path` were transferred.
+    fn transferred(&self, path: &TreePath, bytes: u64) {}
+
+    /// Called once the node at `path` is transferred or did not need to be transferred, because
+    /// it was present already.
+    fn finish(&self, path: &TreePath) {}
+}
+
+/// Reader reporting the amount of bytes read from `rdr` as transferred bytes of a tree node.
+#[derive(Debug)]
+pub(super) struct ProgressReader<R> {
+    pub(super) progress: Option<(Arc<dyn Progress>, TreePath)>,
+    pub(super) rdr: R,
+}
+
+impl<R> ProgressReader<R> {
+    /// Reports `n` bytes read from `rdr`.
+    fn report(&self, n: usize) {
+        if let Some((ref progress, ref path)) = self.progress {
+            if n > 0 {
+                progress.transferred(path, n as u64);
+            }
+        }
+    }
+}
+
+impl<R: Read> Read for ProgressReader<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let n = self.rdr.read(buf)?;
+        self.report(n);
+        Ok(n)
+    }
+}
+
+#[cfg(feature = "async")]
+impl<R: futures::AsyncRead + Unpin> futures::AsyncRead for ProgressReader<R> {
+    fn poll_read(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut [u8],
+    ) -> Poll<io::Result<usize>> {
+        let n = ready!(Pin::new(&mut self.rdr).poll_read(cx, buf))?;
+        self.report(n);
+        Poll::Ready(Ok(n))
+    }
+}
+
+impl<R: Seek> Seek for ProgressReader<R> {
+    fn seek(&mut self, pos: SeekFrom) -> io::Result<u64> {
+        self.rdr.seek(pos)
+    }
+}
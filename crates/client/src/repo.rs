@@ -1,13 +1,20 @@
 // SPDX-FileCopyrightText: 2022 Profian Inc. <opensource@profian.com>
 // SPDX-License-Identifier: Apache-2.0
 
-use super::{scope, Entity, Result, Scope, Tag};
+use super::{scope, Entity, Result, Scope, Tag, Url, JSON_TYPES};
 
 use std::ops::Deref;
+use std::vec;
 
-use drawbridge_type::{RepositoryConfig, RepositoryName, TagName};
+use drawbridge_type::digest::ContentDigest;
+use drawbridge_type::repository::CONFIRM_DELETE_HEADER;
+use drawbridge_type::{
+    RepositoryConfig, RepositoryContext, RepositoryName, TagName, TagQuery, TagResolution,
+};
 
 use mime::APPLICATION_JSON;
+use percent_encoding::{utf8_percent_encode, NON_ALPHANUMERIC};
+use semver::VersionReq;
 
 #[derive(Clone, Debug)]
 pub struct Repository<'a, S: Scope>(Entity<'a, S, scope::Repository>);
@@ -20,6 +27,12 @@ impl<'a, S: Scope> Deref for Repository<'a, S> {
     }
 }
 
+impl<'a, S: Scope> From<Entity<'a, S, scope::Repository>> for Repository<'a, S> {
+    fn from(entity: Entity<'a, S, scope::Repository>) -> Self {
+        Self(entity)
+    }
+}
+
 impl<'a, S: Scope> Repository<'a, S> {
     pub fn new(entity: Entity<'a, S, scope::User>, name: &RepositoryName) -> Repository<'a, S> {
         Repository(entity.child(name.as_ref()))
@@ -29,19 +42,112 @@ impl<'a, S: Scope> Repository<'a, S> {
         self.0.create_json(&APPLICATION_JSON, conf)
     }
 
+    /// Replaces the configuration of the existing repository.
+    pub fn update(&self, conf: &RepositoryConfig) -> Result<()> {
+        self.0.update_json(&APPLICATION_JSON, conf)
+    }
+
+    /// Deletes the repository along with all of its tags, confirming that `cx` identifies the
+    /// repository to delete.
+    pub fn delete(&self, cx: &RepositoryContext) -> Result<()> {
+        self.0
+            .delete_with(&[(CONFIRM_DELETE_HEADER, &cx.to_string())])
+    }
+
     pub fn get(&self) -> Result<RepositoryConfig> {
-        // TODO: Use a reasonable byte limit
-        self.0.get_json(u64::MAX).map(|(_, v)| v)
+        self.0
+            .clone()
+            .accept(JSON_TYPES)
+            .get_json(self.0.limits().repository)
+            .map(|(_, v)| v)
     }
 
+    /// Returns the tags of the repository.
+    ///
+    /// The listing is revalidated using its `ETag`, so repeated calls are cheap while it does
+    /// not change.
     pub fn tags(&self) -> Result<Vec<TagName>> {
         self.0
             .child::<scope::Unknown>("_tag")
-            .get_json(u64::MAX)
+            .accept(JSON_TYPES)
+            .get_json_cached(self.0.limits().listing)
+    }
+
+    /// Returns an iterator over the tags matching `query` in semantic version order, which
+    /// fetches pages of at most `limit` tags of the `query` following the links advertised by
+    /// the server.
+    pub fn query_tags(&self, query: &TagQuery) -> Tags<'a, S> {
+        let tags = self.0.child::<scope::Unknown>("_tag").accept(JSON_TYPES);
+        let next = tags.url().map(|mut url| {
+            _ = url.query_pairs_mut().extend_pairs(query.pairs());
+            url
+        });
+        Tags {
+            entity: tags,
+            page: Default::default(),
+            next: Some(next),
+        }
+    }
+
+    /// Returns the highest tag satisfying `req` along with its entry, omitting yanked and
+    /// pre-release tags.
+    pub fn resolve(&self, req: &VersionReq) -> Result<TagResolution> {
+        self.resolve_request(req, false)
+    }
+
+    /// Like [Self::resolve], but pre-release tags may satisfy `req` as well.
+    pub fn resolve_pre_release(&self, req: &VersionReq) -> Result<TagResolution> {
+        self.resolve_request(req, true)
+    }
+
+    fn resolve_request(&self, req: &VersionReq, pre: bool) -> Result<TagResolution> {
+        let req = utf8_percent_encode(&req.to_string(), NON_ALPHANUMERIC).to_string();
+        self.0
+            .child::<scope::Unknown>(&format!("_resolve/{req}?pre={pre}"))
+            .accept(JSON_TYPES)
+            .get_json(self.0.limits().tag)
             .map(|(_, v)| v)
     }
 
     pub fn tag(&self, name: &TagName) -> Tag<'a, S> {
-        Tag::new(self.child("_tag"), name)
+        Tag::new(self.0.clone(), name)
+    }
+
+    /// Returns `true` if the repository holds contents with `hash`, in which case tree nodes with
+    /// these contents can be created without uploading them.
+    pub fn has_blob(&self, hash: &ContentDigest) -> Result<bool> {
+        self.0.child::<scope::Unknown>("_blob").has_digest(hash)
+    }
+}
+
+/// Iterator over tags of a [Repository] matching a [TagQuery], see [Repository::query_tags].
+#[derive(Debug)]
+pub struct Tags<'a, S: Scope> {
+    entity: Entity<'a, S, scope::Unknown>,
+    page: vec::IntoIter<TagName>,
+    next: Option<Result<Url>>,
+}
+
+impl<S: Scope> Iterator for Tags<'_, S> {
+    type Item = Result<TagName>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            if let Some(name) = self.page.next() {
+                return Some(Ok(name));
+            }
+            let url = match self.next.take()? {
+                Ok(url) => url,
+                Err(e) => return Some(Err(e)),
+            };
+            let limit = self.entity.limits().listing;
+            match self.entity.get_json_page::<Vec<_>>(&url, limit) {
+                Ok((page, next)) => {
+                    self.page = page.into_iter();
+                    self.next = next.map(Ok);
+                }
+                Err(e) => return Some(Err(e)),
+            }
+        }
     }
 }
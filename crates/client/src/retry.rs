@@ -0,0 +1,109 @@
+// SPDX-FileCopyrightText: 2022 Profian Inc. <opensource@profian.com>
+// SPDX-License-Identifier: Apache-2.0
+
+use std::collections::hash_map::RandomState;
+use std::hash::{BuildHasher, Hasher};
+use std::time::Duration;
+
+use ureq::ErrorKind;
+
+/// Policy for retrying idempotent requests, which failed due to transient failures, like
+/// connection failures and `429`, `500`, `502`, `503` or `504` responses.
+///
+/// Retries are delayed by an exponentially growing backoff with random jitter, unless the server
+/// specifies the delay in a `Retry-After` header, which is capped by the maximum backoff.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct RetryPolicy {
+    /// Maximum amount of retries of a request
+    pub max_retries: u32,
+
+    /// Backoff before the first retry, which doubles with every further retry
+    pub initial_backoff: Duration,
+
+    /// Upper bound of the backoff and of delays requested by the server
+    pub max_backoff: Duration,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self {
+            max_retries: 3,
+            initial_backoff: Duration::from_millis(200),
+            max_backoff: Duration::from_secs(10),
+        }
+    }
+}
+
+impl RetryPolicy {
+    /// Returns a policy, which never retries requests.
+    pub fn never() -> Self {
+        Self {
+            max_retries: 0,
+            ..Default::default()
+        }
+    }
+
+    /// Returns the delay before retrying a request, which failed with `err` after `retries`
+    /// retries, or `None` if it must not be retried.
+    pub(super) fn delay(&self, retries: u32, err: &ureq::Error) -> Option<Duration> {
+        match err {
+            ureq::Error::Status(status, res) => {
+                self.delay_status(retries, *status, res.header("Retry-After"))
+            }
+            ureq::Error::Transport(e)
+                if matches!(
+                    e.kind(),
+                    ErrorKind::Dns | ErrorKind::ConnectionFailed | ErrorKind::Io
+                ) =>
+            {
+                self.delay_transport(retries)
+            }
+            ureq::Error::Transport(_) => None,
+        }
+    }
+
+    /// Returns the delay before retrying a request, which the server responded to with `status`
+    /// and the `Retry-After` header value `retry_after` after `retries` retries, or `None` if it
+    /// must not be retried.
+    pub(super) fn delay_status(
+        &self,
+        retries: u32,
+        status: u16,
+        retry_after: Option<&str>,
+    ) -> Option<Duration> {
+        if !matches!(status, 429 | 500 | 502 | 503 | 504) {
+            return None;
+        }
+        let retry_after = retry_after
+            .and_then(|secs| secs.trim().parse().ok())
+            .map(Duration::from_secs);
+        self.backoff(retries, retry_after)
+    }
+
+    /// Returns the delay before retrying a request, which could not be exchanged with the server
+    /// due to a connection or I/O failure after `retries` retries, or `None` if it must not be
+    /// retried.
+    pub(super) fn delay_transport(&self, retries: u32) -> Option<Duration> {
+        self.backoff(retries, None)
+    }
+
+    /// Returns the delay requested by the server in `retry_after` capped by the maximum backoff
+    /// or the backoff after `retries` retries, or `None` if retries are exhausted.
+    fn backoff(&self, retries: u32, retry_after: Option<Duration>) -> Option<Duration> {
+        if retries >= self.max_retries {
+            return None;
+        }
+        Some(match retry_after {
+            Some(delay) => delay.min(self.max_backoff),
+            None => {
+                let backoff = self
+                    .initial_backoff
+                    .saturating_mul(2_u32.saturating_pow(retries))
+                    .min(self.max_backoff);
+                // Spread retries of concurrent requests over the upper half of the backoff
+                let jitter = RandomState::new().build_hasher().finish() as f64 / u64::MAX as f64;
+                backoff.mul_f64(0.5 + jitter / 2.0)
+            }
+        })
+    }
+}
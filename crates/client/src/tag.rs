@@ -1,23 +1,328 @@
 // SPDX-FileCopyrightText: 2022 Profian Inc. <opensource@profian.com>
 // SPDX-License-Identifier: Apache-2.0
 
-use super::{scope, Entity, Node, Result, Scope};
+use super::entity::meta_matches;
+use super::{
+    check_limit, for_each_node, scope, Entity, Limits, Node, Progress, ProgressReader, Repository,
+    Result, Scope, DIRECTORY_TYPES, TAG_TYPES,
+};
 
+use std::cell::Cell;
 use std::collections::BTreeMap;
-use std::io::Seek;
+use std::fs;
+use std::io::{self, copy, pipe, sink, Cursor, ErrorKind, Read, Seek, Write};
 use std::ops::Deref;
-use std::path::Path;
+use std::path::{Path, PathBuf};
+use std::rc::Rc;
+use std::sync::Arc;
+use std::thread;
 
 use drawbridge_jose::jws::Jws;
 use drawbridge_jose::MediaTyped;
+use drawbridge_type::tag::{
+    ArchiveFormat, ARCHIVE_DIRECTORY_KEY, ARCHIVE_ROOT, ARCHIVE_TYPE, MISSING_NODES_HEADER,
+    SEALED_HEADER,
+};
 use drawbridge_type::TreeContent::{Directory, File};
-use drawbridge_type::{TagEntry, TagName, Tree, TreeEntry, TreePath};
+use drawbridge_type::{Meta, TagEntry, TagName, TagYank, Tree, TreeDirectory, TreeEntry, TreePath};
 
-use anyhow::Context;
+use anyhow::{anyhow, bail, ensure, Context};
+use flate2::read::GzDecoder;
+use tar::{Archive, Builder, EntryType, Header, PaxExtensions};
 use ureq::serde::Serialize;
 
+/// Writes all nodes of `tree` to `wtr` as a tag tree archive, in which each node is a regular
+/// file named as described in [ARCHIVE_ROOT]. Written nodes are reported to `progress`.
+fn write_archive(
+    tree: &Tree<fs::File>,
+    wtr: impl Write,
+    progress: Option<Arc<dyn Progress>>,
+) -> Result<()> {
+    let mut archive = Builder::new(wtr);
+    for (path, TreeEntry { meta, content, .. }) in tree.iter() {
+        if let Some(ref progress) = progress {
+            progress.start(path, meta);
+        }
+        let name = archive_name(path);
+        let mut header = Header::new_gnu();
+        header.set_entry_type(EntryType::Regular);
+        header.set_mode(0o644);
+        header.set_size(meta.size);
+        match content {
+            File(file) => {
+                let mut file = file;
+                file.rewind().context("failed to rewind file")?;
+                let rdr = ProgressReader {
+                    progress: progress.clone().map(|progress| (progress, path.clone())),
+                    rdr: file.take(meta.size),
+                };
+                archive.append_data(&mut header, &name, rdr)
+            }
+            Directory(buf) => {
+                let rdr = ProgressReader {
+                    progress: progress.clone().map(|progress| (progress, path.clone())),
+                    rdr: buf.as_slice(),
+                };
+                archive.append_data(&mut header, &name, rdr)
+            }
+        }
+        .with_context(|| format!("failed to write `{name}` to archive"))?;
+        if let Some(ref progress) = progress {
+            progress.finish(path);
+        }
+    }
+    archive
+        .into_inner()
+        .map(|_| ())
+        .context("failed to finish archive")
+}
+
+/// Returns the name of the tag tree archive member holding the node at `path`.
+pub(super) fn archive_name(path: &TreePath) -> String {
+    if path.is_empty() {
+        ARCHIVE_ROOT.into()
+    } else {
+        format!("{ARCHIVE_ROOT}/{path}")
+    }
+}
+
+/// Parses the name of a tag tree archive member into the tree path of the node it holds.
+fn archive_path(name: &[u8]) -> Result<TreePath> {
+    let name = std::str::from_utf8(name).context("archive member name is not valid UTF-8")?;
+    let path = match name.trim_end_matches('/').strip_prefix(ARCHIVE_ROOT) {
+        Some("") => return Ok(TreePath::ROOT),
+        Some(path) => path.strip_prefix('/'),
+        None => None,
+    };
+    path.and_then(|path| path.parse().ok())
+        .with_context(|| format!("archive member name `{name}` is not a valid tree path"))
+}
+
+/// Parses the path of the node held by a tag tree archive member named `name` or by the GNU
+/// extended header `long_name` preceding it, if any.
+pub(super) fn archive_member_path(long_name: Option<Vec<u8>>, name: &[u8]) -> Result<TreePath> {
+    match long_name {
+        Some(mut name) => {
+            while name.last() == Some(&0) {
+                _ = name.pop();
+            }
+            archive_path(&name)
+        }
+        None => archive_path(name),
+    }
+}
+
+/// Returns the contents of the directory at `path` stored in the PAX extended header `pax`
+/// preceding its tag tree archive member.
+pub(super) fn archive_directory(pax: Option<&[u8]>, path: &TreePath) -> Result<Vec<u8>> {
+    pax.map(PaxExtensions::new)
+        .into_iter()
+        .flatten()
+        .find_map(|ext| match ext {
+            Ok(ext) if ext.key_bytes() == ARCHIVE_DIRECTORY_KEY.as_bytes() => {
+                Some(Ok(ext.value_bytes().to_vec()))
+            }
+            Ok(_) => None,
+            Err(e) => Some(Err(e)),
+        })
+        .transpose()
+        .context("failed to read PAX extensions")?
+        .with_context(|| format!("directory `{path}` lacks its contents"))
+}
+
+/// Verifies the contents `buf` of the directory at `path` against `meta` and decodes them.
+pub(super) fn verify_directory(
+    path: &TreePath,
+    meta: &Meta,
+    buf: &[u8],
+) -> Result<TreeDirectory<TreeEntry>> {
+    ensure!(
+        buf.len() as u64 == meta.size,
+        "directory `{path}` size does not match its entry"
+    );
+    _ = copy(&mut meta.hash.clone().verifier(buf), &mut sink())
+        .with_context(|| format!("directory `{path}` digest does not match its entry"))?;
+    serde_json::from_slice(buf).with_context(|| format!("failed to decode directory `{path}`"))
+}
+
+/// Size of tar blocks, by which headers and data of archive members are aligned.
+const ARCHIVE_BLOCK_SIZE: u64 = 512;
+
+/// Upper bound of the size of a member of a tag tree archive in addition to the contents and
+/// path of its node, that is of its headers, the PAX records describing its contents and padding.
+const ARCHIVE_MEMBER_OVERHEAD: u64 = 8 * 1024;
+
+/// Returns the maximum size of the archive member holding the node at `path` described by
+/// `meta`.
+pub(super) fn archive_member_size(path: &TreePath, meta: &Meta) -> u64 {
+    meta.size
+        .saturating_add(path.to_string().len() as _)
+        .saturating_add(ARCHIVE_MEMBER_OVERHEAD)
+}
+
+/// Returns the size of a tag tree archive holding only the root node described by `root`, to
+/// which the sizes of members declared by unpacked directories are added while unpacking.
+pub(super) fn archive_budget(root: &Meta) -> u64 {
+    // The end of the archive is marked by two empty blocks
+    archive_member_size(&TreePath::ROOT, root).saturating_add(2 * ARCHIVE_BLOCK_SIZE)
+}
+
+/// Returns the maximum size of extended headers of tag tree archive members accepted by clients
+/// with `limits`.
+pub(super) fn max_extended_header(limits: Limits) -> u64 {
+    limits
+        .node
+        .min(limits.listing)
+        .saturating_add(ARCHIVE_MEMBER_OVERHEAD)
+}
+
+/// Reader failing once more bytes than the shared `budget` are read.
+struct BudgetReader<R> {
+    rdr: R,
+    budget: Rc<Cell<u64>>,
+}
+
+impl<R: Read> Read for BudgetReader<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let budget = self.budget.get();
+        if budget == 0 && !buf.is_empty() {
+            return Err(io::Error::other(
+                "archive exceeds the size of the declared tree",
+            ));
+        }
+        let len = buf.len().min(budget.try_into().unwrap_or(usize::MAX));
+        let n = self.rdr.read(&mut buf[..len])?;
+        self.budget.set(budget - n as u64);
+        Ok(n)
+    }
+}
+
+/// Unpacks the tag tree archive read from `rdr` into `dest`, verifying each member against the
+/// entry declared by its parent, starting with `root`. Nodes must not exceed the node limit of
+/// `limits`. Returns the metadata of all unpacked nodes.
+///
+/// The archive must not exceed the size of the members holding the declared nodes and the
+/// extended headers of members must not exceed the listing limit of `limits`, so that malicious
+/// archives cannot exhaust memory or bandwidth.
+fn unpack_archive(
+    rdr: impl Read,
+    root: Meta,
+    dest: &Path,
+    limits: Limits,
+) -> Result<BTreeMap<TreePath, Meta>> {
+    let max_header = max_extended_header(limits);
+    let budget = Rc::new(Cell::new(archive_budget(&root)));
+    let mut expected = BTreeMap::from([(TreePath::ROOT, root)]);
+    let mut unpacked = BTreeMap::new();
+    let mut archive = Archive::new(BudgetReader {
+        rdr,
+        budget: budget.clone(),
+    });
+    // Extended headers are read by hand to bound their size, hence members are iterated raw
+    let mut long_name = None;
+    let mut pax = None;
+    for entry in archive
+        .entries()
+        .context("failed to read archive")?
+        .raw(true)
+    {
+        let mut entry = entry.context("failed to read archive member")?;
+        let ext = match entry.header().entry_type() {
+            EntryType::GNULongName => Some(&mut long_name),
+            EntryType::XHeader => Some(&mut pax),
+            _ => None,
+        };
+        if let Some(ext) = ext {
+            ensure!(
+                entry.size() <= max_header,
+                "archive member extended header exceeds {max_header} bytes"
+            );
+            let mut buf = vec![];
+            _ = entry
+                .read_to_end(&mut buf)
+                .context("failed to read archive member extended header")?;
+            ensure!(
+                ext.replace(buf).is_none(),
+                "archive member has multiple extended headers of the same type"
+            );
+            continue;
+        }
+        let path = archive_member_path(long_name.take(), &entry.path_bytes())?;
+        let pax = pax.take();
+        let meta = expected
+            .remove(&path)
+            .with_context(|| format!("node `{path}` is not declared by its parent"))?;
+        check_limit(meta.size, limits.node)
+            .with_context(|| format!("node `{path}` is too large"))?;
+        let dst = dest.join(PathBuf::from(path.clone()));
+        match entry.header().entry_type() {
+            EntryType::Directory => {
+                ensure!(
+                    meta.mime.essence_str() == TreeDirectory::<()>::TYPE,
+                    "node `{path}` is a directory, but is not declared as one"
+                );
+                let buf = archive_directory(pax.as_deref(), &path)?;
+                for (name, TreeEntry { meta, .. }) in verify_directory(&path, &meta, &buf)? {
+                    let path = path.clone().into_iter().chain([name]).collect();
+                    budget.set(
+                        budget
+                            .get()
+                            .saturating_add(archive_member_size(&path, &meta)),
+                    );
+                    _ = expected.insert(path, meta);
+                }
+                fs::create_dir(&dst)
+                    .with_context(|| format!("failed to create `{}`", dst.display()))?;
+            }
+            EntryType::Regular => {
+                ensure!(
+                    meta.mime.essence_str() != TreeDirectory::<()>::TYPE,
+                    "node `{path}` is a file, but is declared as a directory"
+                );
+                ensure!(
+                    entry.header().size().ok() == Some(meta.size),
+                    "file `{path}` size does not match its entry"
+                );
+                let mut file = fs::File::create_new(&dst)
+                    .with_context(|| format!("failed to create `{}`", dst.display()))?;
+                let n = copy(&mut meta.hash.clone().verifier(&mut entry), &mut file)
+                    .with_context(|| format!("failed to unpack file `{path}`"))?;
+                ensure!(
+                    n == meta.size,
+                    "file `{path}` size does not match its entry"
+                );
+            }
+            t => bail!("node `{path}` is stored as an unsupported archive member type {t:?}"),
+        }
+        _ = unpacked.insert(path, meta);
+    }
+    if let Some(path) = expected.keys().next() {
+        bail!("archive lacks node `{path}`")
+    }
+    Ok(unpacked)
+}
+
+/// Returns `true` if `path` holds a file with contents matching `meta`.
+fn file_matches(path: &Path, meta: &Meta) -> Result<bool> {
+    if !path.exists() {
+        return Ok(false);
+    }
+    ensure!(path.is_file(), "`{}` is not a file", path.display());
+    let file =
+        fs::File::open(path).with_context(|| format!("failed to open `{}`", path.display()))?;
+    if file.metadata()?.len() != meta.size {
+        return Ok(false);
+    }
+    match copy(&mut meta.hash.clone().verifier(file), &mut sink()) {
+        Err(e) if e.kind() == ErrorKind::InvalidData => Ok(false),
+        res => res
+            .map(|_| true)
+            .with_context(|| format!("failed to read `{}`", path.display())),
+    }
+}
+
 #[derive(Clone, Debug)]
-pub struct Tag<'a, S: Scope>(Entity<'a, S, scope::Tag>);
+pub struct Tag<'a, S: Scope>(Entity<'a, S, scope::Tag>, Repository<'a, S>);
 
 impl<'a, S: Scope> Deref for Tag<'a, S> {
     type Target = Entity<'a, S, scope::Tag>;
@@ -29,7 +334,10 @@ impl<'a, S: Scope> Deref for Tag<'a, S> {
 
 impl<'a, S: Scope> Tag<'a, S> {
     pub fn new(entity: Entity<'a, S, scope::Repository>, name: &TagName) -> Self {
-        Tag(entity.child(&name.to_string()))
+        Tag(
+            entity.child(&format!("_tag/{name}")),
+            Repository::from(entity),
+        )
     }
 
     pub fn create(&self, entry: &TagEntry<impl Serialize>) -> Result<bool> {
@@ -42,34 +350,259 @@ impl<'a, S: Scope> Tag<'a, S> {
         self.0.create_json(&mime, entry)
     }
 
+    /// Uploads all nodes of `tree` as a single archive, which the server verifies and commits
+    /// atomically. Returns `None` if the server does not support archive uploads, which is
+    /// assumed if the tag exists, but the archive endpoint is not found.
+    pub fn create_archive(&self, tree: &Tree<fs::File>) -> Result<Option<bool>> {
+        let mime = ARCHIVE_TYPE
+            .parse()
+            .expect("failed to parse archive media type");
+        let (rdr, wtr) = pipe().context("failed to create pipe")?;
+        thread::scope(|s| {
+            let progress = self.0.progress().cloned();
+            let writer = s.spawn(move || write_archive(tree, wtr, progress));
+            let res = self
+                .0
+                .child::<scope::Unknown>("archive")
+                .create_stream(&mime, rdr, || self.0.try_head().map(|meta| meta.is_some()));
+            let written = writer
+                .join()
+                .map_err(|_| anyhow!("archive writer panicked"))?;
+            // Failures of the upload cause the writer to fail on the closed pipe, so these are
+            // reported in favor of the writer failure.
+            match (res, written) {
+                (Ok(None), _) => Ok(None),
+                (Err(e), _) => Err(e),
+                (res, Ok(())) => res,
+                (_, Err(e)) => Err(e),
+            }
+        })
+    }
+
+    /// Creates the tag and uploads the tree at `path`.
+    ///
+    /// The tree is uploaded as a single archive, falling back to uploading each node separately
+    /// if the server does not support archive uploads or the tree is partially uploaded already.
+    /// The tag and nodes, which already exist with matching contents, are reused, so that an
+    /// interrupted upload can be resumed. Returns whether the tag and each node were created,
+    /// rather than reused.
+    ///
+    /// The size of the tree is reported to the progress observer of the client before uploading.
     // TODO: Support signed tags
     pub fn create_from_path_unsigned(
         &self,
         path: impl AsRef<Path>,
     ) -> Result<(bool, BTreeMap<TreePath, bool>)> {
         let tree = Tree::from_path_sync(path)?;
-        let tag_created = self.create(&TagEntry::Unsigned(tree.root()))?;
-        let tree_created = tree
-            .into_iter()
-            .map(|(path, TreeEntry { meta, content, .. })| {
-                let node = Node::new(self.child("tree"), &path);
-                let created = match content {
-                    File(mut file) => {
-                        file.rewind().context("failed to rewind file")?;
-                        node.create_from(&meta, file)?
-                    }
-                    Directory(buf) => node.create_from(&meta, buf.as_slice())?,
-                };
-                Ok((path, created))
-            })
-            .collect::<Result<_>>()?;
+        if let Some(progress) = self.0.progress() {
+            progress.total(tree.len(), tree.values().map(|entry| entry.meta.size).sum());
+        }
+        let root = tree.root();
+        let tag_created = match self.0.try_head()? {
+            None => self.create(&TagEntry::Unsigned(root))?,
+            Some(_) => match self.get()? {
+                TagEntry::Unsigned(TreeEntry { meta, .. }) if meta_matches(&meta, &root.meta) => {
+                    false
+                }
+                _ => bail!("tag exists with a different tree"),
+            },
+        };
+        if self.path(&TreePath::ROOT).try_head()?.is_none() {
+            if let Some(created) = self.create_archive(&tree)? {
+                let tree_created = tree.keys().map(|path| (path.clone(), created)).collect();
+                return Ok((tag_created, tree_created));
+            }
+        }
+        // Parents must be uploaded before their children, which are verified against them
+        let mut levels = BTreeMap::<_, Vec<_>>::new();
+        for (path, entry) in tree {
+            levels.entry(path.len()).or_default().push((path, entry));
+        }
+        let mut tree_created = BTreeMap::new();
+        for level in levels.into_values() {
+            tree_created.append(&mut for_each_node(
+                self.0.parallelism(),
+                level,
+                |path, TreeEntry { meta, content, .. }| {
+                    let node = self.path(path);
+                    node.track_node(&meta, || {
+                        node.create_or_reuse(&meta, || match content {
+                            File(_) if meta.size > 0 && self.1.has_blob(&meta.hash)? => {
+                                node.create_from_blob(&meta)
+                            }
+                            File(mut file) => {
+                                file.rewind().context("failed to rewind file")?;
+                                node.create_from_seekable(&meta, file)
+                            }
+                            Directory(buf) => node.create_from_seekable(&meta, Cursor::new(buf)),
+                        })
+                    })
+                },
+            )?);
+        }
         Ok((tag_created, tree_created))
     }
 
     pub fn get(&self) -> Result<TagEntry> {
-        // TODO: Validate MIME type
-        // TODO: Use a reasonable byte limit
-        self.0.get_json(u64::MAX).map(|(_, v)| v)
+        self.0
+            .clone()
+            .accept(TAG_TYPES)
+            .get_json(self.0.limits().tag)
+            .map(|(_, v)| v)
+    }
+
+    /// Downloads the tree of the tag as an archive in `format` and unpacks it into `dest`, which
+    /// must not exist. Every node is verified against the entry declared by its parent while
+    /// unpacking and `dest` is removed if verification fails. Nodes exceeding the
+    /// [node limit](super::Limits::node) of the client are rejected, as are archives exceeding the
+    /// size of the declared tree or holding extended headers exceeding the
+    /// [listing limit](super::Limits::listing).
+    /// Returns the metadata of all unpacked nodes.
+    // TODO: Support signed tags
+    pub fn download_archive(
+        &self,
+        dest: impl AsRef<Path>,
+        format: ArchiveFormat,
+    ) -> Result<BTreeMap<TreePath, Meta>> {
+        let dest = dest.as_ref();
+        ensure!(!dest.exists(), "`{}` already exists", dest.display());
+
+        let root = match self.get()? {
+            TagEntry::Unsigned(TreeEntry { meta, .. }) => meta,
+            TagEntry::Signed(_) => bail!("downloading trees of signed tags is not supported"),
+        };
+        let mut url = self.0.child::<scope::Unknown>("archive").url()?;
+        _ = url.query_pairs_mut().append_pair("format", format.as_str());
+        let (mime, rdr) = self.0.get_stream(&url)?;
+        ensure!(
+            mime.essence_str() == format.media_type(),
+            "unexpected archive media type `{mime}`"
+        );
+        let rdr: Box<dyn Read> = match format {
+            ArchiveFormat::Tar => Box::new(rdr),
+            ArchiveFormat::TarGzip => Box::new(GzDecoder::new(rdr)),
+            ArchiveFormat::TarZstd => {
+                Box::new(zstd::Decoder::new(rdr).context("failed to initialize zstd decoder")?)
+            }
+        };
+        unpack_archive(rdr, root, dest, self.0.limits()).inspect_err(|_| {
+            _ = fs::remove_dir_all(dest).or_else(|_| fs::remove_file(dest));
+        })
+    }
+
+    /// Checks out the tree of the tag into `dest`, verifying every node against the entry declared
+    /// by its parent.
+    ///
+    /// Files are downloaded into temporary files next to their destination and only moved into
+    /// place once verified, so `dest` never holds partially downloaded files. Files already present
+    /// in `dest` with matching contents are not downloaded again, which allows resuming an
+    /// interrupted checkout.
+    /// Returns whether each node was written to `dest`.
+    ///
+    /// Nodes exceeding the [node limit](super::Limits::node) of the client are rejected.
+    /// Nodes are reported to the progress observer of the client as they are checked out.
+    // TODO: Support signed tags
+    pub fn checkout(&self, dest: impl AsRef<Path>) -> Result<BTreeMap<TreePath, bool>> {
+        let dest = dest.as_ref();
+        let root = match self.get()? {
+            TagEntry::Unsigned(TreeEntry { meta, .. }) => meta,
+            TagEntry::Signed(_) => bail!("checkout of signed tags is not supported"),
+        };
+
+        let mut written = BTreeMap::new();
+        let mut level = vec![(TreePath::ROOT, root)];
+        while !level.is_empty() {
+            let checked_out = for_each_node(self.0.parallelism(), level, |path, meta| {
+                self.path(path)
+                    .track_node(&meta, || self.checkout_node(dest, path, &meta))
+            })?;
+            level = vec![];
+            for (path, (created, children)) in checked_out {
+                level.extend(children);
+                _ = written.insert(path, created);
+            }
+        }
+        Ok(written)
+    }
+
+    /// Checks out the node at `path` described by `meta` into `dest`. Returns whether the node was
+    /// written to `dest` and, for directories, the paths and metadata of their entries.
+    fn checkout_node(
+        &self,
+        dest: &Path,
+        path: &TreePath,
+        meta: &Meta,
+    ) -> Result<(bool, Vec<(TreePath, Meta)>)> {
+        let dst = dest.join(PathBuf::from(path.clone()));
+        let node = self.path(path);
+        let limit = self.0.limits().node;
+        if meta.mime.essence_str() == TreeDirectory::<()>::TYPE {
+            check_limit(meta.size, limit)
+                .with_context(|| format!("directory `{path}` is too large"))?;
+            let (_, buf) = (*node)
+                .clone()
+                .accept(DIRECTORY_TYPES)
+                .get_bytes(meta.size)
+                .with_context(|| format!("failed to get directory `{path}`"))?;
+            let children = verify_directory(path, meta, &buf)?
+                .into_iter()
+                .map(|(name, TreeEntry { meta, .. })| {
+                    (path.clone().into_iter().chain([name]).collect(), meta)
+                })
+                .collect();
+            if dst.is_dir() {
+                return Ok((false, children));
+            }
+            fs::create_dir_all(&dst)
+                .with_context(|| format!("failed to create `{}`", dst.display()))?;
+            Ok((true, children))
+        } else if file_matches(&dst, meta)? {
+            Ok((false, vec![]))
+        } else {
+            check_limit(meta.size, limit).with_context(|| format!("file `{path}` is too large"))?;
+            let parent = dst.parent().unwrap_or(dest);
+            let mut file = tempfile::Builder::new()
+                .prefix(".drawbridge-")
+                .tempfile_in(parent)
+                .with_context(|| {
+                    format!("failed to create temporary file in `{}`", parent.display())
+                })?;
+            let (_, rdr) = node
+                .get(meta.size)
+                .with_context(|| format!("failed to get file `{path}`"))?;
+            let n = copy(&mut meta.hash.clone().verifier(rdr), &mut file)
+                .with_context(|| format!("failed to download file `{path}`"))?;
+            ensure!(
+                n == meta.size,
+                "file `{path}` size does not match its entry"
+            );
+            _ = file
+                .persist(&dst)
+                .with_context(|| format!("failed to write `{}`", dst.display()))?;
+            Ok((true, vec![]))
+        }
+    }
+
+    /// Returns `true` if all tree nodes declared by the tag are uploaded. Tags, which are not
+    /// sealed, are hidden from tag listings by default.
+    pub fn is_sealed(&self) -> Result<bool> {
+        self.0.head_header(SEALED_HEADER)
+    }
+
+    /// Returns the amount of tree nodes, which are not uploaded yet, but declared by an uploaded
+    /// directory or, for the root, by the tag entry.
+    pub fn missing_nodes(&self) -> Result<usize> {
+        self.0.head_header(MISSING_NODES_HEADER)
+    }
+
+    /// Yanks the tag, which hides it from tag listings, and returns its yank state.
+    pub fn yank(&self) -> Result<TagYank> {
+        self.0.child::<scope::Unknown>("yank").put_empty_json()
+    }
+
+    /// Deletes the tag.
+    pub fn delete(&self) -> Result<()> {
+        self.0.delete()
     }
 
     pub fn path(&self, path: &TreePath) -> Node<'a, S> {
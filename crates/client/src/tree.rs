@@ -3,14 +3,78 @@
 
 use super::{scope, Entity, Result, Scope};
 
-use std::io::Read;
+use std::collections::BTreeMap;
+use std::fmt::{self, Display};
+use std::io::{Read, Seek};
+use std::num::NonZeroUsize;
 use std::ops::Deref;
+use std::sync::{Mutex, PoisonError};
+use std::thread;
 
 use drawbridge_type::{Meta, TreeDirectory, TreeEntry, TreePath};
 
 use mime::Mime;
 use ureq::serde::Serialize;
 
+/// Failures of operations on individual tree nodes, keyed by path of the node.
+#[derive(Debug)]
+pub struct TreeErrors(pub BTreeMap<TreePath, anyhow::Error>);
+
+impl Display for TreeErrors {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "failed to process {} tree node(s)", self.0.len())?;
+        for (path, e) in &self.0 {
+            write!(f, "\n`{path}`: {e:#}")?;
+        }
+        Ok(())
+    }
+}
+
+impl std::error::Error for TreeErrors {}
+
+/// Applies `f` to all `nodes` using up to `parallelism` threads, including the current one.
+/// Returns the results keyed by path or [TreeErrors] holding all failures.
+pub(super) fn for_each_node<T: Send, R: Send>(
+    parallelism: NonZeroUsize,
+    nodes: impl IntoIterator<Item = (TreePath, T)>,
+    f: impl Sync + Fn(&TreePath, T) -> Result<R>,
+) -> Result<BTreeMap<TreePath, R>> {
+    let nodes = nodes.into_iter().collect::<Vec<_>>();
+    let workers = parallelism.get().min(nodes.len());
+    let queue = Mutex::new(nodes.into_iter());
+    let results = Mutex::new(BTreeMap::new());
+    let work = || loop {
+        let Some((path, node)) = queue.lock().unwrap_or_else(PoisonError::into_inner).next() else {
+            break;
+        };
+        let res = f(&path, node);
+        _ = results
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .insert(path, res);
+    };
+    thread::scope(|s| {
+        for _ in 1..workers {
+            _ = s.spawn(work);
+        }
+        work();
+    });
+
+    let mut done = BTreeMap::new();
+    let mut failed = BTreeMap::new();
+    for (path, res) in results.into_inner().unwrap_or_else(PoisonError::into_inner) {
+        match res {
+            Ok(v) => _ = done.insert(path, v),
+            Err(e) => _ = failed.insert(path, e),
+        }
+    }
+    if failed.is_empty() {
+        Ok(done)
+    } else {
+        Err(TreeErrors(failed).into())
+    }
+}
+
 #[derive(Clone, Debug)]
 pub struct Node<'a, S: Scope>(Entity<'a, S, scope::Node>);
 
@@ -25,9 +89,9 @@ impl<'a, S: Scope> Deref for Node<'a, S> {
 impl<'a, S: Scope> Node<'a, S> {
     pub fn new(entity: Entity<'a, S, scope::Node>, path: &TreePath) -> Self {
         if path.is_empty() {
-            Self(entity)
+            Self(entity.tree_node(path))
         } else {
-            Self(entity.child(&path.to_string()))
+            Self(entity.child(&path.to_string()).tree_node(path))
         }
     }
 
@@ -43,6 +107,18 @@ impl<'a, S: Scope> Node<'a, S> {
         self.0.create_from(meta, rdr)
     }
 
+    /// Like [Self::create_from], but retries transient failures according to the retry policy of
+    /// the client, seeking `rdr` back to its initial position before each attempt.
+    pub fn create_from_seekable(&self, meta: &Meta, rdr: impl Read + Seek) -> Result<bool> {
+        self.0.create_from_seekable(meta, rdr)
+    }
+
+    /// Creates the node referring to contents already stored in the repository, without
+    /// uploading them.
+    pub fn create_from_blob(&self, meta: &Meta) -> Result<bool> {
+        self.0.create_from_blob(meta)
+    }
+
     pub fn create_directory<C>(&self, dir: &TreeDirectory<TreeEntry<C>>) -> Result<bool> {
         let mime = TreeDirectory::<C>::TYPE
             .parse()
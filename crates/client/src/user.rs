@@ -1,11 +1,11 @@
 // SPDX-FileCopyrightText: 2022 Profian Inc. <opensource@profian.com>
 // SPDX-License-Identifier: Apache-2.0
 
-use super::{scope, Entity, Repository, Result, Scope};
+use super::{scope, Entity, Repository, Result, Scope, JSON_TYPES};
 
 use std::ops::Deref;
 
-use drawbridge_type::{RepositoryName, UserName, UserRecord};
+use drawbridge_type::{RepositoryEntry, RepositoryName, UserName, UserRecord};
 
 use mime::APPLICATION_JSON;
 
@@ -31,8 +31,28 @@ impl<'a, S: Scope> User<'a, S> {
     }
 
     pub fn get(&self) -> Result<UserRecord> {
-        // TODO: Use a reasonable byte limit
-        self.0.get_json(u64::MAX).map(|(_, v)| v)
+        self.0
+            .clone()
+            .accept(JSON_TYPES)
+            .get_json(self.0.limits().user)
+            .map(|(_, v)| v)
+    }
+
+    /// Deletes the user, which must not own any repositories.
+    pub fn delete(&self) -> Result<()> {
+        self.0.delete()
+    }
+
+    /// Returns names and configs of repositories of the user sorted by name.
+    ///
+    /// Private repositories are only listed for the owner and clients presenting a trusted
+    /// certificate.
+    pub fn repositories(&self) -> Result<Vec<RepositoryEntry>> {
+        self.0
+            .child::<scope::Unknown>("_repos")
+            .accept(JSON_TYPES)
+            .get_json(self.0.limits().listing)
+            .map(|(_, v)| v)
     }
 
     pub fn repository(&self, name: &RepositoryName) -> Repository<'a, S> {
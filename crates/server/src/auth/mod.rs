@@ -15,6 +15,7 @@ use axum::extract::RequestParts;
 use axum::http::Request;
 use axum::response::IntoResponse;
 
+#[allow(clippy::result_large_err)]
 pub async fn assert_repository_read<'a>(
     store: &'a Store,
     cx: &'a RepositoryContext,
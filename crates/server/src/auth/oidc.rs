@@ -184,6 +184,7 @@ impl Claims {
 
     /// Assert that the client is the user identified by `cx`, and that the token has a scope that
     /// satisfies the given context and level.
+    #[allow(clippy::result_large_err)]
     pub async fn assert_user<'a>(
         &self,
         store: &'a Store,
@@ -0,0 +1,39 @@
+// SPDX-FileCopyrightText: 2022 Profian Inc. <opensource@profian.com>
+// SPDX-License-Identifier: Apache-2.0
+
+use super::super::Store;
+use crate::auth::assert_repository_read;
+
+use drawbridge_type::digest::ContentDigest;
+use drawbridge_type::RepositoryContext;
+
+use async_std::sync::Arc;
+use axum::body::Body;
+use axum::http::Request;
+use axum::response::IntoResponse;
+use axum::{Extension, TypedHeader};
+use tracing::{debug, trace};
+
+/// Checks whether the repository holds contents with the digest specified in the
+/// `Content-Digest` request header.
+pub async fn head(
+    Extension(ref store): Extension<Arc<Store>>,
+    cx: RepositoryContext,
+    TypedHeader(hash): TypedHeader<ContentDigest>,
+    req: Request<Body>,
+) -> impl IntoResponse {
+    trace!(target: "app::blobs::head", "called for `{cx}`");
+
+    assert_repository_read(store, &cx, req)
+        .await
+        .map_err(IntoResponse::into_response)
+        .map(|(repo, _)| repo)?
+        .blobs()
+        .find(&hash)
+        .await
+        .map_err(|e| {
+            debug!(target: "app::blobs::head", "failed for `{cx}`: {:?}", e);
+            e.into_response()
+        })
+        .map(|meta| (meta, ()))
+}
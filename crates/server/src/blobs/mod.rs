@@ -0,0 +1,5 @@
+// SPDX-FileCopyrightText: 2022 Profian Inc. <opensource@profian.com>
+// SPDX-License-Identifier: Apache-2.0
+mod head;
+
+pub use head::*;
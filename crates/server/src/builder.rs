@@ -1,19 +1,15 @@
 // SPDX-FileCopyrightText: 2022 Profian Inc. <opensource@profian.com>
 // SPDX-License-Identifier: Apache-2.0
 
-use super::{handle, App, Store, TlsConfig};
+use super::{handle, App, StorageConfig, Store, TlsConfig};
 use std::ops::Deref;
 
-use anyhow::{anyhow, Context};
-use async_std::fs::File;
-use async_std::path::Path;
+use anyhow::Context;
 use async_std::sync::Arc;
 use axum::handler::Handler;
 use axum::routing::any;
 use axum::{Extension, Router};
-use cap_async_std::fs_utf8::Dir;
 use futures::lock::Mutex;
-use futures::TryFutureExt;
 use futures_rustls::TlsAcceptor;
 use openidconnect::url::Url;
 use tower_http::{
@@ -66,8 +62,8 @@ impl<S: std::fmt::Debug> std::fmt::Debug for Builder<S> {
     }
 }
 
-impl<S: AsRef<Path>> Builder<S> {
-    /// Constructs a new [Builder].
+impl<S: Into<StorageConfig>> Builder<S> {
+    /// Constructs a new [Builder], which stores data in the storage backend configured by `store`.
     pub fn new(store: S, tls: TlsConfig, oidc: OidcConfig) -> Self {
         Self { store, tls, oidc }
     }
@@ -75,14 +71,8 @@ impl<S: AsRef<Path>> Builder<S> {
     /// Builds the application and returns Drawbridge instance as a [tower::MakeService].
     pub async fn build(self) -> anyhow::Result<App> {
         let Self { store, tls, oidc } = self;
-        let store_path = store.as_ref();
-        let store = File::open(store_path)
-            .and_then(|f| Store::new(Dir::from_std_file(f)))
-            .await
-            .context(anyhow!(
-                "failed to open store at `{}`",
-                store_path.to_string_lossy()
-            ))?;
+        let store = Store::open(store).await?;
+        store.migrate().await.context("failed to migrate store")?;
 
         let oidc_verifier =
             crate::auth::OidcVerifier::new(oidc).context("failed to create OIDC verifier")?;
@@ -0,0 +1,80 @@
+// SPDX-FileCopyrightText: 2022 Profian Inc. <opensource@profian.com>
+// SPDX-License-Identifier: Apache-2.0
+
+use axum::extract::{FromRequest, RequestParts};
+use axum::headers::{ETag, IfMatch, IfNoneMatch};
+use axum::http::StatusCode;
+use axum::response::{IntoResponse, Response};
+use axum::{async_trait, TypedHeader};
+use tracing::trace;
+
+/// `If-Match` and `If-None-Match` preconditions of a request.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Preconditions {
+    if_match: Option<IfMatch>,
+    if_none_match: Option<IfNoneMatch>,
+}
+
+impl Preconditions {
+    /// Returns `true` if the request carries an `If-Match` precondition.
+    pub fn has_if_match(&self) -> bool {
+        self.if_match.is_some()
+    }
+
+    /// Returns `true` unless `If-Match` is set and does not match `etag` of the current
+    /// representation.
+    pub fn if_match_passes(&self, etag: &ETag) -> bool {
+        match self.if_match {
+            Some(ref if_match) if !if_match.precondition_passes(etag) => {
+                trace!(target: "app::Preconditions::if_match_passes", "`If-Match` failed for {etag:?}");
+                false
+            }
+            _ => true,
+        }
+    }
+
+    /// Evaluates the preconditions against `etag` of the selected representation and responds
+    /// with `res` tagged by `etag` if they pass.
+    ///
+    /// Responds with `412 Precondition Failed` if `If-Match` does not match `etag` and
+    /// `304 Not Modified` if `If-None-Match` does.
+    pub fn respond(&self, etag: ETag, res: impl IntoResponse) -> Response {
+        match self {
+            Self {
+                if_match: Some(if_match),
+                ..
+            } if !if_match.precondition_passes(&etag) => {
+                trace!(target: "app::Preconditions::respond", "`If-Match` failed for {etag:?}");
+                (StatusCode::PRECONDITION_FAILED, "Precondition failed").into_response()
+            }
+            Self {
+                if_none_match: Some(if_none_match),
+                ..
+            } if !if_none_match.precondition_passes(&etag) => {
+                trace!(target: "app::Preconditions::respond", "`If-None-Match` matched {etag:?}");
+                (StatusCode::NOT_MODIFIED, TypedHeader(etag)).into_response()
+            }
+            _ => (TypedHeader(etag), res).into_response(),
+        }
+    }
+}
+
+#[async_trait]
+impl<B: Send> FromRequest<B> for Preconditions {
+    type Rejection = Response;
+
+    async fn from_request(req: &mut RequestParts<B>) -> Result<Self, Self::Rejection> {
+        let if_match = req
+            .extract::<Option<TypedHeader<IfMatch>>>()
+            .await
+            .map_err(IntoResponse::into_response)?;
+        let if_none_match = req
+            .extract::<Option<TypedHeader<IfNoneMatch>>>()
+            .await
+            .map_err(IntoResponse::into_response)?;
+        Ok(Self {
+            if_match: if_match.map(|TypedHeader(h)| h),
+            if_none_match: if_none_match.map(|TypedHeader(h)| h),
+        })
+    }
+}
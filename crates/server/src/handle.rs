@@ -1,7 +1,7 @@
 // SPDX-FileCopyrightText: 2022 Profian Inc. <opensource@profian.com>
 // SPDX-License-Identifier: Apache-2.0
 
-use super::{repos, tags, trees, users};
+use super::{blobs, repos, tags, trees, users};
 
 use drawbridge_type::{RepositoryName, TagName, TreePath, UserName};
 
@@ -10,6 +10,8 @@ use axum::handler::Handler;
 use axum::http::{Method, Request, StatusCode};
 use axum::response::IntoResponse;
 use once_cell::sync::Lazy;
+use percent_encoding::percent_decode_str;
+use semver::VersionReq;
 use tower::Service;
 use tracing::trace;
 
@@ -76,14 +78,25 @@ pub(crate) async fn handle(mut req: Request<Body>) -> impl IntoResponse {
     trace!(target: "app::handle", "parsed user name: `{user}`");
     assert_eq!(extensions.insert(user), None, "duplicate user name");
     if head.is_empty() {
-        return match *req.method() {
-            Method::HEAD => Ok(users::head.into_service().call(req).await.into_response()),
-            Method::GET => Ok(users::get.into_service().call(req).await.into_response()),
-            Method::PUT => Ok(users::put.into_service().call(req).await.into_response()),
-            _ => Err((
+        return match (tail.as_str(), req.method()) {
+            ("", &Method::HEAD) => Ok(users::head.into_service().call(req).await.into_response()),
+            ("", &Method::GET) => Ok(users::get.into_service().call(req).await.into_response()),
+            ("", &Method::PUT) => Ok(users::put.into_service().call(req).await.into_response()),
+            ("", &Method::DELETE) => {
+                Ok(users::delete.into_service().call(req).await.into_response())
+            }
+            ("", _) => Err((
                 StatusCode::METHOD_NOT_ALLOWED,
                 "Method not allowed for user endpoint".into(),
             )),
+            ("_repos", &Method::GET) => {
+                Ok(users::repos.into_service().call(req).await.into_response())
+            }
+            ("_repos", _) => Err((
+                StatusCode::METHOD_NOT_ALLOWED,
+                "Method not allowed for user repository listing endpoint".into(),
+            )),
+            _ => Err((StatusCode::NOT_FOUND, "Route not found on user".into())),
         };
     }
 
@@ -102,11 +115,19 @@ pub(crate) async fn handle(mut req: Request<Body>) -> impl IntoResponse {
             Method::HEAD => Ok(repos::head.into_service().call(req).await.into_response()),
             Method::GET => Ok(repos::get.into_service().call(req).await.into_response()),
             Method::PUT => Ok(repos::put.into_service().call(req).await.into_response()),
+            Method::DELETE => Ok(repos::delete.into_service().call(req).await.into_response()),
             _ => Err((
                 StatusCode::METHOD_NOT_ALLOWED,
                 "Method not allowed for repository endpoint".into(),
             )),
         },
+        (Some("_blob"), None, None) => match *req.method() {
+            Method::HEAD => Ok(blobs::head.into_service().call(req).await.into_response()),
+            _ => Err((
+                StatusCode::METHOD_NOT_ALLOWED,
+                "Method not allowed for repository blob endpoint".into(),
+            )),
+        },
         (Some("_tag"), None, None) => match *req.method() {
             Method::GET => Ok(tags::query.into_service().call(req).await.into_response()),
             _ => Err((
@@ -114,7 +135,37 @@ pub(crate) async fn handle(mut req: Request<Body>) -> impl IntoResponse {
                 "Method not allowed for repository tag query endpoint".into(),
             )),
         },
-        (Some("_tag"), Some(tag), prop @ (None | Some("tree"))) => {
+        (Some("_resolve"), Some(version_req), None) => {
+            let version_req = percent_decode_str(version_req)
+                .decode_utf8()
+                .map_err(|e| {
+                    (
+                        StatusCode::BAD_REQUEST,
+                        format!("Failed to decode version requirement: {e}"),
+                    )
+                })?
+                .parse::<VersionReq>()
+                .map_err(|e| {
+                    (
+                        StatusCode::BAD_REQUEST,
+                        format!("Failed to parse version requirement: {e}"),
+                    )
+                })?;
+            trace!(target: "app::handle", "parsed version requirement: `{version_req}`");
+            assert_eq!(
+                extensions.insert(version_req),
+                None,
+                "duplicate version requirement"
+            );
+            match *req.method() {
+                Method::GET => Ok(tags::resolve.into_service().call(req).await.into_response()),
+                _ => Err((
+                    StatusCode::METHOD_NOT_ALLOWED,
+                    "Method not allowed for repository tag resolution endpoint".into(),
+                )),
+            }
+        }
+        (Some("_tag"), Some(tag), prop @ (None | Some("archive" | "tree" | "yank"))) => {
             let tag = tag.parse::<TagName>().map_err(|e| {
                 (
                     StatusCode::BAD_REQUEST,
@@ -129,12 +180,57 @@ pub(crate) async fn handle(mut req: Request<Body>) -> impl IntoResponse {
                     Method::HEAD => Ok(tags::head.into_service().call(req).await.into_response()),
                     Method::GET => Ok(tags::get.into_service().call(req).await.into_response()),
                     Method::PUT => Ok(tags::put.into_service().call(req).await.into_response()),
+                    Method::DELETE => {
+                        Ok(tags::delete.into_service().call(req).await.into_response())
+                    }
                     _ => Err((
                         StatusCode::METHOD_NOT_ALLOWED,
                         "Method not allowed for tag endpoint".into(),
                     )),
                 };
             }
+            if prop == Some("archive") {
+                return match (tail.next(), req.method()) {
+                    (None, &Method::GET) => Ok(tags::get_archive
+                        .into_service()
+                        .call(req)
+                        .await
+                        .into_response()),
+                    (None, &Method::PUT) => Ok(tags::put_archive
+                        .into_service()
+                        .call(req)
+                        .await
+                        .into_response()),
+                    (None, _) => Err((
+                        StatusCode::METHOD_NOT_ALLOWED,
+                        "Method not allowed for tag archive endpoint".into(),
+                    )),
+                    (Some(_), _) => Err((
+                        StatusCode::NOT_FOUND,
+                        "Route not found on tag archive endpoint".into(),
+                    )),
+                };
+            }
+            if prop == Some("yank") {
+                return match (tail.next(), req.method()) {
+                    (None, &Method::GET) => Ok(tags::get_yank
+                        .into_service()
+                        .call(req)
+                        .await
+                        .into_response()),
+                    (None, &Method::PUT) => {
+                        Ok(tags::yank.into_service().call(req).await.into_response())
+                    }
+                    (None, _) => Err((
+                        StatusCode::METHOD_NOT_ALLOWED,
+                        "Method not allowed for tag yank endpoint".into(),
+                    )),
+                    (Some(_), _) => Err((
+                        StatusCode::NOT_FOUND,
+                        "Route not found on tag yank endpoint".into(),
+                    )),
+                };
+            }
 
             let path = tail.next().unwrap_or("").parse::<TreePath>().map_err(|e| {
                 (
@@ -29,9 +29,11 @@
 )]
 
 mod builder;
+mod conditional;
 mod handle;
 
 pub mod auth;
+pub mod blobs;
 pub mod repos;
 pub mod store;
 pub mod tags;
@@ -40,13 +42,13 @@ pub mod users;
 
 pub use auth::{OidcClaims, ScopeContext, ScopeLevel, TlsConfig, TrustedCertificate};
 pub use builder::*;
+pub use conditional::*;
 pub(crate) use handle::*;
 pub(crate) use store::*;
 
 pub use openidconnect::url;
 
 use anyhow::Context as _;
-use async_std::path::Path;
 use axum::extract::Extension;
 use axum::routing::IntoMakeService;
 use axum::Router;
@@ -65,12 +67,16 @@ pub struct App {
 }
 
 impl App {
-    pub fn builder<S: AsRef<Path>>(store: S, tls: TlsConfig, oidc: OidcConfig) -> Builder<S> {
+    pub fn builder<S: Into<StorageConfig>>(
+        store: S,
+        tls: TlsConfig,
+        oidc: OidcConfig,
+    ) -> Builder<S> {
         Builder::new(store, tls, oidc)
     }
 
     pub async fn new(
-        store: impl AsRef<Path>,
+        store: impl Into<StorageConfig>,
         tls: TlsConfig,
         oidc: OidcConfig,
     ) -> anyhow::Result<Self> {
@@ -102,9 +108,21 @@ impl App {
             trace!(target: "app::App::handle", "add TrustedCertificate to extensions");
         }
         trace!(target: "app::App::handle", "begin HTTP request serving");
-        Http::new()
-            .serve_connection(stream.compat(), svc)
-            .await
-            .context("failed to handle request")
+        match Http::new().serve_connection(stream.compat(), svc).await {
+            // Clients commonly close the connection without sending TLS `close_notify`, which
+            // is harmless for HTTP, since message framing is handled by HTTP itself.
+            Err(e) if is_unexpected_eof(&e) => {
+                trace!(target: "app::App::handle", "peer closed connection: {e}");
+                Ok(())
+            }
+            res => res.context("failed to handle request"),
+        }
     }
 }
+
+/// Returns `true` if `e` was caused by the peer closing the stream unexpectedly.
+fn is_unexpected_eof(e: &(dyn 'static + std::error::Error)) -> bool {
+    std::iter::successors(Some(e), |e| e.source())
+        .filter_map(|e| e.downcast_ref::<std::io::Error>())
+        .any(|e| e.kind() == std::io::ErrorKind::UnexpectedEof)
+}
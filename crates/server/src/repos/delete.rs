@@ -0,0 +1,62 @@
+// SPDX-FileCopyrightText: 2022 Profian Inc. <opensource@profian.com>
+// SPDX-License-Identifier: Apache-2.0
+
+use super::super::{AuditAction, AuditEvent, OidcClaims, ScopeContext, ScopeLevel, Store};
+
+use drawbridge_type::repository::CONFIRM_DELETE_HEADER;
+use drawbridge_type::RepositoryContext;
+
+use async_std::sync::Arc;
+use axum::http::{HeaderMap, StatusCode};
+use axum::response::IntoResponse;
+use axum::Extension;
+use tracing::{debug, trace};
+
+/// Deletes the repository along with all of its tags, which must be confirmed by setting
+/// [CONFIRM_DELETE_HEADER] to the full name of the repository.
+pub async fn delete(
+    Extension(ref store): Extension<Arc<Store>>,
+    claims: OidcClaims,
+    cx: RepositoryContext,
+    headers: HeaderMap,
+) -> impl IntoResponse {
+    trace!(target: "app::repos::delete", "called for `{cx}`");
+
+    let user = claims
+        .assert_user(
+            store,
+            &cx.owner,
+            ScopeContext::Repository,
+            ScopeLevel::Write,
+        )
+        .await
+        .map_err(IntoResponse::into_response)?;
+
+    match headers.get(CONFIRM_DELETE_HEADER).map(|v| v.to_str()) {
+        Some(Ok(name)) if name == cx.to_string() => {}
+        _ => {
+            return Err((
+                StatusCode::PRECONDITION_REQUIRED,
+                format!("`{CONFIRM_DELETE_HEADER}` header must be set to `{cx}`"),
+            )
+                .into_response())
+        }
+    }
+
+    user.repository(&cx.name).delete().await.map_err(|e| {
+        debug!(target: "app::repos::delete", "failed for `{cx}`: {:?}", e);
+        e.into_response()
+    })?;
+    store
+        .record(&AuditEvent::new(
+            AuditAction::DeleteRepository,
+            &cx,
+            claims.subject(),
+        ))
+        .await
+        .map_err(|e| {
+            debug!(target: "app::repos::delete", "failed to record deletion of `{cx}`: {:?}", e);
+            e.into_response()
+        })
+        .map(|()| StatusCode::NO_CONTENT)
+}
@@ -1,7 +1,7 @@
 // SPDX-FileCopyrightText: 2022 Profian Inc. <opensource@profian.com>
 // SPDX-License-Identifier: Apache-2.0
 
-use super::super::{OidcClaims, ScopeContext, ScopeLevel, Store};
+use super::super::{OidcClaims, Preconditions, ScopeContext, ScopeLevel, Store};
 
 use drawbridge_type::RepositoryContext;
 
@@ -14,6 +14,7 @@ pub async fn get(
     Extension(ref store): Extension<Arc<Store>>,
     claims: OidcClaims,
     cx: RepositoryContext,
+    pre: Preconditions,
 ) -> impl IntoResponse {
     trace!(target: "app::trees::get", "called for `{cx}`");
 
@@ -22,15 +23,12 @@ pub async fn get(
         .await
         .map_err(IntoResponse::into_response)?;
 
-    // TODO: Stream body
-    // https://github.com/profianinc/drawbridge/issues/56
-    let mut body = vec![];
     user.repository(&cx.name)
-        .get_to_writer(&mut body)
+        .get_bytes_replaced()
         .await
         .map_err(|e| {
             debug!(target: "app::repos::get", "failed for `{cx}`: {:?}", e);
             e.into_response()
         })
-        .map(|meta| (meta, body))
+        .map(|(meta, buf)| pre.respond(meta.hash.etag(), (meta, buf)))
 }
@@ -1,7 +1,7 @@
 // SPDX-FileCopyrightText: 2022 Profian Inc. <opensource@profian.com>
 // SPDX-License-Identifier: Apache-2.0
 
-use super::super::{OidcClaims, ScopeContext, ScopeLevel, Store};
+use super::super::{OidcClaims, Preconditions, ScopeContext, ScopeLevel, Store};
 
 use drawbridge_type::RepositoryContext;
 
@@ -14,6 +14,7 @@ pub async fn head(
     Extension(ref store): Extension<Arc<Store>>,
     claims: OidcClaims,
     cx: RepositoryContext,
+    pre: Preconditions,
 ) -> impl IntoResponse {
     trace!(target: "app::trees::head", "called for `{cx}`");
 
@@ -28,5 +29,5 @@ pub async fn head(
             debug!(target: "app::repos::head", "failed for `{cx}`: {:?}", e);
             e.into_response()
         })
-        .map(|meta| (meta, ()))
+        .map(|meta| pre.respond(meta.hash.etag(), (meta, ())))
 }
@@ -1,9 +1,11 @@
 // SPDX-FileCopyrightText: 2022 Profian Inc. <opensource@profian.com>
 // SPDX-License-Identifier: Apache-2.0
+mod delete;
 mod get;
 mod head;
 mod put;
 
+pub use delete::*;
 pub use get::*;
 pub use head::*;
 pub use put::*;
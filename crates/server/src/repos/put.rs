@@ -1,7 +1,9 @@
 // SPDX-FileCopyrightText: 2022 Profian Inc. <opensource@profian.com>
 // SPDX-License-Identifier: Apache-2.0
 
-use super::super::{OidcClaims, ScopeContext, ScopeLevel, Store};
+use super::super::{
+    AuditAction, AuditEvent, OidcClaims, Preconditions, ScopeContext, ScopeLevel, Store,
+};
 
 use drawbridge_type::{Meta, RepositoryConfig, RepositoryContext};
 
@@ -11,16 +13,18 @@ use axum::response::IntoResponse;
 use axum::{Extension, Json};
 use tracing::{debug, trace};
 
+/// Creates the repository or, if `If-Match` is set, updates the configuration of the existing one.
 pub async fn put(
     Extension(ref store): Extension<Arc<Store>>,
     claims: OidcClaims,
     cx: RepositoryContext,
+    pre: Preconditions,
     meta: Meta,
     Json(config): Json<RepositoryConfig>,
 ) -> impl IntoResponse {
-    trace!(target: "app::trees::put", "called for `{cx}`");
+    trace!(target: "app::repos::put", "called for `{cx}`");
 
-    claims
+    let user = claims
         .assert_user(
             store,
             &cx.owner,
@@ -28,12 +32,42 @@ pub async fn put(
             ScopeLevel::Write,
         )
         .await
-        .map_err(IntoResponse::into_response)?
-        .create_repository(&cx.name, meta, &config)
+        .map_err(IntoResponse::into_response)?;
+
+    if !pre.has_if_match() {
+        return user
+            .create_repository(&cx.name, meta, &config)
+            .await
+            .map_err(|e| {
+                debug!(target: "app::repos::put", "failed for `{cx}`: {:?}", e);
+                e.into_response()
+            })
+            .map(|_| StatusCode::CREATED);
+    }
+
+    let repo = user.repository(&cx.name);
+    _ = repo.get_meta().await.map_err(|e| {
+        debug!(target: "app::repos::put", "failed to get metadata of `{cx}`: {:?}", e);
+        e.into_response()
+    })?;
+    repo.update(&meta, &config, |current| {
+        pre.if_match_passes(&current.hash.etag())
+    })
+    .await
+    .map_err(|e| {
+        debug!(target: "app::repos::put", "failed to update `{cx}`: {:?}", e);
+        e.into_response()
+    })?;
+    store
+        .record(&AuditEvent::new(
+            AuditAction::UpdateRepository,
+            &cx,
+            claims.subject(),
+        ))
         .await
         .map_err(|e| {
-            debug!(target: "app::repos::put", "failed for `{cx}`: {:?}", e);
+            debug!(target: "app::repos::put", "failed to record update of `{cx}`: {:?}", e);
             e.into_response()
         })
-        .map(|_| StatusCode::CREATED)
+        .map(|()| StatusCode::OK)
 }
@@ -0,0 +1,110 @@
+// SPDX-FileCopyrightText: 2022 Profian Inc. <opensource@profian.com>
+// SPDX-License-Identifier: Apache-2.0
+
+use super::{CreateError, Entity, GetError, Store};
+
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use anyhow::anyhow;
+use serde::{Deserialize, Serialize};
+use tracing::debug;
+use uuid::Uuid;
+
+/// Directory relative to the root of the store, which holds the audit trail.
+pub(super) const AUDIT_DIR: &str = "audit";
+
+/// Returns the current time in seconds since the Unix epoch.
+pub(super) fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or_default()
+}
+
+/// Action recorded in the audit trail.
+#[derive(Clone, Copy, Debug, Deserialize, Eq, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AuditAction {
+    YankTag,
+    DeleteTag,
+    UpdateRepository,
+    DeleteRepository,
+    DeleteUser,
+}
+
+/// Entry of the audit trail recording who performed an action on which entity and when.
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct AuditEvent {
+    pub action: AuditAction,
+
+    /// Entity the action was performed on, e.g. `user/repo:0.1.0` for tags and `user/repo` for
+    /// repositories.
+    pub target: String,
+
+    /// OpenID Connect identity subject of the user, who performed the action.
+    pub subject: String,
+
+    /// Time the action was performed at in seconds since the Unix epoch.
+    pub time: u64,
+}
+
+impl AuditEvent {
+    pub fn new(action: AuditAction, target: impl ToString, subject: impl Into<String>) -> Self {
+        Self {
+            action,
+            target: target.to_string(),
+            subject: subject.into(),
+            time: now(),
+        }
+    }
+}
+
+impl Store {
+    fn audit_trail(&self) -> Entity<'_, &'static str> {
+        Entity::new(self.root.as_ref())
+    }
+
+    /// Appends `event` to the audit trail.
+    pub async fn record(&self, event: &AuditEvent) -> Result<(), CreateError<anyhow::Error>> {
+        // Events recorded within the same second are ordered by the sub-second part of the name.
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default();
+        let name = format!(
+            "{:020}{:09}-{}.json",
+            now.as_secs(),
+            now.subsec_nanos(),
+            Uuid::new_v4()
+        );
+        self.audit_trail()
+            .create_file_json(format!("{AUDIT_DIR}/{name}"), event)
+            .await
+            .map_err(|e| {
+                debug!(target: "app::store::Store::record", "failed to record audit event: {:?}", e);
+                e
+            })
+    }
+
+    /// Returns all events of the audit trail in chronological order.
+    pub async fn audit(&self) -> Result<Vec<AuditEvent>, GetError<anyhow::Error>> {
+        let trail = self.audit_trail();
+        let mut names = trail.read_dir(AUDIT_DIR).await?;
+        names.sort();
+        let mut events = Vec::with_capacity(names.len());
+        for name in names {
+            let event = trail
+                .read_json(format!("{AUDIT_DIR}/{name}"))
+                .await
+                .map_err(|e| match e {
+                    GetError::NotFound => {
+                        GetError::Internal(anyhow!("audit event `{name}` disappeared"))
+                    }
+                    GetError::Internal(e) => GetError::Internal(
+                        e.context(format!("failed to read audit event `{name}`")),
+                    ),
+                })?;
+            events.push(event);
+        }
+        Ok(events)
+    }
+}
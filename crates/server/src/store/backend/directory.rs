@@ -0,0 +1,118 @@
+// SPDX-FileCopyrightText: 2022 Profian Inc. <opensource@profian.com>
+// SPDX-License-Identifier: Apache-2.0
+
+use super::{normalize, Reader, StorageBackend};
+
+use std::io;
+use std::os::unix::fs::DirBuilderExt;
+
+use async_std::fs::File;
+use async_std::path::Path;
+use axum::async_trait;
+use camino::{Utf8Path, Utf8PathBuf};
+use cap_async_std::fs_utf8::{Dir, DirBuilder, OpenOptions};
+use futures::io::copy;
+use futures::AsyncRead;
+
+/// [StorageBackend] storing entities in a directory on the local filesystem.
+#[derive(Debug)]
+pub struct Directory {
+    root: Dir,
+}
+
+impl From<Dir> for Directory {
+    fn from(root: Dir) -> Self {
+        Self { root }
+    }
+}
+
+impl Directory {
+    /// Opens the directory at `path`.
+    pub async fn open(path: impl AsRef<Path>) -> io::Result<Self> {
+        let file = File::open(path).await?;
+        Ok(Dir::from_std_file(file).into())
+    }
+
+    /// Returns the path of `path` relative to the root directory, which is `.` for the root.
+    fn path(path: &Utf8Path) -> Utf8PathBuf {
+        let path = normalize(path);
+        if path.as_str().is_empty() {
+            ".".into()
+        } else {
+            path
+        }
+    }
+}
+
+#[async_trait]
+impl StorageBackend for Directory {
+    async fn create_dir(&self, path: &Utf8Path) -> io::Result<()> {
+        self.root
+            .create_dir_with(Self::path(path), DirBuilder::new().mode(0o700))
+    }
+
+    async fn is_dir(&self, path: &Utf8Path) -> bool {
+        self.root.is_dir(Self::path(path)).await
+    }
+
+    async fn is_file(&self, path: &Utf8Path) -> bool {
+        self.root.is_file(Self::path(path)).await
+    }
+
+    async fn read_dir(&self, path: &Utf8Path) -> io::Result<Vec<String>> {
+        let mut names = vec![];
+        for entry in self.root.read_dir(Self::path(path)).await? {
+            names.push(entry?.file_name()?);
+        }
+        Ok(names)
+    }
+
+    async fn write(&self, path: &Utf8Path, buf: Vec<u8>) -> io::Result<()> {
+        self.root.write(Self::path(path), buf).await
+    }
+
+    async fn create_file(
+        &self,
+        path: &Utf8Path,
+        rdr: &mut (dyn Send + Unpin + AsyncRead),
+    ) -> io::Result<u64> {
+        let mut file = self
+            .root
+            .open_with(
+                Self::path(path),
+                OpenOptions::new().write(true).create_new(true),
+            )
+            .await?;
+        copy(rdr, &mut file).await
+    }
+
+    async fn read(&self, path: &Utf8Path) -> io::Result<Vec<u8>> {
+        self.root.read(Self::path(path)).await
+    }
+
+    async fn open(&self, path: &Utf8Path) -> io::Result<(u64, Box<dyn Reader>)> {
+        let file = self.root.open(Self::path(path)).await?;
+        let size = file.metadata()?.len();
+        Ok((size, Box::new(file)))
+    }
+
+    async fn rename_dir(&self, from: &Utf8Path, to: &Utf8Path) -> io::Result<()> {
+        self.root
+            .rename(Self::path(from), &self.root, Self::path(to))
+            .await
+    }
+
+    async fn rename_file(&self, from: &Utf8Path, to: &Utf8Path) -> io::Result<()> {
+        self.root
+            .rename(Self::path(from), &self.root, Self::path(to))
+            .await
+    }
+
+    async fn remove_dir_all(&self, path: &Utf8Path) -> io::Result<()> {
+        self.root.remove_dir_all(Self::path(path)).await
+    }
+
+    async fn remove_file(&self, path: &Utf8Path) -> io::Result<()> {
+        self.root.remove_file(Self::path(path)).await
+    }
+}
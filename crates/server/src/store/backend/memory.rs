@@ -0,0 +1,219 @@
+// SPDX-FileCopyrightText: 2022 Profian Inc. <opensource@profian.com>
+// SPDX-License-Identifier: Apache-2.0
+
+use super::{already_exists, normalize, not_found, Reader, StorageBackend};
+
+use std::collections::BTreeMap;
+use std::io;
+use std::sync::{Arc, Mutex, MutexGuard};
+
+use axum::async_trait;
+use camino::{Utf8Path, Utf8PathBuf};
+use futures::io::{copy, Cursor};
+use futures::AsyncRead;
+
+#[derive(Clone, Debug)]
+enum Node {
+    Directory,
+    File(Arc<[u8]>),
+}
+
+/// [StorageBackend] storing entities in memory of the process, primarily useful for testing.
+#[derive(Debug)]
+pub struct Memory {
+    nodes: Mutex<BTreeMap<Utf8PathBuf, Node>>,
+}
+
+impl Default for Memory {
+    fn default() -> Self {
+        Self {
+            nodes: Mutex::new([("".into(), Node::Directory)].into()),
+        }
+    }
+}
+
+type Nodes<'a> = MutexGuard<'a, BTreeMap<Utf8PathBuf, Node>>;
+
+/// Returns paths of all descendants of `path` in `nodes`.
+fn descendants(nodes: &Nodes<'_>, path: &Utf8Path) -> Vec<Utf8PathBuf> {
+    nodes
+        .keys()
+        .filter(|p| p.as_path() != path && p.starts_with(path))
+        .cloned()
+        .collect()
+}
+
+/// Fails unless the parent of `path` is a directory in `nodes`.
+fn check_parent(nodes: &Nodes<'_>, path: &Utf8Path) -> io::Result<()> {
+    match path.parent().map(|p| nodes.get(p)) {
+        Some(Some(Node::Directory)) => Ok(()),
+        Some(Some(Node::File(..))) => Err(io::Error::other("parent is not a directory")),
+        _ => Err(not_found()),
+    }
+}
+
+impl Memory {
+    fn nodes(&self) -> Nodes<'_> {
+        self.nodes.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    fn get(&self, path: &Utf8Path) -> Option<Node> {
+        self.nodes().get(&normalize(path)).cloned()
+    }
+
+    fn file(&self, path: &Utf8Path) -> io::Result<Arc<[u8]>> {
+        match self.get(path) {
+            Some(Node::File(buf)) => Ok(buf),
+            Some(Node::Directory) => Err(io::Error::other("path is a directory")),
+            None => Err(not_found()),
+        }
+    }
+
+    fn insert(&self, path: &Utf8Path, node: Node, replace: bool) -> io::Result<()> {
+        let path = normalize(path);
+        let mut nodes = self.nodes();
+        check_parent(&nodes, &path)?;
+        match nodes.get(&path) {
+            Some(Node::File(..)) if replace && matches!(node, Node::File(..)) => {}
+            Some(..) => return Err(already_exists()),
+            None => {}
+        }
+        _ = nodes.insert(path, node);
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl StorageBackend for Memory {
+    async fn create_dir(&self, path: &Utf8Path) -> io::Result<()> {
+        self.insert(path, Node::Directory, false)
+    }
+
+    async fn is_dir(&self, path: &Utf8Path) -> bool {
+        matches!(self.get(path), Some(Node::Directory))
+    }
+
+    async fn is_file(&self, path: &Utf8Path) -> bool {
+        matches!(self.get(path), Some(Node::File(..)))
+    }
+
+    async fn read_dir(&self, path: &Utf8Path) -> io::Result<Vec<String>> {
+        let path = normalize(path);
+        let nodes = self.nodes();
+        match nodes.get(&path) {
+            Some(Node::Directory) => Ok(descendants(&nodes, &path)
+                .into_iter()
+                .filter(|p| p.parent() == Some(&path))
+                .filter_map(|p| p.file_name().map(Into::into))
+                .collect()),
+            Some(Node::File(..)) => Err(io::Error::other("path is not a directory")),
+            None => Err(not_found()),
+        }
+    }
+
+    async fn write(&self, path: &Utf8Path, buf: Vec<u8>) -> io::Result<()> {
+        self.insert(path, Node::File(buf.into()), true)
+    }
+
+    async fn create_file(
+        &self,
+        path: &Utf8Path,
+        rdr: &mut (dyn Send + Unpin + AsyncRead),
+    ) -> io::Result<u64> {
+        // Claim the path first, so that concurrent creation fails early.
+        self.insert(path, Node::File(Arc::new([])), false)?;
+        let mut buf = vec![];
+        let n = match copy(rdr, &mut buf).await {
+            Ok(n) => n,
+            Err(e) => {
+                _ = self.nodes().remove(&normalize(path));
+                return Err(e);
+            }
+        };
+        _ = self.nodes().insert(normalize(path), Node::File(buf.into()));
+        Ok(n)
+    }
+
+    async fn read(&self, path: &Utf8Path) -> io::Result<Vec<u8>> {
+        self.file(path).map(|buf| buf.to_vec())
+    }
+
+    async fn open(&self, path: &Utf8Path) -> io::Result<(u64, Box<dyn Reader>)> {
+        let buf = self.file(path)?;
+        Ok((buf.len() as _, Box::new(Cursor::new(buf))))
+    }
+
+    async fn rename_dir(&self, from: &Utf8Path, to: &Utf8Path) -> io::Result<()> {
+        let (from, to) = (normalize(from), normalize(to));
+        let mut nodes = self.nodes();
+        if !matches!(nodes.get(&from), Some(Node::Directory)) {
+            return Err(not_found());
+        }
+        check_parent(&nodes, &to)?;
+        match nodes.get(&to) {
+            Some(Node::Directory) if descendants(&nodes, &to).is_empty() => {}
+            Some(Node::Directory) => return Err(io::ErrorKind::DirectoryNotEmpty.into()),
+            Some(Node::File(..)) => return Err(already_exists()),
+            None => {}
+        }
+        let mut moved = descendants(&nodes, &from);
+        moved.push(from.clone());
+        for path in moved {
+            if let Some(node) = nodes.remove(&path) {
+                let path = path
+                    .strip_prefix(&from)
+                    .expect("descendant path must start with prefix");
+                _ = nodes.insert(to.join(path), node);
+            }
+        }
+        Ok(())
+    }
+
+    async fn rename_file(&self, from: &Utf8Path, to: &Utf8Path) -> io::Result<()> {
+        let (from, to) = (normalize(from), normalize(to));
+        let mut nodes = self.nodes();
+        check_parent(&nodes, &to)?;
+        if matches!(nodes.get(&to), Some(Node::Directory)) {
+            return Err(io::Error::other("destination is a directory"));
+        }
+        match nodes.remove(&from) {
+            Some(node @ Node::File(..)) => {
+                _ = nodes.insert(to, node);
+                Ok(())
+            }
+            Some(node) => {
+                _ = nodes.insert(from, node);
+                Err(io::Error::other("path is a directory"))
+            }
+            None => Err(not_found()),
+        }
+    }
+
+    async fn remove_dir_all(&self, path: &Utf8Path) -> io::Result<()> {
+        let path = normalize(path);
+        let mut nodes = self.nodes();
+        match nodes.get(&path) {
+            Some(Node::Directory) => {}
+            Some(Node::File(..)) => return Err(io::Error::other("path is not a directory")),
+            None => return Err(not_found()),
+        }
+        for path in descendants(&nodes, &path) {
+            _ = nodes.remove(&path);
+        }
+        _ = nodes.remove(&path);
+        Ok(())
+    }
+
+    async fn remove_file(&self, path: &Utf8Path) -> io::Result<()> {
+        let path = normalize(path);
+        let mut nodes = self.nodes();
+        match nodes.get(&path) {
+            Some(Node::File(..)) => {
+                _ = nodes.remove(&path);
+                Ok(())
+            }
+            Some(Node::Directory) => Err(io::Error::other("path is a directory")),
+            None => Err(not_found()),
+        }
+    }
+}
@@ -0,0 +1,142 @@
+// SPDX-FileCopyrightText: 2022 Profian Inc. <opensource@profian.com>
+// SPDX-License-Identifier: Apache-2.0
+mod directory;
+mod memory;
+mod s3;
+
+pub use directory::Directory;
+pub use memory::Memory;
+pub use s3::{Config as S3Config, S3};
+
+use std::fmt::Debug;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use anyhow::Context;
+use axum::async_trait;
+use camino::{Utf8Path, Utf8PathBuf};
+use futures::{AsyncRead, AsyncSeek};
+
+/// Seekable reader of file contents returned by a [StorageBackend].
+pub trait Reader: Send + Unpin + AsyncRead + AsyncSeek {}
+
+impl<T: Send + Unpin + AsyncRead + AsyncSeek> Reader for T {}
+
+/// Hierarchy of directories and files the [Store](super::Store) is laid out in.
+///
+/// All paths are relative to the root directory of the backend, which always exists.
+/// Failures are reported by [io::ErrorKind], in particular [io::ErrorKind::NotFound] for
+/// missing paths and [io::ErrorKind::AlreadyExists] for occupied ones.
+#[allow(clippy::double_must_use)] // `async_trait` marks methods returning futures `#[must_use]`
+#[async_trait]
+pub trait StorageBackend: Debug + Send + Sync {
+    /// Creates a directory at `path`. The parent directory must exist.
+    async fn create_dir(&self, path: &Utf8Path) -> io::Result<()>;
+
+    /// Returns `true` if a directory exists at `path`.
+    async fn is_dir(&self, path: &Utf8Path) -> bool;
+
+    /// Returns `true` if a file exists at `path`.
+    async fn is_file(&self, path: &Utf8Path) -> bool;
+
+    /// Returns names of all entries of the directory at `path`.
+    async fn read_dir(&self, path: &Utf8Path) -> io::Result<Vec<String>>;
+
+    /// Writes `buf` to the file at `path`, replacing it if it exists.
+    async fn write(&self, path: &Utf8Path, buf: Vec<u8>) -> io::Result<()>;
+
+    /// Creates a file at `path` containing bytes read from `rdr` and returns their amount.
+    async fn create_file(
+        &self,
+        path: &Utf8Path,
+        rdr: &mut (dyn Send + Unpin + AsyncRead),
+    ) -> io::Result<u64>;
+
+    /// Reads the file at `path`.
+    async fn read(&self, path: &Utf8Path) -> io::Result<Vec<u8>>;
+
+    /// Opens the file at `path` and returns its length and a reader of its contents.
+    async fn open(&self, path: &Utf8Path) -> io::Result<(u64, Box<dyn Reader>)>;
+
+    /// Moves the directory at `from` to `to`, which must not exist or be an empty directory.
+    ///
+    /// `to` must be claimed atomically, such that concurrent moves to `to` fail with
+    /// [io::ErrorKind::AlreadyExists] or [io::ErrorKind::DirectoryNotEmpty].
+    ///
+    /// Backends, which cannot move directories atomically, like [S3], may expose the contents
+    /// at `to` one by one, but must move `meta.json` files last, such that entities appear only
+    /// once their other files are in place. If such a move fails, contents moved already are
+    /// removed from `to` on a best-effort basis, while contents at `from` are left in place.
+    async fn rename_dir(&self, from: &Utf8Path, to: &Utf8Path) -> io::Result<()>;
+
+    /// Moves the file at `from` to `to`, replacing the file at `to`, if any.
+    ///
+    /// Readers of `to` must observe either the replaced or the moved file.
+    async fn rename_file(&self, from: &Utf8Path, to: &Utf8Path) -> io::Result<()>;
+
+    /// Removes the directory at `path` along with all of its contents.
+    async fn remove_dir_all(&self, path: &Utf8Path) -> io::Result<()>;
+
+    /// Removes the file at `path`.
+    async fn remove_file(&self, path: &Utf8Path) -> io::Result<()>;
+}
+
+/// [StorageBackend] configuration.
+#[derive(Clone, Debug)]
+pub enum StorageConfig {
+    /// Directory on the local filesystem, see [Directory].
+    Directory(PathBuf),
+
+    /// Memory of the process, see [Memory].
+    Memory,
+
+    /// S3-compatible object store, see [S3].
+    S3(S3Config),
+}
+
+impl From<PathBuf> for StorageConfig {
+    fn from(path: PathBuf) -> Self {
+        Self::Directory(path)
+    }
+}
+
+impl From<&Path> for StorageConfig {
+    fn from(path: &Path) -> Self {
+        Self::Directory(path.into())
+    }
+}
+
+impl From<S3Config> for StorageConfig {
+    fn from(conf: S3Config) -> Self {
+        Self::S3(conf)
+    }
+}
+
+impl StorageConfig {
+    /// Opens the configured [StorageBackend].
+    pub async fn open(self) -> anyhow::Result<Box<dyn StorageBackend>> {
+        match self {
+            Self::Directory(path) => {
+                let dir = Directory::open(&path)
+                    .await
+                    .with_context(|| format!("failed to open directory `{}`", path.display()))?;
+                Ok(Box::new(dir))
+            }
+            Self::Memory => Ok(Box::<Memory>::default()),
+            Self::S3(conf) => Ok(Box::new(S3::new(conf))),
+        }
+    }
+}
+
+/// Returns `path` without trailing separators and `.` components.
+fn normalize(path: &Utf8Path) -> Utf8PathBuf {
+    path.components().collect()
+}
+
+fn not_found() -> io::Error {
+    io::ErrorKind::NotFound.into()
+}
+
+fn already_exists() -> io::Error {
+    io::ErrorKind::AlreadyExists.into()
+}
@@ -0,0 +1,819 @@
+// SPDX-FileCopyrightText: 2022 Profian Inc. <opensource@profian.com>
+// SPDX-License-Identifier: Apache-2.0
+
+use super::{already_exists, normalize, not_found, Reader, StorageBackend};
+
+use std::cmp::Reverse;
+use std::fmt::{self, Write as _};
+use std::future::Future;
+use std::io::{self, Read, SeekFrom};
+use std::pin::Pin;
+use std::sync::Arc;
+use std::task::{ready, Context, Poll};
+
+use async_std::task::{spawn_blocking, JoinHandle};
+use axum::async_trait;
+use camino::Utf8Path;
+use futures::{AsyncRead, AsyncReadExt, AsyncSeek};
+use hmac::{Hmac, Mac};
+use percent_encoding::{utf8_percent_encode, AsciiSet, NON_ALPHANUMERIC};
+use sha2::{Digest, Sha256};
+use tracing::trace;
+use ureq::{Agent, AgentBuilder};
+use url::Url;
+
+/// Characters percent-encoded in URIs signed using AWS Signature Version 4.
+const URI_ENCODE_SET: &AsciiSet = &NON_ALPHANUMERIC
+    .remove(b'-')
+    .remove(b'.')
+    .remove(b'_')
+    .remove(b'~');
+
+/// Name of the objects marking the existence of directories.
+const DIRECTORY_MARKER: &str = "/";
+
+/// Name of the files, which are copied last when moving directories, such that entities appear
+/// only once their contents are in place.
+const META_FILE: &str = "meta.json";
+
+/// Size of the parts of multipart uploads. Files not exceeding it are uploaded in one request.
+const PART_SIZE: u64 = 8 * 1024 * 1024;
+
+/// Maximum amount of bytes read from a response body at once by [ObjectReader].
+const CHUNK_SIZE: usize = 64 * 1024;
+
+/// [S3] configuration.
+#[derive(Clone)]
+pub struct Config {
+    /// Endpoint of the object store, e.g. `https://s3.eu-central-1.amazonaws.com`.
+    pub endpoint: Url,
+
+    /// Region the bucket is located in.
+    pub region: String,
+
+    /// Bucket to store entities in.
+    pub bucket: String,
+
+    /// Access key ID used to sign requests.
+    pub access_key: String,
+
+    /// Secret access key used to sign requests.
+    pub secret_key: String,
+}
+
+impl fmt::Debug for Config {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Config")
+            .field("endpoint", &self.endpoint.as_str())
+            .field("region", &self.region)
+            .field("bucket", &self.bucket)
+            .field("access_key", &self.access_key)
+            .finish_non_exhaustive()
+    }
+}
+
+/// [StorageBackend] storing entities as objects in a bucket of an S3-compatible object store.
+///
+/// Files are stored as objects keyed by their path and directories as empty objects keyed by
+/// their path followed by `/`. Objects are addressed using path-style URLs and requests are
+/// signed using AWS Signature Version 4.
+///
+/// The object store must support conditional writes using `If-None-Match: *`, which are used
+/// to create files and to claim the destination when moving directories. Files larger than
+/// 8 MiB are created using multipart uploads, hence only one part of them is buffered at a time.
+///
+/// Object stores cannot move objects, hence directories are moved by copying and then deleting
+/// their objects, see [StorageBackend::rename_dir].
+#[derive(Clone, Debug)]
+pub struct S3 {
+    conf: Arc<Config>,
+    agent: Agent,
+}
+
+/// Request to the object store.
+struct Request<'a> {
+    method: &'a str,
+    key: &'a str,
+    query: Vec<(&'a str, &'a str)>,
+    headers: Vec<(&'a str, String)>,
+    body: &'a [u8],
+}
+
+impl<'a> Request<'a> {
+    fn new(method: &'a str, key: &'a str) -> Self {
+        Self {
+            method,
+            key,
+            query: vec![],
+            headers: vec![],
+            body: &[],
+        }
+    }
+
+    fn query(mut self, name: &'a str, value: &'a str) -> Self {
+        self.query.push((name, value));
+        self
+    }
+
+    fn header(mut self, name: &'a str, value: impl Into<String>) -> Self {
+        self.headers.push((name, value.into()));
+        self
+    }
+
+    fn body(mut self, body: &'a [u8]) -> Self {
+        self.body = body;
+        self
+    }
+}
+
+fn hmac(key: &[u8], data: &str) -> Vec<u8> {
+    let mut mac = Hmac::<Sha256>::new_from_slice(key).expect("HMAC accepts keys of any length");
+    mac.update(data.as_bytes());
+    mac.finalize().into_bytes().to_vec()
+}
+
+fn uri_encode(s: &str) -> String {
+    utf8_percent_encode(s, URI_ENCODE_SET).to_string()
+}
+
+/// Returns the object key of `path`, which is followed by `/` for directories.
+fn key(path: &Utf8Path, dir: bool) -> String {
+    let path = normalize(path);
+    if dir && !path.as_str().is_empty() {
+        format!("{path}{DIRECTORY_MARKER}")
+    } else {
+        path.into_string()
+    }
+}
+
+/// Returns the unescaped text of all `name` elements in `xml`.
+fn elements<'a>(xml: &'a str, name: &str) -> Vec<&'a str> {
+    let (open, close) = (format!("<{name}>"), format!("</{name}>"));
+    let mut found = vec![];
+    let mut rest = xml;
+    while let Some(start) = rest.find(&open) {
+        rest = &rest[start + open.len()..];
+        let Some(end) = rest.find(&close) else { break };
+        found.push(&rest[..end]);
+        rest = &rest[end + close.len()..];
+    }
+    found
+}
+
+fn unescape(s: &str) -> String {
+    s.replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&quot;", "\"")
+        .replace("&apos;", "'")
+        .replace("&amp;", "&")
+}
+
+impl S3 {
+    pub fn new(conf: Config) -> Self {
+        Self {
+            conf: Arc::new(conf),
+            agent: AgentBuilder::new().build(),
+        }
+    }
+
+    /// Sends a signed request and returns the response, mapping error statuses to [io::Error].
+    fn send(&self, req: Request<'_>) -> io::Result<ureq::Response> {
+        let Config {
+            endpoint,
+            region,
+            bucket,
+            access_key,
+            secret_key,
+        } = self.conf.as_ref();
+
+        let path = format!(
+            "/{}/{}",
+            uri_encode(bucket),
+            req.key
+                .split('/')
+                .map(uri_encode)
+                .collect::<Vec<_>>()
+                .join("/")
+        );
+        let mut query: Vec<_> = req
+            .query
+            .iter()
+            .map(|(k, v)| (uri_encode(k), uri_encode(v)))
+            .collect();
+        query.sort();
+        let query = query
+            .into_iter()
+            .map(|(k, v)| format!("{k}={v}"))
+            .collect::<Vec<_>>()
+            .join("&");
+
+        let host = match (endpoint.host_str(), endpoint.port()) {
+            (Some(host), Some(port)) => format!("{host}:{port}"),
+            (Some(host), None) => host.into(),
+            (None, _) => {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidInput,
+                    "object store endpoint has no host",
+                ))
+            }
+        };
+        let now = chrono::Utc::now();
+        let date = now.format("%Y%m%d").to_string();
+        let timestamp = now.format("%Y%m%dT%H%M%SZ").to_string();
+        let payload_hash = hex::encode(Sha256::digest(req.body));
+
+        let mut signed = vec![
+            ("host".to_string(), host),
+            ("x-amz-content-sha256".into(), payload_hash.clone()),
+            ("x-amz-date".into(), timestamp.clone()),
+        ];
+        signed.extend(
+            req.headers
+                .iter()
+                .filter(|(name, _)| name.starts_with("x-amz-"))
+                .map(|(name, value)| (name.to_string(), value.trim().to_string())),
+        );
+        signed.sort();
+        let signed_headers = signed
+            .iter()
+            .map(|(name, _)| name.as_str())
+            .collect::<Vec<_>>()
+            .join(";");
+        let canonical_headers: String = signed
+            .iter()
+            .map(|(name, value)| format!("{name}:{value}\n"))
+            .collect();
+        let canonical_request = format!(
+            "{}\n{path}\n{query}\n{canonical_headers}\n{signed_headers}\n{payload_hash}",
+            req.method
+        );
+        let scope = format!("{date}/{region}/s3/aws4_request");
+        let string_to_sign = format!(
+            "AWS4-HMAC-SHA256\n{timestamp}\n{scope}\n{}",
+            hex::encode(Sha256::digest(canonical_request.as_bytes()))
+        );
+        let key = [date.as_str(), region, "s3", "aws4_request"]
+            .into_iter()
+            .fold(format!("AWS4{secret_key}").into_bytes(), |key, data| {
+                hmac(&key, data)
+            });
+        let signature = hex::encode(hmac(&key, &string_to_sign));
+
+        let mut url = endpoint.clone();
+        url.set_path(&path);
+        url.set_query((!query.is_empty()).then_some(query.as_str()));
+        trace!(target: "app::store::S3::send", "{} {url}", req.method);
+        let mut request = self
+            .agent
+            .request_url(req.method, &url)
+            .set("x-amz-content-sha256", &payload_hash)
+            .set("x-amz-date", &timestamp)
+            .set(
+                "authorization",
+                &format!(
+                    "AWS4-HMAC-SHA256 Credential={access_key}/{scope}, SignedHeaders={signed_headers}, Signature={signature}"
+                ),
+            );
+        for (name, value) in &req.headers {
+            request = request.set(name, value);
+        }
+        match request.send_bytes(req.body) {
+            Ok(res) => Ok(res),
+            Err(ureq::Error::Status(404, _)) => Err(not_found()),
+            Err(ureq::Error::Status(409 | 412, _)) => Err(already_exists()),
+            Err(ureq::Error::Status(code, res)) => Err(io::Error::other(format!(
+                "object store responded with status {code}: {}",
+                res.into_string().unwrap_or_default()
+            ))),
+            Err(e) => Err(io::Error::other(e)),
+        }
+    }
+
+    /// Returns `true` if an object keyed by `key` exists.
+    fn exists(&self, key: &str) -> io::Result<bool> {
+        if key.is_empty() {
+            return Ok(true);
+        }
+        match self.send(Request::new("HEAD", key)) {
+            Ok(_) => Ok(true),
+            Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(false),
+            Err(e) => Err(e),
+        }
+    }
+
+    /// Fails unless the parent directory of `path` exists.
+    fn check_parent(&self, path: &Utf8Path) -> io::Result<()> {
+        match path.parent() {
+            Some(parent) if self.exists(&key(parent, true))? => Ok(()),
+            _ => Err(not_found()),
+        }
+    }
+
+    fn get(&self, key: &str) -> io::Result<Vec<u8>> {
+        let mut buf = vec![];
+        _ = self
+            .send(Request::new("GET", key))?
+            .into_reader()
+            .read_to_end(&mut buf)?;
+        Ok(buf)
+    }
+
+    /// Creates an object keyed by `key` containing `body`, unless it exists.
+    fn create(&self, key: &str, body: &[u8]) -> io::Result<()> {
+        self.send(
+            Request::new("PUT", key)
+                .header("if-none-match", "*")
+                .body(body),
+        )
+        .map(|_| ())
+    }
+
+    fn delete(&self, key: &str) -> io::Result<()> {
+        self.send(Request::new("DELETE", key)).map(|_| ())
+    }
+
+    /// Copies the object keyed by `src` to `dst`, replacing the object keyed by `dst`, if any.
+    fn copy(&self, src: &str, dst: &str) -> io::Result<()> {
+        let src = format!(
+            "/{}/{}",
+            uri_encode(&self.conf.bucket),
+            src.split('/').map(uri_encode).collect::<Vec<_>>().join("/")
+        );
+        self.send(Request::new("PUT", dst).header("x-amz-copy-source", src))
+            .map(|_| ())
+    }
+
+    /// Starts a multipart upload of an object keyed by `key` and returns its ID.
+    fn start_upload(&self, key: &str) -> io::Result<String> {
+        let xml = self
+            .send(Request::new("POST", key).query("uploads", ""))?
+            .into_string()?;
+        elements(&xml, "UploadId")
+            .first()
+            .map(|id| unescape(id))
+            .ok_or_else(|| io::Error::other("object store did not return an upload ID"))
+    }
+
+    /// Uploads `body` as part `number` of the multipart upload `id` of `key` and returns the
+    /// `ETag` of the part.
+    fn upload_part(&self, key: &str, id: &str, number: usize, body: &[u8]) -> io::Result<String> {
+        let number = number.to_string();
+        self.send(
+            Request::new("PUT", key)
+                .query("partNumber", &number)
+                .query("uploadId", id)
+                .body(body),
+        )?
+        .header("etag")
+        .map(ToString::to_string)
+        .ok_or_else(|| io::Error::other("object store did not return an ETag of the part"))
+    }
+
+    /// Completes the multipart upload `id` of `key` consisting of parts with `etags`, unless an
+    /// object keyed by `key` exists.
+    fn complete_upload(&self, key: &str, id: &str, etags: &[String]) -> io::Result<()> {
+        let mut body = "<CompleteMultipartUpload>".to_string();
+        for (i, etag) in etags.iter().enumerate() {
+            let etag = etag.replace('&', "&amp;").replace('"', "&quot;");
+            _ = write!(
+                body,
+                "<Part><PartNumber>{}</PartNumber><ETag>{etag}</ETag></Part>",
+                i + 1
+            );
+        }
+        body.push_str("</CompleteMultipartUpload>");
+        let xml = self
+            .send(
+                Request::new("POST", key)
+                    .query("uploadId", id)
+                    .header("if-none-match", "*")
+                    .body(body.as_bytes()),
+            )?
+            .into_string()?;
+        // Failures may be reported in the body of a successful response
+        match elements(&xml, "Error").first() {
+            Some(err) if elements(err, "Code").first() == Some(&"PreconditionFailed") => {
+                Err(already_exists())
+            }
+            Some(err) => Err(io::Error::other(format!(
+                "object store failed to complete upload: {}",
+                unescape(err)
+            ))),
+            None => Ok(()),
+        }
+    }
+
+    /// Aborts the multipart upload `id` of `key`.
+    fn abort_upload(&self, key: &str, id: &str) -> io::Result<()> {
+        self.send(Request::new("DELETE", key).query("uploadId", id))
+            .map(|_| ())
+    }
+
+    /// Lists keys of objects starting with `prefix`. If `delimiter` is set, keys containing it
+    /// after `prefix` are rolled up into a single key ending with it.
+    fn list(&self, prefix: &str, delimiter: Option<&str>) -> io::Result<Vec<String>> {
+        let mut keys = vec![];
+        let mut token: Option<String> = None;
+        loop {
+            let mut req = Request::new("GET", "")
+                .query("list-type", "2")
+                .query("prefix", prefix);
+            if let Some(delimiter) = delimiter {
+                req = req.query("delimiter", delimiter);
+            }
+            if let Some(ref token) = token {
+                req = req.query("continuation-token", token);
+            }
+            let xml = self.send(req)?.into_string()?;
+            for contents in elements(&xml, "Contents") {
+                keys.extend(elements(contents, "Key").into_iter().map(unescape));
+            }
+            for prefixes in elements(&xml, "CommonPrefixes") {
+                keys.extend(elements(prefixes, "Prefix").into_iter().map(unescape));
+            }
+            match elements(&xml, "NextContinuationToken").first() {
+                Some(next) if elements(&xml, "IsTruncated").first() == Some(&"true") => {
+                    token = Some(unescape(next))
+                }
+                _ => return Ok(keys),
+            }
+        }
+    }
+
+    /// Runs `f` on a thread, on which blocking is allowed.
+    async fn blocking<T: Send + 'static>(
+        &self,
+        f: impl FnOnce(Self) -> io::Result<T> + Send + 'static,
+    ) -> io::Result<T> {
+        let s3 = self.clone();
+        spawn_blocking(move || f(s3)).await
+    }
+}
+
+#[async_trait]
+impl StorageBackend for S3 {
+    async fn create_dir(&self, path: &Utf8Path) -> io::Result<()> {
+        let path = normalize(path);
+        self.blocking(move |s3| {
+            s3.check_parent(&path)?;
+            if s3.exists(&key(&path, false))? {
+                return Err(already_exists());
+            }
+            s3.create(&key(&path, true), &[])
+        })
+        .await
+    }
+
+    async fn is_dir(&self, path: &Utf8Path) -> bool {
+        let key = key(path, true);
+        self.blocking(move |s3| s3.exists(&key))
+            .await
+            .unwrap_or(false)
+    }
+
+    async fn is_file(&self, path: &Utf8Path) -> bool {
+        let key = key(path, false);
+        !key.is_empty()
+            && self
+                .blocking(move |s3| s3.exists(&key))
+                .await
+                .unwrap_or(false)
+    }
+
+    async fn read_dir(&self, path: &Utf8Path) -> io::Result<Vec<String>> {
+        let prefix = key(path, true);
+        self.blocking(move |s3| {
+            if !s3.exists(&prefix)? {
+                return Err(not_found());
+            }
+            let mut names: Vec<_> = s3
+                .list(&prefix, Some(DIRECTORY_MARKER))?
+                .into_iter()
+                .filter_map(|key| {
+                    let name = key[prefix.len()..].trim_end_matches(DIRECTORY_MARKER);
+                    (!name.is_empty()).then(|| name.to_string())
+                })
+                .collect();
+            names.sort();
+            names.dedup();
+            Ok(names)
+        })
+        .await
+    }
+
+    async fn write(&self, path: &Utf8Path, buf: Vec<u8>) -> io::Result<()> {
+        let path = normalize(path);
+        self.blocking(move |s3| {
+            s3.check_parent(&path)?;
+            s3.send(Request::new("PUT", path.as_str()).body(&buf))
+                .map(|_| ())
+        })
+        .await
+    }
+
+    async fn create_file(
+        &self,
+        path: &Utf8Path,
+        rdr: &mut (dyn Send + Unpin + AsyncRead),
+    ) -> io::Result<u64> {
+        let path = normalize(path);
+        let mut buf = vec![];
+        let mut n = rdr.take(PART_SIZE).read_to_end(&mut buf).await? as u64;
+        if n < PART_SIZE {
+            self.blocking(move |s3| {
+                s3.check_parent(&path)?;
+                s3.create(path.as_str(), &buf)
+            })
+            .await?;
+            return Ok(n);
+        }
+
+        let key = key(&path, false);
+        let id = {
+            let key = key.clone();
+            self.blocking(move |s3| {
+                s3.check_parent(&path)?;
+                s3.start_upload(&key)
+            })
+            .await?
+        };
+        let res = async {
+            let mut etags = vec![];
+            while !buf.is_empty() {
+                let (key, id, number) = (key.clone(), id.clone(), etags.len() + 1);
+                let (etag, part) = self
+                    .blocking(move |s3| {
+                        let etag = s3.upload_part(&key, &id, number, &buf)?;
+                        Ok((etag, buf))
+                    })
+                    .await?;
+                etags.push(etag);
+                buf = part;
+                buf.clear();
+                n += rdr.take(PART_SIZE).read_to_end(&mut buf).await? as u64;
+            }
+            let (key, id) = (key.clone(), id.clone());
+            self.blocking(move |s3| s3.complete_upload(&key, &id, &etags))
+                .await
+        }
+        .await;
+        if res.is_err() {
+            _ = self.blocking(move |s3| s3.abort_upload(&key, &id)).await;
+        }
+        res.map(|()| n)
+    }
+
+    async fn read(&self, path: &Utf8Path) -> io::Result<Vec<u8>> {
+        let key = key(path, false);
+        self.blocking(move |s3| s3.get(&key)).await
+    }
+
+    async fn open(&self, path: &Utf8Path) -> io::Result<(u64, Box<dyn Reader>)> {
+        let key = key(path, false);
+        self.blocking(move |s3| {
+            let res = s3.send(Request::new("GET", &key))?;
+            let len = res
+                .header("content-length")
+                .and_then(|len| len.parse().ok())
+                .ok_or_else(|| io::Error::other("object store did not return a content length"))?;
+            let rdr: Box<dyn Reader> = Box::new(ObjectReader {
+                s3,
+                key,
+                len,
+                pos: 0,
+                state: ReadState::Idle {
+                    body: Some(res.into_reader()),
+                    chunk: vec![],
+                },
+            });
+            Ok((len, rdr))
+        })
+        .await
+    }
+
+    async fn rename_dir(&self, from: &Utf8Path, to: &Utf8Path) -> io::Result<()> {
+        let (from, to) = (key(from, true), key(to, true));
+        self.blocking(move |s3| {
+            if !s3.exists(&from)? {
+                return Err(not_found());
+            }
+            s3.check_parent(Utf8Path::new(&to))?;
+
+            // Claim the destination first, so that concurrent moves fail. Existing directories
+            // are claimed by an object keyed by their marker followed by another one, which is
+            // not listed as an entry of the directory, and must be empty once claimed.
+            let claim = format!("{to}{DIRECTORY_MARKER}");
+            let existed = s3.exists(&to)?;
+            if existed {
+                s3.create(&claim, &[])?;
+                if s3
+                    .list(&to, None)?
+                    .iter()
+                    .any(|key| key != &to && key != &claim)
+                {
+                    _ = s3.delete(&claim);
+                    return Err(io::ErrorKind::DirectoryNotEmpty.into());
+                }
+            } else {
+                s3.create(&to, &[])?;
+            }
+            let from_claim = format!("{from}{DIRECTORY_MARKER}");
+            let mut keys: Vec<_> = s3
+                .list(&from, None)?
+                .into_iter()
+                .filter(|key| key != &from)
+                .collect();
+            keys.sort_by_key(|key| {
+                (
+                    key.ends_with(&format!("/{META_FILE}")),
+                    Reverse(key.matches('/').count()),
+                )
+            });
+            let mut copied = vec![];
+            // Claims left behind by interrupted moves to `from` are not moved along.
+            for key in keys.iter().filter(|key| *key != &from_claim) {
+                let dst = format!("{to}{}", &key[from.len()..]);
+                if let Err(e) = s3.copy(key, &dst) {
+                    let claim = if existed { &claim } else { &to };
+                    for key in copied.iter().rev().chain([claim]) {
+                        _ = s3.delete(key);
+                    }
+                    return Err(e);
+                }
+                copied.push(dst);
+            }
+            for key in keys.iter().chain([&from]) {
+                s3.delete(key)?;
+            }
+            if existed {
+                s3.delete(&claim)?;
+            }
+            Ok(())
+        })
+        .await
+    }
+
+    async fn rename_file(&self, from: &Utf8Path, to: &Utf8Path) -> io::Result<()> {
+        let (from, to) = (key(from, false), key(to, false));
+        self.blocking(move |s3| {
+            if from.is_empty() || !s3.exists(&from)? {
+                return Err(not_found());
+            }
+            s3.check_parent(Utf8Path::new(&to))?;
+            // Copies replace objects atomically
+            s3.copy(&from, &to)?;
+            s3.delete(&from)
+        })
+        .await
+    }
+
+    async fn remove_dir_all(&self, path: &Utf8Path) -> io::Result<()> {
+        let prefix = key(path, true);
+        self.blocking(move |s3| {
+            if !s3.exists(&prefix)? {
+                return Err(not_found());
+            }
+            let mut keys = s3.list(&prefix, None)?;
+            // Remove the directory marker last, so that removal can be retried on failure.
+            keys.sort_by_key(|key| key == &prefix);
+            for key in keys {
+                s3.delete(&key)?;
+            }
+            Ok(())
+        })
+        .await
+    }
+
+    async fn remove_file(&self, path: &Utf8Path) -> io::Result<()> {
+        let key = key(path, false);
+        self.blocking(move |s3| {
+            if key.is_empty() || !s3.exists(&key)? {
+                return Err(not_found());
+            }
+            s3.delete(&key)
+        })
+        .await
+    }
+}
+
+/// Body of a response to a request of an object.
+type Body = Box<dyn Read + Send + Sync>;
+
+/// State of an [ObjectReader].
+enum ReadState {
+    /// No read is in progress. `body` streams the object following the bytes in `chunk`, which
+    /// were read, but not returned yet, and is requested on demand if missing.
+    Idle { body: Option<Body>, chunk: Vec<u8> },
+
+    /// A chunk of the object is being read.
+    Reading(JoinHandle<io::Result<(Body, Vec<u8>)>>),
+}
+
+/// Reader streaming the contents of an object, which requests the remaining range of the
+/// object again when seeked.
+struct ObjectReader {
+    s3: S3,
+    key: String,
+    len: u64,
+    /// Position of the next byte returned by the reader.
+    pos: u64,
+    state: ReadState,
+}
+
+impl fmt::Debug for ObjectReader {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ObjectReader")
+            .field("key", &self.key)
+            .field("len", &self.len)
+            .field("pos", &self.pos)
+            .finish_non_exhaustive()
+    }
+}
+
+impl ObjectReader {
+    /// Waits for a read in progress, such that the reader is idle.
+    fn poll_idle(&mut self, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        if let ReadState::Reading(ref mut task) = self.state {
+            let res = ready!(Pin::new(task).poll(cx));
+            self.state = match res {
+                Ok((body, chunk)) => ReadState::Idle {
+                    body: Some(body),
+                    chunk,
+                },
+                Err(e) => {
+                    self.state = ReadState::Idle {
+                        body: None,
+                        chunk: vec![],
+                    };
+                    return Poll::Ready(Err(e));
+                }
+            };
+        }
+        Poll::Ready(Ok(()))
+    }
+}
+
+impl AsyncRead for ObjectReader {
+    fn poll_read(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut [u8],
+    ) -> Poll<io::Result<usize>> {
+        let this = self.get_mut();
+        loop {
+            ready!(this.poll_idle(cx))?;
+            let ReadState::Idle { body, chunk } = &mut this.state else {
+                unreachable!("reader must be idle")
+            };
+            if !chunk.is_empty() || buf.is_empty() || this.pos >= this.len {
+                let n = chunk.len().min(buf.len());
+                buf[..n].copy_from_slice(&chunk[..n]);
+                _ = chunk.drain(..n);
+                this.pos += n as u64;
+                return Poll::Ready(Ok(n));
+            }
+            let (s3, key, pos, body) = (this.s3.clone(), this.key.clone(), this.pos, body.take());
+            this.state = ReadState::Reading(spawn_blocking(move || {
+                let mut body = match body {
+                    Some(body) => body,
+                    None => s3
+                        .send(Request::new("GET", &key).header("range", format!("bytes={pos}-")))?
+                        .into_reader(),
+                };
+                let mut chunk = vec![0; CHUNK_SIZE];
+                let n = body.read(&mut chunk)?;
+                if n == 0 {
+                    return Err(io::ErrorKind::UnexpectedEof.into());
+                }
+                chunk.truncate(n);
+                Ok((body, chunk))
+            }));
+        }
+    }
+}
+
+impl AsyncSeek for ObjectReader {
+    fn poll_seek(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        pos: SeekFrom,
+    ) -> Poll<io::Result<u64>> {
+        let this = self.get_mut();
+        let pos = match pos {
+            SeekFrom::Start(pos) => Some(pos),
+            SeekFrom::End(off) => this.len.checked_add_signed(off),
+            SeekFrom::Current(off) => this.pos.checked_add_signed(off),
+        }
+        .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidInput, "invalid seek position"))?;
+        // A failed read in progress is irrelevant, since the object is requested again anyway
+        _ = ready!(this.poll_idle(cx));
+        if pos != this.pos {
+            this.pos = pos;
+            this.state = ReadState::Idle {
+                body: None,
+                chunk: vec![],
+            };
+        }
+        Poll::Ready(Ok(pos))
+    }
+}
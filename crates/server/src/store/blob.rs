@@ -0,0 +1,167 @@
+// SPDX-FileCopyrightText: 2022 Profian Inc. <opensource@profian.com>
+// SPDX-License-Identifier: Apache-2.0
+
+use super::{CreateError, Entity, GetError};
+
+use std::fmt::Write;
+use std::ops::Deref;
+
+use drawbridge_type::digest::{Algorithm, Algorithms, ContentDigest};
+use drawbridge_type::Meta;
+
+use anyhow::{anyhow, Context};
+use camino::{Utf8Path, Utf8PathBuf};
+use futures::AsyncRead;
+use mime::APPLICATION_OCTET_STREAM;
+use tracing::trace;
+
+/// Content-addressed storage of a repository.
+///
+/// Each blob is an [Entity] named by the hex-encoded SHA-256 hash of its contents, which records
+/// the digest of the contents computed using all [default](Algorithms::default) algorithms in
+/// its metadata.
+#[repr(transparent)]
+#[derive(Clone, Debug)]
+pub struct Blobs<'a, P = Utf8PathBuf>(Entity<'a, P>);
+
+impl<'a, P> Deref for Blobs<'a, P> {
+    type Target = Entity<'a, P>;
+
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
+impl<'a, P> From<Entity<'a, P>> for Blobs<'a, P> {
+    fn from(entity: Entity<'a, P>) -> Self {
+        Self(entity)
+    }
+}
+
+/// Returns the name of the blob holding contents with digest `hash`, if `hash` contains a
+/// SHA-256 hash.
+pub(super) fn blob_name(hash: &ContentDigest) -> Option<String> {
+    hash.get(&Algorithm::Sha256).map(|hash| {
+        hash.iter().fold(String::with_capacity(64), |mut name, b| {
+            _ = write!(name, "{b:02x}");
+            name
+        })
+    })
+}
+
+/// Returns `true` if all hashes in `hash` match those in `blob`.
+pub(super) fn is_consistent(hash: &ContentDigest, blob: &ContentDigest) -> bool {
+    hash.iter()
+        .all(|(algo, hash)| blob.get(algo).map(|h| h.as_ref()) == Some(hash.as_ref()))
+}
+
+impl<'a, P: AsRef<Utf8Path>> Blobs<'a, P> {
+    /// Returns the blob holding contents with digest `hash`.
+    pub fn blob(
+        &self,
+        hash: &ContentDigest,
+    ) -> Result<Entity<'a, Utf8PathBuf>, GetError<anyhow::Error>> {
+        blob_name(hash)
+            .map(|name| self.child(name))
+            .ok_or_else(|| GetError::Internal(anyhow!("content digest lacks a SHA-256 hash")))
+    }
+
+    /// Returns metadata of the blob holding contents with digest `hash`.
+    ///
+    /// Fails with [GetError::NotFound] if `hash` lacks a SHA-256 hash, no such blob exists or
+    /// the blob contents do not match `hash`.
+    pub async fn find(&self, hash: &ContentDigest) -> Result<Meta, GetError<anyhow::Error>> {
+        let name = blob_name(hash).ok_or(GetError::NotFound)?;
+        let meta = self.child(name).get_meta().await?;
+        if is_consistent(hash, &meta.hash) {
+            Ok(meta)
+        } else {
+            Err(GetError::NotFound)
+        }
+    }
+
+    /// Stores contents read from `rdr` in a blob, unless a blob with these contents already
+    /// exists, and returns the blob metadata.
+    ///
+    /// `rdr` must produce `size` bytes matching `hash`, which must contain a SHA-256 hash.
+    /// If a matching blob already exists, `rdr` is not read.
+    pub async fn create(
+        &self,
+        hash: &ContentDigest,
+        size: u64,
+        rdr: impl Send + Unpin + AsyncRead,
+    ) -> Result<Meta, CreateError<anyhow::Error>> {
+        let name = blob_name(hash)
+            .ok_or_else(|| CreateError::Internal(anyhow!("content digest lacks a SHA-256 hash")))?;
+        match self.find(hash).await {
+            Ok(meta) if meta.size == size => return Ok(meta),
+            Ok(meta) => {
+                return Err(CreateError::LengthMismatch {
+                    expected: meta.size,
+                    got: size,
+                })
+            }
+            Err(GetError::NotFound) => {}
+            Err(GetError::Internal(e)) => return Err(CreateError::Internal(e)),
+        }
+
+        trace!(target: "app::store::Blobs::create", "create blob `{name}`");
+        let res = self
+            .child(&name)
+            .create_with(|staged| async move {
+                let hash = staged.create_content_digested(hash, size, rdr).await?;
+                let meta = Meta {
+                    hash,
+                    size,
+                    mime: APPLICATION_OCTET_STREAM,
+                };
+                staged.write_meta(&meta).await?;
+                Ok(meta)
+            })
+            .await;
+        match res {
+            // The blob was created concurrently
+            Err(CreateError::Occupied) => self.find(hash).await.map_err(|e| match e {
+                GetError::NotFound => {
+                    CreateError::Internal(anyhow!("blob `{name}` does not match its name"))
+                }
+                GetError::Internal(e) => CreateError::Internal(e),
+            }),
+            res => res,
+        }
+    }
+
+    /// Stores contents of the content file of `entity` in a blob, unless a blob with these
+    /// contents already exists, removes the content file and returns the blob metadata.
+    pub(super) async fn adopt(
+        &self,
+        entity: &Entity<'a, impl AsRef<Utf8Path>>,
+    ) -> Result<Meta, CreateError<anyhow::Error>> {
+        let get_content = || async {
+            entity.get_content().await.map_err(|e| match e {
+                GetError::NotFound => CreateError::Internal(anyhow!("entity has no content file")),
+                GetError::Internal(e) => CreateError::Internal(e),
+            })
+        };
+        let (size, hash) = Algorithms::default()
+            .read(get_content().await?)
+            .await
+            .context("failed to compute content digest")
+            .map_err(CreateError::Internal)?;
+        let meta = match self.create(&hash, size, get_content().await?).await {
+            Err(CreateError::Internal(e)) => return Err(CreateError::Internal(e)),
+            Err(e) => {
+                return Err(CreateError::Internal(anyhow!(
+                    "content file changed while being stored: {e:?}"
+                )))
+            }
+            Ok(meta) => meta,
+        };
+        entity
+            .remove_content()
+            .await
+            .context("failed to remove content file")
+            .map_err(CreateError::Internal)?;
+        Ok(meta)
+    }
+}
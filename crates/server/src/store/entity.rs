@@ -1,23 +1,41 @@
 // SPDX-FileCopyrightText: 2022 Profian Inc. <opensource@profian.com>
 // SPDX-License-Identifier: Apache-2.0
 
+use super::{Reader, StorageBackend};
+
 use std::io;
-use std::os::unix::fs::DirBuilderExt;
+use std::time::Duration;
 
+use drawbridge_type::digest::Algorithms;
 use drawbridge_type::Meta;
 
-use anyhow::Context;
+use anyhow::{anyhow, Context};
+use async_std::task::sleep;
+use axum::body::StreamBody;
 use axum::http::StatusCode;
 use axum::response::{IntoResponse, Response};
 use camino::{Utf8Path, Utf8PathBuf};
-use cap_async_std::fs_utf8::{Dir, DirBuilder, ReadDir};
 use drawbridge_type::digest::ContentDigest;
 use futures::future::TryFutureExt;
-use futures::io::copy;
+use futures::io::{copy, sink};
 use futures::try_join;
-use futures::{AsyncRead, AsyncWrite};
+use futures::{AsyncRead, AsyncSeek, AsyncWrite, Future, Stream};
 use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use tokio_util::compat::FuturesAsyncReadCompatExt;
+use tokio_util::io::ReaderStream;
 use tracing::{debug, trace};
+use uuid::Uuid;
+
+/// Directory relative to the root of the store, in which entities are populated before being
+/// moved into place.
+pub(super) const STAGING_DIR: &str = ".tmp";
+
+/// Amount of attempts to read matching metadata and contents of entities being replaced.
+const REPLACED_READ_ATTEMPTS: u32 = 8;
+
+/// Delay between attempts to read entities being replaced, multiplied by the attempt number.
+const REPLACED_READ_DELAY: Duration = Duration::from_millis(10);
 
 const STORAGE_FAILURE_RESPONSE: (StatusCode, &str) =
     (StatusCode::INTERNAL_SERVER_ERROR, "Storage backend failure");
@@ -25,8 +43,20 @@ const STORAGE_FAILURE_RESPONSE: (StatusCode, &str) =
 #[derive(Debug)]
 pub enum CreateError<E> {
     Occupied,
-    LengthMismatch { expected: u64, got: u64 },
+    LengthMismatch {
+        expected: u64,
+        got: u64,
+    },
     DigestMismatch,
+    /// Tree node is not declared by its parent directory or tag.
+    Undeclared,
+    /// Metadata of the tree node does not match the entry declared by its parent directory or tag.
+    NodeMismatch,
+    /// Tree archive is malformed or lacks nodes.
+    InvalidArchive(String),
+    /// Precondition of a replacement does not hold for the current entity or the entity is
+    /// replaced concurrently.
+    PreconditionFailed,
     Internal(E),
 }
 
@@ -42,6 +72,22 @@ impl<E> IntoResponse for CreateError<E> {
                 format!("Content length mismatch, expected: {expected}, got {got}"),
             )
                 .into_response(),
+            CreateError::Undeclared => (
+                StatusCode::BAD_REQUEST,
+                "Node is not declared by its parent",
+            )
+                .into_response(),
+            CreateError::NodeMismatch => (
+                StatusCode::BAD_REQUEST,
+                "Node does not match the entry declared by its parent",
+            )
+                .into_response(),
+            CreateError::InvalidArchive(e) => {
+                (StatusCode::BAD_REQUEST, format!("Invalid archive: {e}")).into_response()
+            }
+            CreateError::PreconditionFailed => {
+                (StatusCode::PRECONDITION_FAILED, "Precondition failed").into_response()
+            }
             CreateError::Internal(_) => STORAGE_FAILURE_RESPONSE.into_response(),
         }
     }
@@ -63,6 +109,33 @@ impl<E> IntoResponse for GetError<E> {
     }
 }
 
+#[derive(Debug)]
+pub enum DeleteError<E> {
+    NotFound,
+    NotEmpty,
+    Internal(E),
+}
+
+impl<E> IntoResponse for DeleteError<E> {
+    fn into_response(self) -> Response {
+        match self {
+            DeleteError::NotFound => (StatusCode::NOT_FOUND, "Not found"),
+            DeleteError::NotEmpty => (StatusCode::CONFLICT, "Not empty"),
+            DeleteError::Internal(_) => STORAGE_FAILURE_RESPONSE,
+        }
+        .into_response()
+    }
+}
+
+impl<E> From<GetError<E>> for DeleteError<E> {
+    fn from(e: GetError<E>) -> Self {
+        match e {
+            GetError::NotFound => DeleteError::NotFound,
+            GetError::Internal(e) => DeleteError::Internal(e),
+        }
+    }
+}
+
 #[derive(Debug)]
 pub enum GetToWriterError<E> {
     IO(io::Error),
@@ -86,25 +159,60 @@ impl<E> IntoResponse for GetToWriterError<E> {
 
 #[derive(Copy, Clone, Debug)]
 pub struct Entity<'a, P> {
-    root: &'a Dir,
+    root: &'a dyn StorageBackend,
     prefix: P,
 }
 
+/// Creates a file at `path` containing `rdr` and returns the digest of its contents computed
+/// using both the default algorithms and the algorithms in `hash`, verifying `hash` and `size`.
+async fn create_digested(
+    root: &dyn StorageBackend,
+    path: impl AsRef<Utf8Path>,
+    hash: &ContentDigest,
+    size: u64,
+    rdr: impl Send + Unpin + AsyncRead,
+) -> Result<ContentDigest, CreateError<anyhow::Error>> {
+    let mut algs = Algorithms::default();
+    algs.extend(hash.keys());
+    let mut rdr = algs.reader(rdr);
+    let n = root
+        .create_file(path.as_ref(), &mut rdr)
+        .await
+        .map_err(|e| match e.kind() {
+            io::ErrorKind::AlreadyExists => CreateError::Occupied,
+            _ => CreateError::Internal(anyhow::Error::new(e).context("failed to create file")),
+        })?;
+    if n != size {
+        return Err(CreateError::LengthMismatch {
+            expected: size,
+            got: n,
+        });
+    }
+    let digest = rdr.digests();
+    if hash
+        .iter()
+        .any(|(algo, hash)| digest.get(algo) != Some(hash))
+    {
+        return Err(CreateError::DigestMismatch);
+    }
+    Ok(digest)
+}
+
 async fn create_verified(
-    dir: &Dir,
+    root: &dyn StorageBackend,
     path: impl AsRef<Utf8Path>,
     hash: ContentDigest,
     size: u64,
-    rdr: impl Unpin + AsyncRead,
+    rdr: impl Send + Unpin + AsyncRead,
 ) -> Result<(), CreateError<anyhow::Error>> {
-    let mut file = dir.create(path).await.map_err(|e| match e.kind() {
-        io::ErrorKind::AlreadyExists => CreateError::Occupied,
-        _ => CreateError::Internal(anyhow::Error::new(e).context("failed to create file")),
-    })?;
-    match copy(hash.verifier(rdr), &mut file).await {
+    match root
+        .create_file(path.as_ref(), &mut hash.verifier(rdr))
+        .await
+    {
+        Err(e) if e.kind() == io::ErrorKind::AlreadyExists => Err(CreateError::Occupied),
         Err(e) if e.kind() == io::ErrorKind::InvalidData => Err(CreateError::DigestMismatch),
         Err(e) => Err(CreateError::Internal(
-            anyhow::Error::new(e).context("failed to write file"),
+            anyhow::Error::new(e).context("failed to create file"),
         )),
         Ok(n) if n != size => Err(CreateError::LengthMismatch {
             expected: size,
@@ -114,8 +222,22 @@ async fn create_verified(
     }
 }
 
+/// Verifies `buf` against size and digest in `meta`, failing with [io::ErrorKind::InvalidData]
+/// on mismatch.
+async fn verify_bytes(meta: &Meta, buf: &[u8]) -> io::Result<()> {
+    if buf.len() as u64 != meta.size {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!("expected {} bytes, got {}", meta.size, buf.len()),
+        ));
+    }
+    copy(meta.hash.clone().verifier(buf), &mut sink())
+        .await
+        .map(|_| ())
+}
+
 impl<'a> Entity<'a, &'static str> {
-    pub fn new(root: &'a Dir) -> Self {
+    pub fn new(root: &'a dyn StorageBackend) -> Self {
         Self { root, prefix: "" }
     }
 }
@@ -129,6 +251,11 @@ impl<'a, P: AsRef<Utf8Path>> Entity<'a, P> {
         }
     }
 
+    /// Returns the path of the entity relative to the root of the store.
+    pub(super) fn prefix(&self) -> &Utf8Path {
+        self.prefix.as_ref()
+    }
+
     fn path(&self, path: impl AsRef<Utf8Path>) -> Utf8PathBuf {
         self.prefix.as_ref().join(path)
     }
@@ -141,18 +268,58 @@ impl<'a, P: AsRef<Utf8Path>> Entity<'a, P> {
         self.path("content")
     }
 
+    /// Creates the entity atomically.
+    ///
+    /// The entity is populated by `f` in a directory within [STAGING_DIR], which is then moved
+    /// into place. The staging directory is removed on failure, so that creation can be retried.
+    pub(super) async fn create_with<T, F>(
+        &self,
+        f: impl FnOnce(Entity<'a, Utf8PathBuf>) -> F,
+    ) -> Result<T, CreateError<anyhow::Error>>
+    where
+        F: Future<Output = Result<T, CreateError<anyhow::Error>>>,
+    {
+        let staged = Entity::new(self.root)
+            .child(Utf8Path::new(STAGING_DIR).join(Uuid::new_v4().to_string()));
+        trace!(target: "app::store::Entity::create_with", "stage entity `{}` at `{}`", self.prefix(), staged.prefix());
+        staged.create_dir("").await?;
+        let res = match f(staged.clone()).await {
+            Ok(v) => self
+                .root
+                .rename_dir(staged.prefix(), self.prefix())
+                .await
+                .map(|()| v)
+                .map_err(|e| match e.kind() {
+                    io::ErrorKind::AlreadyExists | io::ErrorKind::DirectoryNotEmpty => {
+                        CreateError::Occupied
+                    }
+                    _ => CreateError::Internal(
+                        anyhow::Error::new(e).context("failed to move staged entity into place"),
+                    ),
+                }),
+            Err(e) => Err(e),
+        };
+        if res.is_err() {
+            if let Err(e) = self.root.remove_dir_all(staged.prefix()).await {
+                debug!(target: "app::store::Entity::create_with", "failed to remove staged entity: {:?}", e);
+            }
+        }
+        res
+    }
+
     pub(super) async fn create_from_reader(
         &self,
         meta: Meta,
-        rdr: impl Unpin + AsyncRead,
+        rdr: impl Send + Unpin + AsyncRead,
     ) -> Result<(), CreateError<anyhow::Error>> {
         trace!(target: "app::store::Entity::create_from_reader", "create entity at `{}`", self.prefix.as_ref());
         let meta_json = serde_json::to_vec(&meta)
             .context("failed to encode metadata")
             .map_err(CreateError::Internal)?;
+        let meta_path = self.meta_path();
         try_join!(
             self.root
-                .write(self.meta_path(), meta_json)
+                .write(&meta_path, meta_json)
                 .map_err(|e| match e.kind() {
                     io::ErrorKind::AlreadyExists => CreateError::Occupied,
                     _ => CreateError::Internal(
@@ -171,6 +338,36 @@ impl<'a, P: AsRef<Utf8Path>> Entity<'a, P> {
         Ok(())
     }
 
+    /// Creates the content file of the entity containing `rdr`.
+    ///
+    /// Returns the digest of the contents computed using both the default algorithms and the
+    /// algorithms in `hash`, verifying `hash` and `size`.
+    pub(super) async fn create_content_digested(
+        &self,
+        hash: &ContentDigest,
+        size: u64,
+        rdr: impl Send + Unpin + AsyncRead,
+    ) -> Result<ContentDigest, CreateError<anyhow::Error>> {
+        create_digested(self.root, self.content_path(), hash, size, rdr)
+            .await
+            .map_err(|e| {
+                debug!(target: "app::store::Entity::create_content_digested", "failed to create content file `{:?}`", e);
+                e
+            })
+    }
+
+    /// Writes metadata of the entity, replacing existing metadata, if any.
+    pub(super) async fn write_meta(&self, meta: &Meta) -> Result<(), CreateError<anyhow::Error>> {
+        let meta_json = serde_json::to_vec(meta)
+            .context("failed to encode metadata")
+            .map_err(CreateError::Internal)?;
+        self.root
+            .write(&self.meta_path(), meta_json)
+            .await
+            .context("failed to write metadata")
+            .map_err(CreateError::Internal)
+    }
+
     pub(super) async fn create_json(
         &self,
         meta: Meta,
@@ -182,6 +379,104 @@ impl<'a, P: AsRef<Utf8Path>> Entity<'a, P> {
         self.create_from_reader(meta, buf.as_slice()).await
     }
 
+    /// Replaces metadata and contents of the existing entity by `meta` and `val` encoded as JSON,
+    /// verifying `meta` against the encoded contents, if `precondition` holds for the current
+    /// metadata of the entity.
+    ///
+    /// Both files are staged in a directory within [STAGING_DIR], which is then moved to a path
+    /// derived from the entity to lock it against concurrent replacements. `precondition` is
+    /// evaluated while holding the lock and the staged files are moved into place, metadata
+    /// first. Readers must use [Self::get_bytes_replaced] to not pair the new metadata with the
+    /// old contents. Concurrent replacements fail with [CreateError::PreconditionFailed].
+    pub(super) async fn replace_json(
+        &self,
+        meta: &Meta,
+        val: &impl Serialize,
+        precondition: impl FnOnce(&Meta) -> bool,
+    ) -> Result<(), CreateError<anyhow::Error>> {
+        trace!(target: "app::store::Entity::replace_json", "replace entity at `{}`", self.prefix());
+        let buf = serde_json::to_vec(val)
+            .context("failed to encode value to JSON")
+            .map_err(CreateError::Internal)?;
+        if buf.len() as u64 != meta.size {
+            return Err(CreateError::LengthMismatch {
+                expected: meta.size,
+                got: buf.len() as _,
+            });
+        }
+        match verify_bytes(meta, &buf).await {
+            Err(e) if e.kind() == io::ErrorKind::InvalidData => {
+                return Err(CreateError::DigestMismatch)
+            }
+            Err(e) => {
+                return Err(CreateError::Internal(
+                    anyhow::Error::new(e).context("failed to verify contents"),
+                ))
+            }
+            Ok(()) => {}
+        }
+
+        let staged = Entity::new(self.root)
+            .child(Utf8Path::new(STAGING_DIR).join(Uuid::new_v4().to_string()));
+        let lock = Entity::new(self.root).child(Utf8Path::new(STAGING_DIR).join(format!(
+            "{}.lock",
+            hex::encode(Sha256::digest(self.prefix().as_str()))
+        )));
+        trace!(target: "app::store::Entity::replace_json", "stage replacement at `{}`", staged.prefix());
+        staged.create_dir("").await?;
+        let res = async {
+            staged.write_meta(meta).await?;
+            self.root
+                .write(&staged.content_path(), buf)
+                .await
+                .context("failed to write contents")
+                .map_err(CreateError::Internal)?;
+            self.root
+                .rename_dir(staged.prefix(), lock.prefix())
+                .await
+                .map_err(|e| match e.kind() {
+                    io::ErrorKind::AlreadyExists | io::ErrorKind::DirectoryNotEmpty => {
+                        debug!(target: "app::store::Entity::replace_json", "entity is replaced concurrently");
+                        CreateError::PreconditionFailed
+                    }
+                    _ => CreateError::Internal(
+                        anyhow::Error::new(e).context("failed to lock entity"),
+                    ),
+                })
+        }
+        .await;
+        if let Err(e) = res {
+            if let Err(e) = self.root.remove_dir_all(staged.prefix()).await {
+                debug!(target: "app::store::Entity::replace_json", "failed to remove staged replacement: {:?}", e);
+            }
+            return Err(e);
+        }
+
+        let res = async {
+            match self.get_meta().await {
+                Ok(current) if precondition(&current) => {}
+                Ok(_) | Err(GetError::NotFound) => return Err(CreateError::PreconditionFailed),
+                Err(GetError::Internal(e)) => return Err(CreateError::Internal(e)),
+            }
+            for (from, to) in [
+                (lock.meta_path(), self.meta_path()),
+                (lock.content_path(), self.content_path()),
+            ] {
+                self.root
+                    .rename_file(&from, &to)
+                    .await
+                    .with_context(|| format!("failed to move `{from}` into place"))
+                    .map_err(CreateError::Internal)?;
+            }
+            Ok(())
+        }
+        .await;
+        if let Err(e) = self.root.remove_dir_all(lock.prefix()).await {
+            debug!(target: "app::store::Entity::replace_json", "failed to unlock entity: {:?}", e);
+        }
+        res
+    }
+
     pub(super) async fn create_dir(
         &self,
         path: impl AsRef<Utf8Path>,
@@ -192,7 +487,8 @@ impl<'a, P: AsRef<Utf8Path>> Entity<'a, P> {
 
         trace!(target: "app::store::Entity::create_dir", "create directory at `{path}`");
         self.root
-            .create_dir_with(path, DirBuilder::new().mode(0o700))
+            .create_dir(&path)
+            .await
             .map_err(|e| match e.kind() {
                 io::ErrorKind::AlreadyExists => CreateError::Occupied,
                 _ => CreateError::Internal(
@@ -205,12 +501,161 @@ impl<'a, P: AsRef<Utf8Path>> Entity<'a, P> {
             })
     }
 
+    /// Creates a file at `path` relative to the entity containing `val` encoded as JSON.
+    pub(super) async fn create_file_json(
+        &self,
+        path: impl AsRef<Utf8Path>,
+        val: &impl Serialize,
+    ) -> Result<(), CreateError<anyhow::Error>> {
+        let buf = serde_json::to_vec(val)
+            .context("failed to encode value to JSON")
+            .map_err(CreateError::Internal)?;
+        self.root
+            .create_file(&self.path(path), &mut buf.as_slice())
+            .await
+            .map(|_| ())
+            .map_err(|e| match e.kind() {
+                io::ErrorKind::AlreadyExists => CreateError::Occupied,
+                _ => CreateError::Internal(anyhow::Error::new(e).context("failed to create file")),
+            })
+    }
+
+    /// Writes `val` encoded as JSON to the file at `path` relative to the entity, replacing
+    /// existing contents, if any.
+    ///
+    /// The file is written within [STAGING_DIR] and then moved into place, such that readers
+    /// never observe partially written contents.
+    pub(super) async fn write_json(
+        &self,
+        path: impl AsRef<Utf8Path>,
+        val: &impl Serialize,
+    ) -> anyhow::Result<()> {
+        let buf = serde_json::to_vec(val).context("failed to encode value to JSON")?;
+        let staged = Entity::new(self.root)
+            .child(Utf8Path::new(STAGING_DIR).join(Uuid::new_v4().to_string()));
+        self.root
+            .create_dir(staged.prefix())
+            .await
+            .context("failed to create staging directory")?;
+        let res = async {
+            let from = staged.content_path();
+            self.root
+                .write(&from, buf)
+                .await
+                .context("failed to write file")?;
+            self.root
+                .rename_file(&from, &self.path(path))
+                .await
+                .context("failed to move file into place")
+        }
+        .await;
+        if let Err(e) = self.root.remove_dir_all(staged.prefix()).await {
+            debug!(target: "app::store::Entity::write_json", "failed to remove staging directory: {:?}", e);
+        }
+        res
+    }
+
+    /// Reads the JSON-encoded file at `path` relative to the entity.
+    #[allow(single_use_lifetimes)]
+    pub(super) async fn read_json<T>(
+        &self,
+        path: impl AsRef<Utf8Path>,
+    ) -> Result<T, GetError<anyhow::Error>>
+    where
+        for<'de> T: Deserialize<'de>,
+    {
+        let buf = self
+            .root
+            .read(&self.path(path))
+            .await
+            .map_err(|e| match e.kind() {
+                io::ErrorKind::NotFound => GetError::NotFound,
+                _ => GetError::Internal(anyhow::Error::new(e).context("failed to read file")),
+            })?;
+        serde_json::from_slice(&buf)
+            .context("failed to decode file as JSON")
+            .map_err(GetError::Internal)
+    }
+
+    /// Removes the entity atomically.
+    ///
+    /// The entity is moved into [STAGING_DIR] before its contents are removed, so that contents
+    /// left behind by an interrupted removal are collected by garbage collection.
+    pub(super) async fn remove(&self) -> Result<(), GetError<anyhow::Error>> {
+        self.remove_unless(|_| async { Ok(false) })
+            .await
+            .map(|_| ())
+    }
+
+    /// Removes the entity atomically like [Self::remove], unless `keep` returns `true` for the
+    /// entity moved into [STAGING_DIR], in which case it is moved back into place.
+    ///
+    /// Returns `true` if the entity was removed.
+    pub(super) async fn remove_unless<F>(
+        &self,
+        keep: impl FnOnce(Entity<'a, Utf8PathBuf>) -> F,
+    ) -> Result<bool, GetError<anyhow::Error>>
+    where
+        F: Future<Output = Result<bool, GetError<anyhow::Error>>>,
+    {
+        let staged = Entity::new(self.root)
+            .child(Utf8Path::new(STAGING_DIR).join(Uuid::new_v4().to_string()));
+        trace!(target: "app::store::Entity::remove_unless", "remove entity `{}` via `{}`", self.prefix(), staged.prefix());
+        self.root
+            .rename_dir(self.prefix(), staged.prefix())
+            .await
+            .map_err(|e| match e.kind() {
+                io::ErrorKind::NotFound => GetError::NotFound,
+                _ => GetError::Internal(
+                    anyhow::Error::new(e).context("failed to move entity out of place"),
+                ),
+            })?;
+        let keep = keep(staged.clone()).await;
+        if !matches!(keep, Ok(false)) {
+            self.root
+                .rename_dir(staged.prefix(), self.prefix())
+                .await
+                .with_context(|| format!("failed to move entity back from `{}`", staged.prefix()))
+                .map_err(GetError::Internal)?;
+            return keep.map(|_| false);
+        }
+        if let Err(e) = self.root.remove_dir_all(staged.prefix()).await {
+            debug!(target: "app::store::Entity::remove_unless", "failed to remove staged entity: {:?}", e);
+        }
+        Ok(true)
+    }
+
+    /// Removes the directory at `path` relative to the entity along with all of its contents.
+    pub(super) async fn remove_dir_all(&self, path: impl AsRef<Utf8Path>) -> io::Result<()> {
+        self.root.remove_dir_all(&self.path(path)).await
+    }
+
+    /// Removes the file at `path` relative to the entity.
+    pub(super) async fn remove_file(&self, path: impl AsRef<Utf8Path>) -> io::Result<()> {
+        self.root.remove_file(&self.path(path)).await
+    }
+
+    /// Returns `true` if the entity has a content file.
+    pub(super) async fn has_content(&self) -> bool {
+        self.root.is_file(&self.content_path()).await
+    }
+
+    /// Removes the content file of the entity.
+    pub(super) async fn remove_content(&self) -> io::Result<()> {
+        self.root.remove_file(&self.content_path()).await
+    }
+
+    /// Returns `true` if the entity directory exists.
+    pub(super) async fn exists(&self) -> bool {
+        self.root.is_dir(self.prefix()).await
+    }
+
     pub(super) async fn read_dir(
         &self,
         path: impl AsRef<Utf8Path>,
-    ) -> Result<ReadDir, GetError<anyhow::Error>> {
+    ) -> Result<Vec<String>, GetError<anyhow::Error>> {
         self.root
-            .read_dir(self.path(path))
+            .read_dir(&self.path(path))
             .await
             .map_err(|e| match e.kind() {
                 io::ErrorKind::NotFound => GetError::NotFound,
@@ -222,7 +667,7 @@ impl<'a, P: AsRef<Utf8Path>> Entity<'a, P> {
     pub async fn get_meta(&self) -> Result<Meta, GetError<anyhow::Error>> {
         let buf = self
             .root
-            .read(self.meta_path())
+            .read(&self.meta_path())
             .await
             .map_err(|e| match e.kind() {
                 io::ErrorKind::NotFound => GetError::NotFound,
@@ -233,10 +678,9 @@ impl<'a, P: AsRef<Utf8Path>> Entity<'a, P> {
             .map_err(GetError::Internal)
     }
 
-    /// Returns contents of the entity as [AsyncRead].
-    pub async fn get_content(&self) -> Result<impl '_ + AsyncRead, GetError<anyhow::Error>> {
+    async fn open_content(&self) -> Result<(u64, Box<dyn Reader>), GetError<anyhow::Error>> {
         self.root
-            .open(self.content_path())
+            .open(&self.content_path())
             .map_err(|e| match e.kind() {
                 io::ErrorKind::NotFound => GetError::NotFound,
                 _ => {
@@ -246,10 +690,17 @@ impl<'a, P: AsRef<Utf8Path>> Entity<'a, P> {
             .await
     }
 
+    /// Returns contents of the entity as [AsyncRead].
+    pub async fn get_content(
+        &self,
+    ) -> Result<impl 'static + Send + Unpin + AsyncRead, GetError<anyhow::Error>> {
+        self.open_content().await.map(|(_, rdr)| rdr)
+    }
+
     /// Reads contents of the entity.
     pub async fn read_content(&self) -> Result<Vec<u8>, GetError<anyhow::Error>> {
         self.root
-            .read(self.content_path())
+            .read(&self.content_path())
             .map_err(|e| match e.kind() {
                 io::ErrorKind::NotFound => GetError::NotFound,
                 _ => {
@@ -271,9 +722,59 @@ impl<'a, P: AsRef<Utf8Path>> Entity<'a, P> {
             .map_err(GetError::Internal)
     }
 
-    /// Returns metadata of the entity and a reader of its contents.
-    pub async fn get(&self) -> Result<(Meta, impl '_ + AsyncRead), GetError<anyhow::Error>> {
-        try_join!(self.get_meta(), self.get_content())
+    /// Returns metadata of the entity and a seekable reader of its contents.
+    ///
+    /// The length of the contents is validated against the size recorded in the metadata.
+    pub async fn get(
+        &self,
+    ) -> Result<(Meta, impl 'static + Send + Unpin + AsyncRead + AsyncSeek), GetError<anyhow::Error>>
+    {
+        let (meta, (size, rdr)) = try_join!(self.get_meta(), self.open_content())?;
+        if size != meta.size {
+            return Err(GetError::Internal(anyhow!(
+                "content length mismatch, expected: {}, got {size}",
+                meta.size
+            )));
+        }
+        Ok((meta, rdr))
+    }
+
+    /// Returns metadata and contents of an entity replaced by [Self::replace_json].
+    ///
+    /// Metadata is replaced before contents, hence contents are verified against the metadata
+    /// and both are read again, while they do not match.
+    pub async fn get_bytes_replaced(&self) -> Result<(Meta, Vec<u8>), GetError<anyhow::Error>> {
+        let mut attempt = 1;
+        loop {
+            let (meta, buf) = try_join!(self.get_meta(), self.read_content())?;
+            match verify_bytes(&meta, &buf).await {
+                Ok(()) => return Ok((meta, buf)),
+                Err(e) if attempt == REPLACED_READ_ATTEMPTS => {
+                    return Err(GetError::Internal(
+                        anyhow::Error::new(e).context("contents do not match metadata"),
+                    ))
+                }
+                Err(_) => {
+                    trace!(target: "app::store::Entity::get_bytes_replaced", "entity `{}` is being replaced", self.prefix());
+                    sleep(REPLACED_READ_DELAY * attempt).await;
+                    attempt += 1;
+                }
+            }
+        }
+    }
+
+    /// Returns metadata of the entity and a response body streaming its contents.
+    pub async fn get_body(
+        &self,
+    ) -> Result<
+        (
+            Meta,
+            StreamBody<impl Stream<Item = io::Result<axum::body::Bytes>>>,
+        ),
+        GetError<anyhow::Error>,
+    > {
+        let (meta, rdr) = self.get().await?;
+        Ok((meta, StreamBody::new(ReaderStream::new(rdr.compat()))))
     }
 
     /// Returns metadata of the entity and writes its contents into `dst`.
@@ -283,7 +784,6 @@ impl<'a, P: AsRef<Utf8Path>> Entity<'a, P> {
     ) -> Result<Meta, GetToWriterError<anyhow::Error>> {
         let (meta, rdr) = self.get().await.map_err(GetToWriterError::Get)?;
         _ = copy(rdr, dst).await.map_err(GetToWriterError::IO)?;
-        // TODO: Validate size
         Ok(meta)
     }
 }
@@ -0,0 +1,469 @@
+// SPDX-FileCopyrightText: 2022 Profian Inc. <opensource@profian.com>
+// SPDX-License-Identifier: Apache-2.0
+
+use super::{
+    blob_name, is_consistent, Blobs, Entity, GetError, Repository, Store, LAYOUT_VERSION,
+    LAYOUT_VERSION_PATH, STAGING_DIR,
+};
+
+use std::collections::{BTreeMap, BTreeSet, HashSet};
+
+use drawbridge_type::digest::{Algorithms, ContentDigest};
+use drawbridge_type::{Meta, RepositoryConfig, TagEntry, TreeDirectory, TreeEntry, UserRecord};
+
+use anyhow::Context;
+use camino::{Utf8Path, Utf8PathBuf};
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use tracing::{trace, warn};
+
+/// Problem found in the store.
+#[derive(Clone, Debug, Serialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum Problem {
+    /// Data could not be read.
+    Unreadable { error: String },
+    /// Metadata of the entity is missing, e.g. because its creation was interrupted.
+    MissingMeta,
+    /// Metadata of the entity could not be decoded.
+    InvalidMeta { error: String },
+    /// Content file of the entity is missing.
+    MissingContent,
+    /// Length of the contents does not match the metadata.
+    SizeMismatch { expected: u64, got: u64 },
+    /// Digest of the contents does not match the metadata.
+    DigestMismatch {
+        expected: ContentDigest,
+        got: ContentDigest,
+    },
+    /// Contents could not be decoded.
+    InvalidContent { error: String },
+    /// Blob holding contents of the tree node is missing.
+    MissingBlob,
+    /// Tree node referred to by its parent directory or tag is missing.
+    MissingNode,
+    /// Metadata of the tree node does not match the entry in its parent directory or tag.
+    NodeMismatch { expected: Meta },
+    /// Tree node is not listed in its parent directory.
+    UnexpectedNode,
+    /// Content file of a tree node, which is unused, since contents are stored in [Blobs].
+    OrphanedContent,
+    /// Blob not referred to by any tree node.
+    UnreferencedBlob,
+    /// Entity left in staging by an interrupted upload.
+    StagedEntity,
+    /// Store uses the layout of a previous version of this crate, see [Store::migrate].
+    OutdatedLayout { version: u32 },
+}
+
+impl Problem {
+    fn unreadable(e: impl Into<anyhow::Error>) -> Self {
+        Self::Unreadable {
+            error: format!("{:#}", e.into()),
+        }
+    }
+}
+
+/// [Problem] found at a path in the store.
+#[derive(Clone, Debug, Serialize)]
+pub struct Finding {
+    /// Path relative to the root of the store.
+    pub path: Utf8PathBuf,
+
+    #[serde(flatten)]
+    pub problem: Problem,
+
+    /// Whether the affected data was removed.
+    pub repaired: bool,
+}
+
+/// Report produced by [Store::fsck] and [Store::gc].
+#[derive(Clone, Debug, Default, Serialize)]
+pub struct Report {
+    /// Number of entities inspected.
+    pub checked: u64,
+
+    pub findings: Vec<Finding>,
+}
+
+impl Report {
+    /// Returns `true` if all problems found were repaired.
+    pub fn is_ok(&self) -> bool {
+        self.findings.iter().all(|f| f.repaired)
+    }
+}
+
+/// Returns names of entries of the directory at `path` relative to `entity`.
+///
+/// Missing directories are treated as empty.
+async fn names(
+    entity: &Entity<'_, impl AsRef<Utf8Path>>,
+    path: &str,
+) -> anyhow::Result<BTreeSet<String>> {
+    match entity.read_dir(path).await {
+        Ok(entries) => Ok(entries.into_iter().collect()),
+        Err(GetError::NotFound) => Ok(BTreeSet::new()),
+        Err(GetError::Internal(e)) => {
+            Err(e.context(format!("failed to read `{}`", entity.prefix().join(path))))
+        }
+    }
+}
+
+async fn meta(entity: &Entity<'_, impl AsRef<Utf8Path>>) -> Result<Meta, Problem> {
+    entity.get_meta().await.map_err(|e| match e {
+        GetError::NotFound => Problem::MissingMeta,
+        GetError::Internal(e) => Problem::InvalidMeta {
+            error: format!("{e:#}"),
+        },
+    })
+}
+
+/// Verifies contents of `entity` against `meta`.
+async fn verify(entity: &Entity<'_, impl AsRef<Utf8Path>>, meta: &Meta) -> Result<(), Problem> {
+    let rdr = entity.get_content().await.map_err(|e| match e {
+        GetError::NotFound => Problem::MissingContent,
+        GetError::Internal(e) => Problem::unreadable(e),
+    })?;
+    let (size, hash) = Algorithms::from(meta.hash.keys().copied().collect::<BTreeSet<_>>())
+        .read(rdr)
+        .await
+        .map_err(Problem::unreadable)?;
+    if size != meta.size {
+        Err(Problem::SizeMismatch {
+            expected: meta.size,
+            got: size,
+        })
+    } else if hash != meta.hash {
+        Err(Problem::DigestMismatch {
+            expected: meta.hash.clone(),
+            got: hash,
+        })
+    } else {
+        Ok(())
+    }
+}
+
+fn is_directory(meta: &Meta) -> bool {
+    meta.mime == TreeDirectory::<()>::TYPE
+}
+
+/// State of a [Store::fsck] or [Store::gc] run.
+struct Check<'a> {
+    root: Entity<'a, &'static str>,
+    repair: bool,
+    report: Report,
+}
+
+impl<'a> Check<'a> {
+    fn new(store: &'a Store, repair: bool) -> Self {
+        Self {
+            root: Entity::new(store.root.as_ref()),
+            repair,
+            report: Report::default(),
+        }
+    }
+
+    fn record(
+        &mut self,
+        entity: &Entity<'_, impl AsRef<Utf8Path>>,
+        problem: Problem,
+        repaired: bool,
+    ) {
+        let path = entity.prefix().to_path_buf();
+        warn!(target: "app::store::Check::record", "`{path}`: {problem:?} (repaired: {repaired})");
+        self.report.findings.push(Finding {
+            path,
+            problem,
+            repaired,
+        });
+    }
+
+    /// Removes `entity` along with all of its contents, if repairs are enabled.
+    ///
+    /// Returns `true` if `entity` was removed.
+    async fn remove(&self, entity: &Entity<'_, impl AsRef<Utf8Path>>) -> bool {
+        if !self.repair {
+            return false;
+        }
+        trace!(target: "app::store::Check::remove", "remove `{}`", entity.prefix());
+        match self.root.remove_dir_all(entity.prefix()).await {
+            Ok(()) => true,
+            Err(e) => {
+                warn!(target: "app::store::Check::remove", "failed to remove `{}`: {e}", entity.prefix());
+                false
+            }
+        }
+    }
+
+    /// Returns all repositories in the store.
+    async fn repositories(&self) -> anyhow::Result<Vec<Repository<'a>>> {
+        let mut repos = vec![];
+        for user in names(&self.root, "users").await? {
+            let user = self.root.child(format!("users/{user}"));
+            for repo in names(&user, "repos").await? {
+                repos.push(user.child(format!("repos/{repo}")).into());
+            }
+        }
+        Ok(repos)
+    }
+
+    /// Verifies metadata and contents of the JSON-encoded `entity` and returns the decoded
+    /// contents.
+    async fn check_json<T: DeserializeOwned>(
+        &mut self,
+        entity: &Entity<'_, impl AsRef<Utf8Path>>,
+    ) -> Option<T> {
+        self.report.checked += 1;
+        let res = async {
+            let meta = meta(entity).await?;
+            verify(entity, &meta).await?;
+            entity.get_content_json().await.map_err(|e| match e {
+                GetError::NotFound => Problem::MissingContent,
+                GetError::Internal(e) => Problem::InvalidContent {
+                    error: format!("{e:#}"),
+                },
+            })
+        }
+        .await;
+        res.map_err(|problem| self.record(entity, problem, false))
+            .ok()
+    }
+
+    /// Verifies metadata and contents of all blobs in `blobs`.
+    ///
+    /// Blobs lacking metadata or contents are removed, if repairs are enabled.
+    async fn check_blobs(&mut self, blobs: &Blobs<'_>) -> anyhow::Result<()> {
+        for name in names(blobs, "").await? {
+            self.report.checked += 1;
+            let blob = blobs.child(&name);
+            let res = async {
+                let meta = meta(&blob).await?;
+                verify(&blob, &meta).await
+            }
+            .await;
+            if let Err(problem) = res {
+                let repaired = matches!(problem, Problem::MissingMeta | Problem::MissingContent)
+                    && self.remove(&blob).await;
+                self.record(&blob, problem, repaired);
+            }
+        }
+        Ok(())
+    }
+
+    /// Verifies the tree rooted at `tree`, which is expected to have metadata `expected`.
+    ///
+    /// Nodes lacking metadata and nodes not listed in their parent directory are removed, if
+    /// repairs are enabled.
+    async fn check_tree(
+        &mut self,
+        blobs: &Blobs<'_>,
+        tree: Entity<'a, Utf8PathBuf>,
+        expected: Option<Meta>,
+    ) -> anyhow::Result<()> {
+        let mut nodes = vec![(tree, expected)];
+        while let Some((node, expected)) = nodes.pop() {
+            self.report.checked += 1;
+            let meta = match meta(&node).await {
+                Ok(meta) => meta,
+                Err(Problem::MissingMeta) if !node.exists().await => {
+                    self.record(&node, Problem::MissingNode, false);
+                    continue;
+                }
+                Err(problem @ Problem::MissingMeta) => {
+                    let repaired = self.remove(&node).await;
+                    self.record(&node, problem, repaired);
+                    continue;
+                }
+                Err(problem) => {
+                    self.record(&node, problem, false);
+                    continue;
+                }
+            };
+            if let Some(expected) = expected.filter(|expected| {
+                expected.size != meta.size
+                    || expected.mime != meta.mime
+                    || !is_consistent(&expected.hash, &meta.hash)
+            }) {
+                self.record(&node, Problem::NodeMismatch { expected }, false);
+            }
+
+            let dir = match blobs.find(&meta.hash).await {
+                Ok(blob) if blob.size != meta.size => {
+                    self.record(
+                        &node,
+                        Problem::SizeMismatch {
+                            expected: meta.size,
+                            got: blob.size,
+                        },
+                        false,
+                    );
+                    None
+                }
+                Ok(_) if is_directory(&meta) => match async {
+                    blobs
+                        .blob(&meta.hash)?
+                        .get_content_json::<TreeDirectory<TreeEntry>>()
+                        .await
+                }
+                .await
+                {
+                    Ok(dir) => Some(dir),
+                    Err(e) => {
+                        self.record(
+                            &node,
+                            Problem::InvalidContent {
+                                error: format!("{e:?}"),
+                            },
+                            false,
+                        );
+                        None
+                    }
+                },
+                Ok(_) => None,
+                Err(GetError::NotFound) => {
+                    self.record(&node, Problem::MissingBlob, false);
+                    None
+                }
+                Err(GetError::Internal(e)) => {
+                    self.record(&node, Problem::unreadable(e), false);
+                    None
+                }
+            };
+
+            let listed = dir.is_some();
+            let children = names(&node, "entries").await?;
+            let mut entries: BTreeMap<_, _> = dir
+                .into_iter()
+                .flatten()
+                .map(|(name, entry)| (name.to_string(), entry.meta))
+                .collect();
+            for name in children {
+                let child = node.child(format!("entries/{name}"));
+                match entries.remove(&name) {
+                    Some(meta) => nodes.push((child, Some(meta))),
+                    None if listed => {
+                        let repaired = self.remove(&child).await;
+                        self.record(&child, Problem::UnexpectedNode, repaired);
+                    }
+                    None => nodes.push((child, None)),
+                }
+            }
+            for (name, meta) in entries {
+                nodes.push((node.child(format!("entries/{name}")), Some(meta)));
+            }
+        }
+        Ok(())
+    }
+}
+
+impl Store {
+    /// Verifies integrity of all entities in the store.
+    ///
+    /// Contents are verified against the digests in their metadata and directories against
+    /// the nodes stored under them. If `repair` is set, data left behind by interrupted
+    /// uploads is removed and an outdated layout is migrated. Outdated layouts are left
+    /// unmodified otherwise and trees and blobs are not checked in layouts preceding version 1.
+    ///
+    /// Must not be called while the store is used by a server.
+    pub async fn fsck(&self, repair: bool) -> anyhow::Result<Report> {
+        let mut check = Check::new(self, repair);
+        let version = self.layout_version().await?;
+        let migrated = version == LAYOUT_VERSION;
+        if !migrated {
+            if repair {
+                self.migrate().await.context("failed to migrate store")?;
+            }
+            let path = check.root.child(LAYOUT_VERSION_PATH);
+            check.record(&path, Problem::OutdatedLayout { version }, repair);
+        }
+        for user in names(&check.root, "users").await? {
+            let user = check.root.child(format!("users/{user}"));
+            _ = check.check_json::<UserRecord>(&user).await;
+        }
+        for repo in check.repositories().await? {
+            _ = check.check_json::<RepositoryConfig>(&repo).await;
+            if version < 1 && !repair {
+                // Outdated layouts store contents of tree nodes inline instead of in blobs
+                continue;
+            }
+            let blobs = repo.blobs();
+            check.check_blobs(&blobs).await?;
+            for tag in names(&repo, "tags").await? {
+                let tag = repo.child(format!("tags/{tag}"));
+                let expected = check
+                    .check_json::<TagEntry>(&tag)
+                    .await
+                    .and_then(|entry| entry.entry().ok())
+                    .map(|entry| entry.meta);
+                check
+                    .check_tree(&blobs, tag.child("tree"), expected)
+                    .await?;
+            }
+        }
+        Ok(check.report)
+    }
+
+    /// Removes data not referred to by any tree node, that is unreferenced blobs, content files
+    /// of tree nodes, which were moved into [Blobs], and entities left in staging.
+    ///
+    /// An outdated layout is migrated first. If `dry_run` is set, the data is only reported
+    /// and an outdated layout is reported without collecting any garbage, since it does not
+    /// store data where garbage is looked for.
+    ///
+    /// Must not be called while the store is used by a server, since entities being uploaded
+    /// would be removed.
+    pub async fn gc(&self, dry_run: bool) -> anyhow::Result<Report> {
+        let mut check = Check::new(self, !dry_run);
+        let version = self.layout_version().await?;
+        if version != LAYOUT_VERSION {
+            if !dry_run {
+                self.migrate().await.context("failed to migrate store")?;
+            }
+            let path = check.root.child(LAYOUT_VERSION_PATH);
+            check.record(&path, Problem::OutdatedLayout { version }, !dry_run);
+            if dry_run {
+                return Ok(check.report);
+            }
+        }
+        for repo in check.repositories().await? {
+            let blobs = repo.blobs();
+            let mut referenced = HashSet::new();
+            for tag in names(&repo, "tags").await? {
+                let mut nodes = vec![repo.child(format!("tags/{tag}/tree"))];
+                while let Some(node) = nodes.pop() {
+                    check.report.checked += 1;
+                    if let Ok(meta) = node.get_meta().await {
+                        if node.has_content().await {
+                            // Only remove contents, which are known to be stored in a blob
+                            let repaired = check.repair
+                                && blobs.find(&meta.hash).await.is_ok()
+                                && node.remove_content().await.is_ok();
+                            check.record(&node, Problem::OrphanedContent, repaired);
+                        }
+                        if let Some(name) = blob_name(&meta.hash) {
+                            _ = referenced.insert(name);
+                        }
+                    }
+                    for name in names(&node, "entries").await? {
+                        nodes.push(node.child(format!("entries/{name}")));
+                    }
+                }
+            }
+
+            for name in names(&blobs, "").await? {
+                if !referenced.contains(&name) {
+                    check.report.checked += 1;
+                    let blob = blobs.child(&name);
+                    let repaired = check.remove(&blob).await;
+                    check.record(&blob, Problem::UnreferencedBlob, repaired);
+                }
+            }
+        }
+        for staged in names(&check.root, STAGING_DIR).await? {
+            let staged = check.root.child(format!("{STAGING_DIR}/{staged}"));
+            let repaired = check.remove(&staged).await;
+            check.record(&staged, Problem::StagedEntity, repaired);
+        }
+        Ok(check.report)
+    }
+}
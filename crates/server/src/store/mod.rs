@@ -1,12 +1,20 @@
 // SPDX-FileCopyrightText: 2022 Profian Inc. <opensource@profian.com>
 // SPDX-License-Identifier: Apache-2.0
+mod audit;
+mod backend;
+mod blob;
 mod entity;
+mod fsck;
 mod repo;
 mod tag;
 mod tree;
 mod user;
 
+pub use audit::*;
+pub use backend::*;
+pub use blob::*;
 pub use entity::*;
+pub use fsck::*;
 pub use repo::*;
 pub use tag::*;
 pub use tree::*;
@@ -14,34 +22,111 @@ pub use user::*;
 
 use drawbridge_type::{Meta, RepositoryContext, TagContext, TreeContext, UserContext, UserRecord};
 
+use anyhow::{anyhow, Context};
 use async_std::io;
 use camino::{Utf8Path, Utf8PathBuf};
-use cap_async_std::fs_utf8::Dir;
 use futures::try_join;
+use tracing::info;
+
+/// Version of the store layout written by this crate.
+///
+/// Version 1 stores tree node contents in content-addressed repository [Blobs]. Version 2
+/// records the completeness of tags as their nodes are uploaded, see [Tag::missing_nodes].
+const LAYOUT_VERSION: u32 = 2;
+
+/// Path of the file recording the store layout version. Stores without it have version 0.
+const LAYOUT_VERSION_PATH: &str = "version";
 
 #[derive(Debug)]
 pub struct Store {
-    root: Dir,
+    root: Box<dyn StorageBackend>,
 }
 
-async fn upsert_dir(root: &Dir, path: impl AsRef<Utf8Path>) -> io::Result<()> {
+async fn upsert_dir(root: &dyn StorageBackend, path: impl AsRef<Utf8Path>) -> io::Result<()> {
     let path = path.as_ref();
     if !root.is_dir(path).await {
-        root.create_dir(path)
+        root.create_dir(path).await
     } else {
         Ok(())
     }
 }
 
 impl Store {
-    /// Initalizes a new [Store] at `root`
-    pub async fn new(root: Dir) -> io::Result<Self> {
-        upsert_dir(&root, "users").await?;
+    /// Initalizes a new [Store] in `root`
+    pub async fn new(root: Box<dyn StorageBackend>) -> io::Result<Self> {
+        upsert_dir(root.as_ref(), "users").await?;
+        upsert_dir(root.as_ref(), STAGING_DIR).await?;
+        upsert_dir(root.as_ref(), AUDIT_DIR).await?;
         Ok(Self { root })
     }
 
+    /// Opens the [Store] in the [StorageBackend] configured by `conf`, initializing it if
+    /// necessary.
+    pub async fn open(conf: impl Into<StorageConfig>) -> anyhow::Result<Self> {
+        let conf = conf.into();
+        let root = conf
+            .clone()
+            .open()
+            .await
+            .with_context(|| format!("failed to open storage backend `{conf:?}`"))?;
+        Self::new(root)
+            .await
+            .with_context(|| format!("failed to initialize store in `{conf:?}`"))
+    }
+
+    /// Returns the layout version of the store, failing if it is not supported by this crate.
+    async fn layout_version(&self) -> anyhow::Result<u32> {
+        let version = match self.root.read(LAYOUT_VERSION_PATH.as_ref()).await {
+            Ok(v) => String::from_utf8(v)
+                .context("failed to decode store layout version")?
+                .trim()
+                .parse()
+                .context("failed to parse store layout version")?,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => 0,
+            Err(e) => {
+                return Err(anyhow::Error::new(e).context("failed to read store layout version"))
+            }
+        };
+        if version > LAYOUT_VERSION {
+            return Err(anyhow!("unsupported store layout version `{version}`"));
+        }
+        Ok(version)
+    }
+
+    /// Migrates the store from layouts written by previous versions of this crate.
+    pub async fn migrate(&self) -> anyhow::Result<()> {
+        let version = self.layout_version().await?;
+        if version < LAYOUT_VERSION {
+            info!(target: "app::store::Store::migrate", "migrate store layout from version {version} to {LAYOUT_VERSION}");
+            let root = Entity::new(self.root.as_ref());
+            let users = root
+                .read_dir("users")
+                .await
+                .map_err(|e| anyhow!("failed to read users: {e:?}"))?;
+            for user in users {
+                let repos = root
+                    .read_dir(format!("users/{user}/repos"))
+                    .await
+                    .map_err(|e| anyhow!("failed to read repositories of `{user}`: {e:?}"))?;
+                for repo in repos {
+                    Repository::from(root.child(format!("users/{user}/repos/{repo}")))
+                        .migrate(version)
+                        .await
+                        .with_context(|| format!("failed to migrate repository `{user}/{repo}`"))?;
+                }
+            }
+        }
+        self.root
+            .write(
+                LAYOUT_VERSION_PATH.as_ref(),
+                LAYOUT_VERSION.to_string().into_bytes(),
+            )
+            .await
+            .context("failed to write store layout version")
+    }
+
     pub fn user(&self, UserContext { name }: &UserContext) -> User<'_, Utf8PathBuf> {
-        Entity::new(&self.root)
+        Entity::new(self.root.as_ref())
             .child(format!("users/{name}"))
             .into()
     }
@@ -53,8 +138,10 @@ impl Store {
         rec: &UserRecord,
     ) -> Result<User<'_>, CreateError<anyhow::Error>> {
         let user = self.user(cx);
-        user.create_dir("").await?;
-        try_join!(user.create_json(meta, rec), user.create_dir("repos"),)?;
+        user.create_with(|staged| async move {
+            try_join!(staged.create_json(meta, rec), staged.create_dir("repos")).map(|_| ())
+        })
+        .await?;
         Ok(user)
     }
 
@@ -1,15 +1,42 @@
 // SPDX-FileCopyrightText: 2022 Profian Inc. <opensource@profian.com>
 // SPDX-License-Identifier: Apache-2.0
 
-use super::{CreateError, Entity, GetError, Tag};
+use super::{now, stage_missing, Blobs, CreateError, Entity, GetError, Tag, CREATED_PATH};
 
+use std::num::NonZeroUsize;
 use std::ops::Deref;
 
 use drawbridge_type::digest::{Algorithms, ContentDigest};
-use drawbridge_type::{Meta, RepositoryConfig, TagEntry, TagName};
+use drawbridge_type::{
+    Meta, RepositoryConfig, TagEntry, TagName, TagQuery, TagResolution, TreePath,
+};
 
-use anyhow::{anyhow, Context};
+use anyhow::{anyhow, bail, Context};
 use camino::{Utf8Path, Utf8PathBuf};
+use futures::try_join;
+use semver::{Version, VersionReq};
+use serde::Serialize;
+
+/// Encodes `val` as JSON and computes the digest of the encoding.
+pub(super) fn encode_json(
+    val: &impl Serialize,
+) -> Result<(ContentDigest, Vec<u8>), GetError<anyhow::Error>> {
+    // TODO: Optimize hash computation
+    let buf = serde_json::to_vec(val)
+        .context("failed to encode value as JSON")
+        .map_err(GetError::Internal)?;
+    let (n, hash) = Algorithms::default()
+        .read_sync(&buf[..])
+        .context("failed to compute digest")
+        .map_err(GetError::Internal)?;
+    if n != buf.len() as u64 {
+        return Err(GetError::Internal(anyhow!(
+            "invalid amount of bytes read, expected: {}, got {n}",
+            buf.len(),
+        )));
+    }
+    Ok((hash, buf))
+}
 
 #[repr(transparent)]
 #[derive(Copy, Clone, Debug)]
@@ -39,42 +66,149 @@ impl<'a, P: AsRef<Utf8Path>> Repository<'a, P> {
         Ok(conf.public)
     }
 
+    /// Replaces the configuration of the repository, if `precondition` holds for the metadata
+    /// of the current configuration.
+    pub async fn update(
+        &self,
+        meta: &Meta,
+        conf: &RepositoryConfig,
+        precondition: impl FnOnce(&Meta) -> bool,
+    ) -> Result<(), CreateError<anyhow::Error>> {
+        self.replace_json(meta, conf, precondition).await
+    }
+
+    /// Deletes the repository along with all of its tags and blobs.
+    pub async fn delete(&self) -> Result<(), GetError<anyhow::Error>> {
+        self.remove().await
+    }
+
     pub async fn tags(&self) -> Result<Vec<TagName>, GetError<anyhow::Error>> {
         self.read_dir("tags")
             .await?
-            .try_fold(vec![], |mut names, entry| {
-                let name = entry?
-                    .file_name()
-                    .context("failed to read tag name")?
-                    .parse()
-                    .context("failed to parse tag name")?;
-                names.push(name);
-                Ok(names)
-            })
+            .into_iter()
+            .map(|name| name.parse().context("failed to parse tag name"))
+            .collect::<anyhow::Result<_>>()
             .map_err(GetError::Internal)
     }
 
-    pub async fn tags_json(&self) -> Result<(ContentDigest, Vec<u8>), GetError<anyhow::Error>> {
-        // TODO: Optimize hash computation
-        let tags = self.tags().await?;
-        let buf = serde_json::to_vec(&tags)
-            .context("failed to encode tags as JSON")
-            .map_err(GetError::Internal)?;
-        let (n, hash) = Algorithms::default()
-            .read_sync(&buf[..])
-            .context("failed to compute tag digest")
-            .map_err(GetError::Internal)?;
-        if n != buf.len() as u64 {
-            return Err(GetError::Internal(anyhow!(
-                "invalid amount of bytes read, expected: {}, got {n}",
-                buf.len(),
-            )));
+    /// Returns names of tags matching `query` in semantic version order along with the cursor of
+    /// the next page, if there are more matching tags than the `limit` of the `query`.
+    ///
+    /// Tags without a recorded creation time never match `created_after`.
+    pub async fn query_tags(
+        &self,
+        query: &TagQuery,
+    ) -> Result<(Vec<TagName>, Option<TagName>), GetError<anyhow::Error>> {
+        let mut names: Vec<_> = self
+            .tags()
+            .await?
+            .into_iter()
+            .filter(|name| query.matches_name(name))
+            .collect();
+        names.sort_by(|a, b| Version::cmp(a, b));
+
+        let limit = query.limit.map_or(usize::MAX, NonZeroUsize::get);
+        let mut page = Vec::with_capacity(limit.min(names.len()));
+        for name in names {
+            let tag = self.tag(&name);
+            if !query.yanked && tag.is_yanked().await? {
+                continue;
+            }
+            if !query.incomplete && !tag.is_sealed().await? {
+                continue;
+            }
+            if let Some(created_after) = query.created_after {
+                if !matches!(tag.created_at().await?, Some(t) if t > created_after) {
+                    continue;
+                }
+            }
+            if page.len() == limit {
+                let next = page.last().cloned();
+                return Ok((page, next));
+            }
+            page.push(name);
+        }
+        Ok((page, None))
+    }
+
+    /// Returns names of tags matching `query` encoded as JSON along with the cursor of the next
+    /// page, see [Self::query_tags].
+    pub async fn tags_json(
+        &self,
+        query: &TagQuery,
+    ) -> Result<(ContentDigest, Vec<u8>, Option<TagName>), GetError<anyhow::Error>> {
+        let (tags, next) = self.query_tags(query).await?;
+        let (hash, buf) = encode_json(&tags)?;
+        Ok((hash, buf, next))
+    }
+
+    /// Returns the name of the highest tag satisfying `req`, omitting yanked and incomplete tags.
+    /// Pre-release tags are considered as described in [TagName::matches].
+    pub async fn resolve(
+        &self,
+        req: &VersionReq,
+        pre: bool,
+    ) -> Result<TagName, GetError<anyhow::Error>> {
+        let (names, _) = self.query_tags(&TagQuery::default()).await?;
+        names
+            .into_iter()
+            .rev()
+            .find(|name| name.matches(req, pre))
+            .ok_or(GetError::NotFound)
+    }
+
+    /// Returns the highest tag satisfying `req` along with its entry encoded as JSON, see
+    /// [Self::resolve].
+    pub async fn resolve_json(
+        &self,
+        req: &VersionReq,
+        pre: bool,
+    ) -> Result<(ContentDigest, Vec<u8>), GetError<anyhow::Error>> {
+        let name = self.resolve(req, pre).await?;
+        let entry = self.tag(&name).get_content_json().await?;
+        encode_json(&TagResolution { name, entry })
+    }
+
+    /// Returns the content-addressed storage of the repository.
+    pub fn blobs(&self) -> Blobs<'a, Utf8PathBuf> {
+        self.child("blobs").into()
+    }
+
+    /// Migrates all tags of the repository from the store layout `version`.
+    ///
+    /// Tree node contents stored inline by layouts preceding version 1 are moved into [Blobs] of
+    /// the repository and completeness of tags, which is not recorded by layouts preceding
+    /// version 2, is recorded.
+    pub(super) async fn migrate(&self, version: u32) -> anyhow::Result<()> {
+        if version < 1 {
+            match self.create_dir("blobs").await {
+                Ok(()) | Err(CreateError::Occupied) => {}
+                Err(e) => bail!("failed to create blob directory: {e:?}"),
+            }
+        }
+        let tags = self.tags().await.map_err(|e| match e {
+            GetError::NotFound => anyhow!("repository has no tag directory"),
+            GetError::Internal(e) => e,
+        })?;
+        for name in tags {
+            let tag = self.tag(&name);
+            async {
+                if version < 1 {
+                    tag.node(&TreePath::ROOT).migrate().await?;
+                }
+                if version < 2 {
+                    tag.recount_missing().await?;
+                }
+                anyhow::Ok(())
+            }
+            .await
+            .with_context(|| format!("failed to migrate tag `{name}`"))?;
         }
-        Ok((hash, buf))
+        Ok(())
     }
 
     pub fn tag(&self, name: &TagName) -> Tag<'a, Utf8PathBuf> {
-        self.child(format!("tags/{name}")).into()
+        Tag::new(self.child(format!("tags/{name}")), self.blobs())
     }
 
     pub async fn create_tag(
@@ -84,8 +218,16 @@ impl<'a, P: AsRef<Utf8Path>> Repository<'a, P> {
         entry: &TagEntry,
     ) -> Result<Tag<'a, Utf8PathBuf>, CreateError<anyhow::Error>> {
         let tag = self.tag(name);
-        tag.create_dir("").await?;
-        tag.create_json(meta, entry).await?;
+        let created = now();
+        tag.create_with(|staged| async move {
+            try_join!(
+                staged.create_json(meta, entry),
+                staged.create_file_json(CREATED_PATH, &created),
+                stage_missing(&staged)
+            )
+            .map(|_| ())
+        })
+        .await?;
         Ok(tag)
     }
 }
@@ -1,60 +1,188 @@
 // SPDX-FileCopyrightText: 2022 Profian Inc. <opensource@profian.com>
 // SPDX-License-Identifier: Apache-2.0
 
-use super::{CreateError, Entity, Node};
+use super::{is_consistent, now, Blobs, CreateError, Entity, GetError, Node};
 
+use std::collections::{BTreeMap, BTreeSet};
 use std::ops::Deref;
 
-use drawbridge_type::{Meta, TreeDirectory, TreeEntry, TreePath};
+use drawbridge_type::digest::Algorithm;
+use drawbridge_type::tag::{
+    ARCHIVE_DIGEST_KEY, ARCHIVE_DIRECTORY_KEY, ARCHIVE_MIME_KEY, ARCHIVE_ROOT,
+};
+use drawbridge_type::{Meta, TagEntry, TagYank, TreeDirectory, TreeEntry, TreePath};
 
+use anyhow::{anyhow, bail, Context};
+use async_tar::{Archive, Builder, EntryType, Header};
 use camino::{Utf8Path, Utf8PathBuf};
-use futures::{try_join, AsyncRead};
-use tracing::debug;
+use futures::io::empty;
+use futures::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt, StreamExt};
+use sha2::{Digest, Sha256};
+use tracing::{debug, trace};
 
-#[repr(transparent)]
-#[derive(Copy, Clone, Debug)]
-pub struct Tag<'a, P = Utf8PathBuf>(Entity<'a, P>);
+/// Path of the file recording the yank state of a tag relative to the tag.
+const YANK_PATH: &str = "yank.json";
+
+/// Path of the file recording the creation time of a tag relative to the tag.
+pub(super) const CREATED_PATH: &str = "created.json";
+
+/// Path of the file recording the time a tag was sealed at relative to the tag.
+const SEALED_PATH: &str = "sealed.json";
+
+/// Path of the directory recording tree nodes missing from an unsealed tag relative to the tag.
+///
+/// It holds a file named by [missing_name] for each node, which is not uploaded yet, but declared
+/// by an uploaded directory or, for the root, by the tag entry.
+const MISSING_DIR: &str = "missing";
+
+#[derive(Clone, Debug)]
+pub struct Tag<'a, P = Utf8PathBuf> {
+    entity: Entity<'a, P>,
+    blobs: Blobs<'a>,
+}
 
 impl<'a, P> Deref for Tag<'a, P> {
     type Target = Entity<'a, P>;
 
     fn deref(&self) -> &Self::Target {
-        &self.0
+        &self.entity
     }
 }
 
-impl<'a, P> From<Entity<'a, P>> for Tag<'a, P> {
-    fn from(entity: Entity<'a, P>) -> Self {
-        Self(entity)
+impl<'a, P> Tag<'a, P> {
+    pub(super) fn new(entity: Entity<'a, P>, blobs: Blobs<'a>) -> Self {
+        Self { entity, blobs }
     }
 }
 
+/// Returns the name of the file recording the node at `path` as missing, see [MISSING_DIR].
+fn missing_name(path: &TreePath) -> String {
+    hex::encode(Sha256::digest(path.to_string()))
+}
+
+/// Records the root node of a new tag staged at `staged` as missing.
+pub(super) async fn stage_missing(
+    staged: &Entity<'_, impl AsRef<Utf8Path>>,
+) -> Result<(), CreateError<anyhow::Error>> {
+    staged.create_dir(MISSING_DIR).await?;
+    staged
+        .create_file_json(
+            Utf8Path::new(MISSING_DIR).join(missing_name(&TreePath::ROOT)),
+            &TreePath::ROOT,
+        )
+        .await
+}
+
+/// Returns the path of the node at `path` relative to the root node of the tree.
+fn node_path(path: &TreePath) -> Utf8PathBuf {
+    if path.is_empty() {
+        Utf8PathBuf::new()
+    } else {
+        format!("entries/{}", path.intersperse("/entries/")).into()
+    }
+}
+
+/// Parses the tree path of the node held by the tag tree archive member named `name`.
+fn archive_path(name: &[u8]) -> Result<TreePath, CreateError<anyhow::Error>> {
+    let name = std::str::from_utf8(name)
+        .map_err(|_| CreateError::InvalidArchive("member name is not valid UTF-8".into()))?;
+    let path = match name.strip_prefix(ARCHIVE_ROOT) {
+        Some("") => return Ok(TreePath::ROOT),
+        Some(path) => path.strip_prefix('/'),
+        None => None,
+    };
+    path.and_then(|path| path.parse().ok()).ok_or_else(|| {
+        CreateError::InvalidArchive(format!("member name `{name}` is not a valid tree path"))
+    })
+}
+
+/// Returns the name of the tag tree archive member holding the node at `path`.
+fn archive_name(path: &TreePath) -> String {
+    if path.is_empty() {
+        ARCHIVE_ROOT.into()
+    } else {
+        format!("{ARCHIVE_ROOT}/{path}")
+    }
+}
+
+/// Returns a function converting failures to read the node at `path` into errors.
+fn node_error(path: &TreePath) -> impl '_ + FnOnce(GetError<anyhow::Error>) -> anyhow::Error {
+    move |e| match e {
+        GetError::NotFound => anyhow!("node `{path}` is missing"),
+        GetError::Internal(e) => e.context(format!("failed to read node `{path}`")),
+    }
+}
+
+/// Encodes `records` as the contents of a PAX extended header.
+fn pax_records<'r>(records: impl IntoIterator<Item = (&'r str, &'r [u8])>) -> Vec<u8> {
+    let mut buf = vec![];
+    for (key, value) in records {
+        // The length prefix of each record includes its own decimal digits.
+        let rest = key.len() + value.len() + 3;
+        let mut len = rest;
+        while rest + len.to_string().len() != len {
+            len = rest + len.to_string().len();
+        }
+        buf.extend_from_slice(format!("{len} {key}=").as_bytes());
+        buf.extend_from_slice(value);
+        buf.push(b'\n');
+    }
+    buf
+}
+
+/// Appends a PAX extended header holding `records` to `archive`, which applies to the member
+/// appended next.
+async fn append_pax<W: Send + Sync + Unpin + AsyncWrite>(
+    archive: &mut Builder<W>,
+    records: Vec<(&str, &[u8])>,
+) -> anyhow::Result<()> {
+    let buf = pax_records(records);
+    let mut header = Header::new_ustar();
+    header.set_entry_type(EntryType::XHeader);
+    header.set_mode(0o644);
+    header.set_size(buf.len() as _);
+    archive
+        .append_data(&mut header, "PaxHeader", buf.as_slice())
+        .await
+        .context("failed to write PAX extended header")
+}
+
 impl<'a, P: AsRef<Utf8Path>> Tag<'a, P> {
     pub fn node(&self, path: &TreePath) -> Node<'a, Utf8PathBuf> {
-        if path.is_empty() {
-            self.0.child("tree").into()
-        } else {
-            self.0
-                .child(format!("tree/entries/{}", path.intersperse("/entries/")))
-                .into()
-        }
+        Node::new(
+            self.entity
+                .child(Utf8Path::new("tree").join(node_path(path))),
+            self.blobs.clone(),
+        )
     }
 
     pub async fn create_file_node(
         &self,
         path: &TreePath,
         meta: Meta,
-        rdr: impl Unpin + AsyncRead,
+        rdr: impl Send + Unpin + AsyncRead,
     ) -> Result<Node<'a, Utf8PathBuf>, CreateError<anyhow::Error>> {
-        // TODO: Validate node hash against parents' expected values
-        // https://github.com/profianinc/drawbridge/issues/77
+        let size = match (meta.size, self.blobs.find(&meta.hash).await) {
+            (0, Ok(blob)) => blob.size,
+            (_, Err(GetError::Internal(e))) => return Err(CreateError::Internal(e)),
+            (size, _) => size,
+        };
+        self.check_expected(
+            path,
+            &Meta {
+                size,
+                ..meta.clone()
+            },
+        )
+        .await?;
         let node = self.node(path);
-        node.create_dir("").await.map_err(|e| {
-            debug!(target: "app::store::Tag::create_file_node", "failed to create content directory: {:?}", e);
-            e
-        })?;
-        node.create_from_reader(meta, rdr).await?;
-        Ok(node)
+        let res = node.create_file(meta, rdr).await;
+        if matches!(res, Ok(()) | Err(CreateError::Occupied)) {
+            self.track_created(path, None)
+                .await
+                .map_err(CreateError::Internal)?;
+        }
+        res.map(|()| node)
     }
 
     pub async fn create_directory_node(
@@ -63,14 +191,457 @@ impl<'a, P: AsRef<Utf8Path>> Tag<'a, P> {
         meta: Meta,
         dir: &TreeDirectory<TreeEntry>,
     ) -> Result<Node<'a, Utf8PathBuf>, CreateError<anyhow::Error>> {
-        // TODO: Validate node hash against parents' expected values
-        // https://github.com/profianinc/drawbridge/issues/77
+        self.check_expected(path, &meta).await?;
         let node = self.node(path);
-        node.create_dir("").await.map_err(|e| {
-            debug!(target: "app::store::Tag::create_directory_node", "failed to create content directory: {:?}", e);
+        let res = node.create_directory(meta, dir).await;
+        if matches!(res, Ok(()) | Err(CreateError::Occupied)) {
+            self.track_created(path, Some(dir))
+                .await
+                .map_err(CreateError::Internal)?;
+        }
+        res.map(|()| node)
+    }
+
+    /// Creates the whole tree of the tag from the tar archive read from `rdr` atomically and seals
+    /// the tag. Returns the amount of nodes created.
+    ///
+    /// Archive members must be regular files holding the node contents, named as described in
+    /// [ARCHIVE_ROOT] and preceded by their parent directory. Each node is verified against the
+    /// entry declared by its parent or, for the root, the tag entry and all declared nodes must
+    /// be present.
+    pub async fn create_tree_from_archive(
+        &self,
+        rdr: impl Send + Unpin + AsyncRead,
+    ) -> Result<usize, CreateError<anyhow::Error>> {
+        let mut expected =
+            BTreeMap::from([(TreePath::ROOT, self.expected_meta(&TreePath::ROOT).await?)]);
+        let mut entries = Archive::new(rdr)
+            .entries()
+            .context("failed to read archive")
+            .map_err(CreateError::Internal)?;
+        let blobs = &self.blobs;
+        let created = self
+            .entity
+            .child("tree")
+            .create_with(|staged| async move {
+                let mut created = 0;
+                while let Some(entry) = entries.next().await {
+                    let mut entry = entry.map_err(|e| CreateError::InvalidArchive(e.to_string()))?;
+                    if !entry.header().entry_type().is_file() {
+                        return Err(CreateError::InvalidArchive(
+                            "members must be regular files".into(),
+                        ));
+                    }
+                    let path = archive_path(&entry.path_bytes())?;
+                    trace!(target: "app::store::Tag::create_tree_from_archive", "create node at `{path}`");
+                    let meta = expected.remove(&path).ok_or(CreateError::Undeclared)?;
+                    if !meta.hash.contains_key(&Algorithm::Sha256) {
+                        return Err(CreateError::InvalidArchive(format!(
+                            "entry of `{path}` lacks a SHA-256 content digest"
+                        )));
+                    }
+                    let size = entry
+                        .header()
+                        .size()
+                        .map_err(|e| CreateError::InvalidArchive(e.to_string()))?;
+                    if size != meta.size {
+                        return Err(CreateError::LengthMismatch {
+                            expected: meta.size,
+                            got: size,
+                        });
+                    }
+
+                    let node = staged.child(node_path(&path));
+                    if !path.is_empty() {
+                        node.create_dir("").await?;
+                    }
+                    if meta.mime == TreeDirectory::<()>::TYPE {
+                        let mut buf = vec![];
+                        _ = entry
+                            .read_to_end(&mut buf)
+                            .await
+                            .map_err(|e| CreateError::InvalidArchive(e.to_string()))?;
+                        _ = blobs.create(&meta.hash, meta.size, buf.as_slice()).await?;
+                        let dir: TreeDirectory<TreeEntry> = serde_json::from_slice(&buf)
+                            .map_err(|e| {
+                                CreateError::InvalidArchive(format!(
+                                    "failed to decode directory `{path}`: {e}"
+                                ))
+                            })?;
+                        for (name, entry) in dir {
+                            let path = path.iter().cloned().chain([name]).collect();
+                            _ = expected.insert(path, entry.meta);
+                        }
+                        node.create_dir("entries").await?;
+                    } else {
+                        _ = blobs.create(&meta.hash, meta.size, &mut entry).await?;
+                    }
+                    node.write_meta(&meta).await?;
+                    created += 1;
+                }
+                if let Some(path) = expected.into_keys().next() {
+                    return Err(CreateError::InvalidArchive(format!(
+                        "declared node `{path}` is missing"
+                    )));
+                }
+                Ok(created)
+            })
+            .await?;
+        self.entity
+            .write_json(SEALED_PATH, &now())
+            .await
+            .map_err(|e| {
+                debug!(target: "app::store::Tag::create_tree_from_archive", "failed to seal tag: {:?}", e);
+                CreateError::Internal(e)
+            })?;
+        Ok(created)
+    }
+
+    /// Writes the tree of the tag to `wtr` as a tar archive, which can be unpacked by common tools,
+    /// and closes `wtr`.
+    ///
+    /// Nodes are named as described in [ARCHIVE_ROOT], with directories written as directory
+    /// members, parents preceding their children. Each member is preceded by a PAX extended
+    /// header holding the content digest and media type of the node and, for directories, the
+    /// directory contents, so that the archive can be verified against the tag entry.
+    pub async fn write_archive(
+        &self,
+        wtr: impl Send + Sync + Unpin + AsyncWrite,
+    ) -> anyhow::Result<()> {
+        let mut archive = Builder::new(wtr);
+        let mut paths = vec![TreePath::ROOT];
+        while let Some(path) = paths.pop() {
+            let node = self.node(&path);
+            let meta = node.get_meta().await.map_err(node_error(&path))?;
+            let hash = meta.hash.to_string();
+            let mime = meta.mime.to_string();
+            let mut records = vec![
+                (ARCHIVE_DIGEST_KEY, hash.as_bytes()),
+                (ARCHIVE_MIME_KEY, mime.as_bytes()),
+            ];
+            let mut header = Header::new_gnu();
+            header.set_mtime(0);
+            if meta.mime == TreeDirectory::<()>::TYPE {
+                let buf = self
+                    .blobs
+                    .blob(&meta.hash)
+                    .map_err(node_error(&path))?
+                    .read_content()
+                    .await
+                    .map_err(node_error(&path))?;
+                let dir: TreeDirectory<TreeEntry> = serde_json::from_slice(&buf)
+                    .with_context(|| format!("failed to decode directory `{path}`"))?;
+                paths.extend(
+                    dir.into_iter()
+                        .rev()
+                        .map(|(name, _)| path.iter().cloned().chain([name]).collect()),
+                );
+                records.push((ARCHIVE_DIRECTORY_KEY, &buf));
+                append_pax(&mut archive, records).await?;
+                header.set_entry_type(EntryType::Directory);
+                header.set_mode(0o755);
+                header.set_size(0);
+                archive
+                    .append_data(&mut header, archive_name(&path), empty())
+                    .await
+            } else {
+                let (meta, rdr) = node.get().await.map_err(node_error(&path))?;
+                append_pax(&mut archive, records).await?;
+                header.set_entry_type(EntryType::Regular);
+                header.set_mode(0o644);
+                header.set_size(meta.size);
+                archive
+                    .append_data(&mut header, archive_name(&path), rdr)
+                    .await
+            }
+            .with_context(|| format!("failed to write node `{path}` to archive"))?;
+        }
+        archive
+            .into_inner()
+            .await
+            .context("failed to finish archive")?
+            .close()
+            .await
+            .context("failed to close archive")
+    }
+
+    /// Returns the metadata the node at `path` is expected to have according to its parent
+    /// directory or, for the root, the tag entry. Entries of signed tags are decoded from their
+    /// payload.
+    async fn expected_meta(&self, path: &TreePath) -> Result<Meta, CreateError<anyhow::Error>> {
+        let Some((name, parent)) = path.split_last() else {
+            return match self.entity.get_content_json::<TagEntry>().await {
+                Ok(entry) => entry
+                    .entry()
+                    .map(|entry| entry.meta)
+                    .map_err(CreateError::Internal),
+                Err(GetError::NotFound) => Err(CreateError::Undeclared),
+                Err(GetError::Internal(e)) => Err(CreateError::Internal(e)),
+            };
+        };
+        let parent = self.node(&parent.iter().cloned().collect());
+        let meta = parent.get_meta().await.map_err(|e| match e {
+            GetError::NotFound => CreateError::Undeclared,
+            GetError::Internal(e) => CreateError::Internal(e),
+        })?;
+        if meta.mime != TreeDirectory::<()>::TYPE {
+            return Err(CreateError::Undeclared);
+        }
+        let mut dir = self
+            .blobs
+            .blob(&meta.hash)
+            .map_err(|e| match e {
+                GetError::NotFound => CreateError::Undeclared,
+                GetError::Internal(e) => CreateError::Internal(e),
+            })?
+            .get_content_json::<TreeDirectory<TreeEntry>>()
+            .await
+            .map_err(|e| match e {
+                GetError::NotFound => {
+                    CreateError::Internal(anyhow!("parent directory contents are missing"))
+                }
+                GetError::Internal(e) => CreateError::Internal(e),
+            })?;
+        dir.remove(name)
+            .map(|entry| entry.meta)
+            .ok_or(CreateError::Undeclared)
+    }
+
+    /// Fails unless `meta` matches the metadata expected for the node at `path`.
+    async fn check_expected(
+        &self,
+        path: &TreePath,
+        meta: &Meta,
+    ) -> Result<(), CreateError<anyhow::Error>> {
+        let expected = self.expected_meta(path).await?;
+        if expected.size != meta.size
+            || expected.mime != meta.mime
+            || !is_consistent(&expected.hash, &meta.hash)
+        {
+            debug!(target: "app::store::Tag::check_expected", "node at `{path}` does not match expected metadata {expected:?}");
+            return Err(CreateError::NodeMismatch);
+        }
+        Ok(())
+    }
+
+    /// Returns the time the tag was created at in seconds since the Unix epoch or `None` if the
+    /// tag was created by a version of this crate, which did not record it.
+    pub async fn created_at(&self) -> Result<Option<u64>, GetError<anyhow::Error>> {
+        match self.entity.read_json(CREATED_PATH).await {
+            Ok(time) => Ok(Some(time)),
+            Err(GetError::NotFound) => Ok(None),
+            Err(e) => Err(e),
+        }
+    }
+
+    /// Returns `true` if all tree nodes declared by the tag are uploaded.
+    pub async fn is_sealed(&self) -> Result<bool, GetError<anyhow::Error>> {
+        match self.entity.read_json::<u64>(SEALED_PATH).await {
+            Ok(_) => Ok(true),
+            Err(GetError::NotFound) => Ok(false),
+            Err(e) => Err(e),
+        }
+    }
+
+    /// Returns the amount of tree nodes, which are not uploaded yet, but declared by an uploaded
+    /// directory or, for the root, by the tag entry.
+    pub async fn missing_nodes(&self) -> Result<u64, GetError<anyhow::Error>> {
+        if self.is_sealed().await? {
+            return Ok(0);
+        }
+        self.entity
+            .read_dir(MISSING_DIR)
+            .await
+            .map(|names| names.len() as _)
+    }
+
+    /// Returns `true` if the node at `path` is uploaded.
+    async fn has_node(&self, path: &TreePath) -> Result<bool, GetError<anyhow::Error>> {
+        match self.node(path).get_meta().await {
+            Ok(_) => Ok(true),
+            Err(GetError::NotFound) => Ok(false),
+            Err(e) => Err(e),
+        }
+    }
+
+    /// Records the node at `path` as missing, unless it is uploaded.
+    async fn mark_missing(&self, path: &TreePath) -> anyhow::Result<()> {
+        if self.has_node(path).await.map_err(node_error(path))? {
+            return Ok(());
+        }
+        let marker = Utf8Path::new(MISSING_DIR).join(missing_name(path));
+        match self.entity.create_file_json(&marker, path).await {
+            Ok(()) | Err(CreateError::Occupied) => {}
+            Err(e) => return Err(anyhow!("failed to record node `{path}` as missing: {e:?}")),
+        }
+        // The node may have been uploaded concurrently before it was recorded
+        if self.has_node(path).await.map_err(node_error(path))? {
+            self.unmark_missing(path).await?;
+        }
+        Ok(())
+    }
+
+    /// Removes the record of the node at `path` being missing, if any.
+    async fn unmark_missing(&self, path: &TreePath) -> anyhow::Result<()> {
+        match self
+            .entity
+            .remove_file(Utf8Path::new(MISSING_DIR).join(missing_name(path)))
+            .await
+        {
+            Err(e) if e.kind() != std::io::ErrorKind::NotFound => Err(anyhow::Error::new(e)
+                .context(format!("failed to remove missing record of node `{path}`"))),
+            _ => Ok(()),
+        }
+    }
+
+    /// Returns names of the files recording missing nodes, see [MISSING_DIR].
+    async fn read_missing(&self) -> anyhow::Result<Vec<String>> {
+        self.entity
+            .read_dir(MISSING_DIR)
+            .await
+            .map_err(|e| match e {
+                GetError::NotFound => anyhow!("missing nodes are not recorded"),
+                GetError::Internal(e) => e,
+            })
+    }
+
+    /// Seals the tag, if no nodes are recorded as missing.
+    async fn seal_if_complete(&self) -> anyhow::Result<()> {
+        if !self.read_missing().await?.is_empty() {
+            return Ok(());
+        }
+        trace!(target: "app::store::Tag::seal_if_complete", "seal tag at `{}`", self.entity.prefix());
+        match self.entity.create_file_json(SEALED_PATH, &now()).await {
+            Ok(()) | Err(CreateError::Occupied) => Ok(()),
+            Err(e) => Err(anyhow!("failed to seal tag: {e:?}")),
+        }
+    }
+
+    /// Records the node at `path` as uploaded and the entries of `dir`, which are not uploaded
+    /// yet, as missing and seals the tag once no nodes are missing.
+    ///
+    /// Entries are recorded before the node itself is removed from the missing nodes, so that
+    /// the tag is not sealed while they are recorded concurrently.
+    async fn track_created(
+        &self,
+        path: &TreePath,
+        dir: Option<&TreeDirectory<TreeEntry>>,
+    ) -> anyhow::Result<()> {
+        for name in dir.into_iter().flat_map(|dir| dir.keys()) {
+            self.mark_missing(&path.iter().cloned().chain([name.clone()]).collect())
+                .await?;
+        }
+        self.unmark_missing(path).await?;
+        self.seal_if_complete().await.map_err(|e| {
+            debug!(target: "app::store::Tag::track_created", "failed to seal tag: {:?}", e);
             e
+        })
+    }
+
+    /// Finds all tree nodes declared by the tag, which are not uploaded yet, replaces the record
+    /// of missing nodes by them and seals the tag, if none are missing.
+    ///
+    /// Walks the whole tree, hence it is only used when migrating the store.
+    pub(super) async fn recount_missing(&self) -> anyhow::Result<()> {
+        let missing = self.find_missing().await.map_err(|e| match e {
+            GetError::NotFound => anyhow!("tag entry is missing"),
+            GetError::Internal(e) => e,
         })?;
-        try_join!(node.create_json(meta, dir), node.create_dir("entries"))?;
-        Ok(node)
+        match self.entity.create_dir(MISSING_DIR).await {
+            Ok(()) | Err(CreateError::Occupied) => {}
+            Err(e) => bail!("failed to create missing node directory: {e:?}"),
+        }
+        let names: BTreeSet<_> = missing.iter().map(missing_name).collect();
+        for name in self.read_missing().await? {
+            if !names.contains(&name) {
+                self.entity
+                    .remove_file(Utf8Path::new(MISSING_DIR).join(&name))
+                    .await
+                    .with_context(|| format!("failed to remove missing record `{name}`"))?;
+            }
+        }
+        for path in &missing {
+            self.mark_missing(path).await?;
+        }
+        self.seal_if_complete().await
+    }
+
+    /// Finds tree nodes, which are not uploaded yet, but declared by an uploaded directory or,
+    /// for the root, by the tag entry.
+    async fn find_missing(&self) -> Result<Vec<TreePath>, GetError<anyhow::Error>> {
+        if !self.has_node(&TreePath::ROOT).await? {
+            return Ok(vec![TreePath::ROOT]);
+        }
+        let mut missing = vec![];
+        let mut dirs = vec![TreePath::ROOT];
+        while let Some(path) = dirs.pop() {
+            let meta = self.node(&path).get_meta().await?;
+            if meta.mime != TreeDirectory::<()>::TYPE {
+                continue;
+            }
+            let dir = self
+                .blobs
+                .blob(&meta.hash)?
+                .get_content_json::<TreeDirectory<TreeEntry>>()
+                .await
+                .map_err(|e| match e {
+                    GetError::NotFound => {
+                        GetError::Internal(anyhow!("contents of directory `{path}` are missing"))
+                    }
+                    e => e,
+                })?;
+            for (name, _) in dir {
+                let path: TreePath = path.iter().cloned().chain([name]).collect();
+                if self.has_node(&path).await? {
+                    dirs.push(path);
+                } else {
+                    missing.push(path);
+                }
+            }
+        }
+        Ok(missing)
+    }
+
+    /// Returns the yank state of the tag, failing with [GetError::NotFound] if the tag is not
+    /// yanked.
+    pub async fn get_yank(&self) -> Result<TagYank, GetError<anyhow::Error>> {
+        self.entity.read_json(YANK_PATH).await
+    }
+
+    /// Returns `true` if the tag is yanked.
+    pub async fn is_yanked(&self) -> Result<bool, GetError<anyhow::Error>> {
+        match self.get_yank().await {
+            Ok(_) => Ok(true),
+            Err(GetError::NotFound) => Ok(false),
+            Err(e) => Err(e),
+        }
+    }
+
+    /// Yanks the tag on behalf of the user identified by `subject` and returns the yank state.
+    pub async fn yank(
+        &self,
+        subject: impl Into<String>,
+    ) -> Result<TagYank, GetError<anyhow::Error>> {
+        if !self.entity.exists().await {
+            return Err(GetError::NotFound);
+        }
+        let yank = TagYank {
+            subject: subject.into(),
+            time: now(),
+        };
+        self.entity
+            .write_json(YANK_PATH, &yank)
+            .await
+            .map_err(|e| {
+                debug!(target: "app::store::Tag::yank", "failed to write yank state: {:?}", e);
+                GetError::Internal(e)
+            })?;
+        Ok(yank)
+    }
+
+    /// Deletes the tag. Contents of its tree nodes remain in the repository [Blobs] until
+    /// garbage collected.
+    pub async fn delete(&self) -> Result<(), GetError<anyhow::Error>> {
+        self.entity.remove().await
     }
 }
@@ -1,26 +1,162 @@
 // SPDX-FileCopyrightText: 2022 Profian Inc. <opensource@profian.com>
 // SPDX-License-Identifier: Apache-2.0
 
-use super::Entity;
+use super::{Blobs, CreateError, Entity, GetError};
 
-use std::ops::Deref;
+use std::collections::btree_map::Entry;
 
-use camino::Utf8PathBuf;
+use drawbridge_type::digest::Algorithm;
+use drawbridge_type::{Meta, TreeDirectory, TreeEntry};
 
-#[repr(transparent)]
-#[derive(Copy, Clone, Debug)]
-pub struct Node<'a, P = Utf8PathBuf>(Entity<'a, P>);
+use anyhow::{anyhow, Context};
+use camino::{Utf8Path, Utf8PathBuf};
+use futures::{try_join, AsyncRead, AsyncSeek};
+use tracing::{debug, trace, warn};
 
-impl<'a, P> Deref for Node<'a, P> {
-    type Target = Entity<'a, P>;
+/// A node of a tag tree.
+///
+/// Node directories only hold the metadata of the node, the contents are stored in [Blobs] of
+/// the repository and looked up by the content digest of the node.
+#[derive(Clone, Debug)]
+pub struct Node<'a, P = Utf8PathBuf> {
+    entity: Entity<'a, P>,
+    blobs: Blobs<'a>,
+}
 
-    fn deref(&self) -> &Self::Target {
-        &self.0
+impl<'a, P> Node<'a, P> {
+    pub(super) fn new(entity: Entity<'a, P>, blobs: Blobs<'a>) -> Self {
+        Self { entity, blobs }
     }
 }
 
-impl<'a, P> From<Entity<'a, P>> for Node<'a, P> {
-    fn from(entity: Entity<'a, P>) -> Self {
-        Self(entity)
+impl<P: AsRef<Utf8Path>> Node<'_, P> {
+    /// Returns metadata of the node.
+    pub async fn get_meta(&self) -> Result<Meta, GetError<anyhow::Error>> {
+        self.entity.get_meta().await
+    }
+
+    /// Returns metadata of the node and a seekable reader of its contents.
+    pub async fn get(
+        &self,
+    ) -> Result<(Meta, impl 'static + Send + Unpin + AsyncRead + AsyncSeek), GetError<anyhow::Error>>
+    {
+        let meta = self.get_meta().await?;
+        let (blob, rdr) = self
+            .blobs
+            .blob(&meta.hash)?
+            .get()
+            .await
+            .map_err(|e| match e {
+                GetError::NotFound => GetError::Internal(anyhow!("node contents are missing")),
+                e => e,
+            })?;
+        if blob.size != meta.size {
+            return Err(GetError::Internal(anyhow!(
+                "content length mismatch, expected: {}, got {}",
+                meta.size,
+                blob.size
+            )));
+        }
+        Ok((meta, rdr))
+    }
+
+    /// Creates the node with metadata `meta` and contents read from `rdr`.
+    ///
+    /// If `meta` has zero size and a blob matching its content digest exists, the node refers to
+    /// that blob and its size is taken from the blob.
+    pub(super) async fn create_file(
+        &self,
+        mut meta: Meta,
+        rdr: impl Send + Unpin + AsyncRead,
+    ) -> Result<(), CreateError<anyhow::Error>> {
+        self.check_vacant().await?;
+        let blob = match (meta.size, self.blobs.find(&meta.hash).await) {
+            (0, Ok(blob)) => blob,
+            (_, Err(GetError::Internal(e))) => return Err(CreateError::Internal(e)),
+            _ => self.blobs.create(&meta.hash, meta.size, rdr).await?,
+        };
+        meta.size = blob.size;
+        self.entity
+            .create_with(|staged| async move { staged.write_meta(&meta).await })
+            .await
+    }
+
+    /// Creates the node with metadata `meta` and contents `dir`.
+    pub(super) async fn create_directory(
+        &self,
+        meta: Meta,
+        dir: &TreeDirectory<TreeEntry>,
+    ) -> Result<(), CreateError<anyhow::Error>> {
+        let buf = serde_json::to_vec(dir)
+            .context("failed to encode directory to JSON")
+            .map_err(CreateError::Internal)?;
+        self.check_vacant().await?;
+        _ = self
+            .blobs
+            .create(&meta.hash, meta.size, buf.as_slice())
+            .await?;
+        self.entity
+            .create_with(|staged| async move {
+                try_join!(staged.write_meta(&meta), staged.create_dir("entries")).map(|_| ())
+            })
+            .await
+    }
+
+    /// Moves contents of the node and all of its descendants stored inline by previous store
+    /// layouts into [Blobs].
+    pub(super) async fn migrate(&self) -> anyhow::Result<()> {
+        let mut paths = vec![Utf8PathBuf::new()];
+        while let Some(path) = paths.pop() {
+            let node = self.entity.child(&path);
+            if node.has_content().await {
+                trace!(target: "app::store::Node::migrate", "migrate node at `{path}`");
+                let mut meta = node.get_meta().await.map_err(|e| match e {
+                    GetError::NotFound => anyhow!("node at `{path}` has no metadata"),
+                    GetError::Internal(e) => e.context("failed to read node metadata"),
+                })?;
+                let blob = self
+                    .blobs
+                    .adopt(&node)
+                    .await
+                    .map_err(|e| anyhow!("failed to move node contents into blob: {e:?}"))?;
+                if blob.size != meta.size
+                    || meta
+                        .hash
+                        .iter()
+                        .any(|(algo, hash)| blob.hash.get(algo) != Some(hash))
+                {
+                    warn!(target: "app::store::Node::migrate", "contents of node at `{path}` do not match its metadata");
+                } else if let (Entry::Vacant(entry), Some(hash)) = (
+                    meta.hash.entry(Algorithm::Sha256),
+                    blob.hash.get(&Algorithm::Sha256),
+                ) {
+                    _ = entry.insert(hash.clone());
+                    node.write_meta(&meta)
+                        .await
+                        .map_err(|e| anyhow!("failed to update node metadata: {e:?}"))?;
+                }
+            }
+            match node.read_dir("entries").await {
+                Ok(entries) => {
+                    for name in entries {
+                        paths.push(path.join("entries").join(name));
+                    }
+                }
+                Err(GetError::NotFound) => {}
+                Err(GetError::Internal(e)) => return Err(e),
+            }
+        }
+        Ok(())
+    }
+
+    /// Fails with [CreateError::Occupied] if the node exists, which avoids storing contents of
+    /// nodes, which cannot be created.
+    async fn check_vacant(&self) -> Result<(), CreateError<anyhow::Error>> {
+        if self.entity.exists().await {
+            debug!(target: "app::store::Node::check_vacant", "node already exists");
+            Err(CreateError::Occupied)
+        } else {
+            Ok(())
+        }
     }
 }
@@ -1,12 +1,14 @@
 // SPDX-FileCopyrightText: 2022 Profian Inc. <opensource@profian.com>
 // SPDX-License-Identifier: Apache-2.0
 
-use super::{CreateError, Entity, Repository};
+use super::{encode_json, CreateError, DeleteError, Entity, GetError, Repository};
 
 use std::ops::Deref;
 
-use drawbridge_type::{Meta, RepositoryConfig, RepositoryName};
+use drawbridge_type::digest::ContentDigest;
+use drawbridge_type::{Meta, RepositoryConfig, RepositoryEntry, RepositoryName};
 
+use anyhow::Context;
 use camino::{Utf8Path, Utf8PathBuf};
 use futures::try_join;
 
@@ -33,6 +35,52 @@ impl<'a, P: AsRef<Utf8Path>> User<'a, P> {
         self.0.child(format!("repos/{name}")).into()
     }
 
+    pub async fn repositories(&self) -> Result<Vec<RepositoryName>, GetError<anyhow::Error>> {
+        self.read_dir("repos")
+            .await?
+            .into_iter()
+            .map(|name| name.parse().context("failed to parse repository name"))
+            .collect::<anyhow::Result<_>>()
+            .map_err(GetError::Internal)
+    }
+
+    /// Returns names and configs of repositories of the user sorted by name encoded as JSON,
+    /// omitting private repositories unless `private` is set.
+    pub async fn repositories_json(
+        &self,
+        private: bool,
+    ) -> Result<(ContentDigest, Vec<u8>), GetError<anyhow::Error>> {
+        let mut names = self.repositories().await?;
+        names.sort_by(|a, b| a.as_str().cmp(b.as_str()));
+        let mut entries = Vec::with_capacity(names.len());
+        for name in names {
+            let config = self.repository(&name).get_json().await?;
+            if private || config.public {
+                entries.push(RepositoryEntry { name, config });
+            }
+        }
+        encode_json(&entries)
+    }
+
+    /// Deletes the user, which must not own any repositories.
+    ///
+    /// Repositories are looked up after the user is moved out of place, such that none can be
+    /// created concurrently.
+    pub async fn delete(&self) -> Result<(), DeleteError<anyhow::Error>> {
+        let removed = self
+            .remove_unless(|staged| async move {
+                User::from(staged)
+                    .repositories()
+                    .await
+                    .map(|repos| !repos.is_empty())
+            })
+            .await?;
+        if !removed {
+            return Err(DeleteError::NotEmpty);
+        }
+        Ok(())
+    }
+
     pub async fn create_repository(
         &self,
         name: &RepositoryName,
@@ -40,8 +88,15 @@ impl<'a, P: AsRef<Utf8Path>> User<'a, P> {
         conf: &RepositoryConfig,
     ) -> Result<Repository<'a, Utf8PathBuf>, CreateError<anyhow::Error>> {
         let repo = self.repository(name);
-        repo.create_dir("").await?;
-        try_join!(repo.create_json(meta, conf), repo.create_dir("tags"))?;
+        repo.create_with(|staged| async move {
+            try_join!(
+                staged.create_json(meta, conf),
+                staged.create_dir("tags"),
+                staged.create_dir("blobs")
+            )
+            .map(|_| ())
+        })
+        .await?;
         Ok(repo)
     }
 }
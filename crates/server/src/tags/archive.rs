@@ -0,0 +1,141 @@
+// SPDX-FileCopyrightText: 2022 Profian Inc. <opensource@profian.com>
+// SPDX-License-Identifier: Apache-2.0
+
+use super::super::{OidcClaims, ScopeContext, ScopeLevel, Store};
+use crate::auth::assert_repository_read;
+
+use std::pin::Pin;
+use std::task::{ready, Context, Poll};
+
+use drawbridge_type::tag::{ArchiveFormat, ArchiveQuery, ARCHIVE_TYPE};
+use drawbridge_type::TagContext;
+
+use async_compression::futures::write::{GzipEncoder, ZstdEncoder};
+use async_std::sync::Arc;
+use async_std::task::spawn;
+use axum::body::{Body, Bytes, StreamBody};
+use axum::extract::{BodyStream, Query, RequestParts};
+use axum::http::header::CONTENT_TYPE;
+use axum::http::{Request, StatusCode};
+use axum::response::IntoResponse;
+use axum::Extension;
+use futures::channel::mpsc::{channel, Sender};
+use futures::io::BufWriter;
+use futures::{io, AsyncWrite, SinkExt, TryStreamExt};
+use tracing::{debug, trace};
+
+/// [AsyncWrite] sending written bytes to a response body.
+struct BodyWriter(Sender<io::Result<Bytes>>);
+
+impl AsyncWrite for BodyWriter {
+    fn poll_write(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &[u8],
+    ) -> Poll<io::Result<usize>> {
+        let sent = ready!(self.0.poll_ready(cx))
+            .and_then(|()| self.0.start_send(Ok(Bytes::copy_from_slice(buf))));
+        Poll::Ready(
+            sent.map(|()| buf.len())
+                .map_err(|_| io::ErrorKind::BrokenPipe.into()),
+        )
+    }
+
+    fn poll_flush(self: Pin<&mut Self>, _: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Poll::Ready(Ok(()))
+    }
+
+    fn poll_close(mut self: Pin<&mut Self>, _: &mut Context<'_>) -> Poll<io::Result<()>> {
+        self.0.close_channel();
+        Poll::Ready(Ok(()))
+    }
+}
+
+/// Downloads the whole tree of a sealed tag as a tar archive in the requested format.
+pub async fn get_archive(
+    Extension(store): Extension<Arc<Store>>,
+    cx: TagContext,
+    Query(ArchiveQuery { format }): Query<ArchiveQuery>,
+    req: Request<Body>,
+) -> impl IntoResponse {
+    trace!(target: "app::tags::get_archive", "called for `{cx}`");
+
+    {
+        let (repo, _) = assert_repository_read(&store, &cx.repository, req)
+            .await
+            .map_err(IntoResponse::into_response)?;
+        let tag = repo.tag(&cx.name);
+        _ = tag.get_meta().await.map_err(|e| {
+            debug!(target: "app::tags::get_archive", "failed for `{cx}`: {:?}", e);
+            e.into_response()
+        })?;
+        let sealed = tag.is_sealed().await.map_err(|e| {
+            debug!(target: "app::tags::get_archive", "failed to check whether `{cx}` is sealed: {:?}", e);
+            e.into_response()
+        })?;
+        if !sealed {
+            return Err((StatusCode::CONFLICT, "Tag is not sealed").into_response());
+        }
+    }
+
+    let (tx, rx) = channel(16);
+    let mut err_tx = tx.clone();
+    _ = spawn(async move {
+        let tag = store.repository(&cx.repository).tag(&cx.name);
+        let wtr = BufWriter::new(BodyWriter(tx));
+        let res = match format {
+            ArchiveFormat::Tar => tag.write_archive(wtr).await,
+            ArchiveFormat::TarGzip => tag.write_archive(GzipEncoder::new(wtr)).await,
+            ArchiveFormat::TarZstd => tag.write_archive(ZstdEncoder::new(wtr)).await,
+        };
+        if let Err(e) = res {
+            debug!(target: "app::tags::get_archive", "failed to write archive of `{cx}`: {:?}", e);
+            // Abort the response, so that the archive is not mistaken for a complete one.
+            _ = err_tx.send(Err(io::Error::other(e))).await;
+        }
+    });
+    Ok(([(CONTENT_TYPE, format.media_type())], StreamBody::new(rx)))
+}
+
+/// Uploads the whole tree of a tag as a single tar archive and seals the tag.
+pub async fn put_archive(
+    Extension(ref store): Extension<Arc<Store>>,
+    claims: OidcClaims,
+    cx: TagContext,
+    req: Request<Body>,
+) -> impl IntoResponse {
+    trace!(target: "app::tags::put_archive", "called for `{cx}`");
+
+    if req.headers().get(CONTENT_TYPE).map(|v| v.as_bytes()) != Some(ARCHIVE_TYPE.as_bytes()) {
+        return Err((
+            StatusCode::UNSUPPORTED_MEDIA_TYPE,
+            format!("Archive content type must be `{ARCHIVE_TYPE}`"),
+        )
+            .into_response());
+    }
+
+    let user = claims
+        .assert_user(
+            store,
+            &cx.repository.owner,
+            ScopeContext::Tag,
+            ScopeLevel::Write,
+        )
+        .await
+        .map_err(IntoResponse::into_response)?;
+
+    let body = RequestParts::new(req)
+        .extract::<BodyStream>()
+        .await
+        .map_err(|e| (StatusCode::BAD_REQUEST, e).into_response())?
+        .map_err(io::Error::other);
+    user.repository(&cx.repository.name)
+        .tag(&cx.name)
+        .create_tree_from_archive(body.into_async_read())
+        .await
+        .map_err(|e| {
+            debug!(target: "app::tags::put_archive", "failed for `{cx}`: {:?}", e);
+            e.into_response()
+        })
+        .map(|_| StatusCode::CREATED)
+}
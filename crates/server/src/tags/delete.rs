@@ -0,0 +1,51 @@
+// SPDX-FileCopyrightText: 2022 Profian Inc. <opensource@profian.com>
+// SPDX-License-Identifier: Apache-2.0
+
+use super::super::{AuditAction, AuditEvent, OidcClaims, ScopeContext, ScopeLevel, Store};
+
+use drawbridge_type::TagContext;
+
+use async_std::sync::Arc;
+use axum::http::StatusCode;
+use axum::response::IntoResponse;
+use axum::Extension;
+use tracing::{debug, trace};
+
+pub async fn delete(
+    Extension(ref store): Extension<Arc<Store>>,
+    claims: OidcClaims,
+    cx: TagContext,
+) -> impl IntoResponse {
+    trace!(target: "app::tags::delete", "called for `{cx}`");
+
+    let user = claims
+        .assert_user(
+            store,
+            &cx.repository.owner,
+            ScopeContext::Tag,
+            ScopeLevel::Write,
+        )
+        .await
+        .map_err(IntoResponse::into_response)?;
+
+    user.repository(&cx.repository.name)
+        .tag(&cx.name)
+        .delete()
+        .await
+        .map_err(|e| {
+            debug!(target: "app::tags::delete", "failed for `{cx}`: {:?}", e);
+            e.into_response()
+        })?;
+    store
+        .record(&AuditEvent::new(
+            AuditAction::DeleteTag,
+            &cx,
+            claims.subject(),
+        ))
+        .await
+        .map_err(|e| {
+            debug!(target: "app::tags::delete", "failed to record deletion of `{cx}`: {:?}", e);
+            e.into_response()
+        })
+        .map(|()| StatusCode::NO_CONTENT)
+}
@@ -1,9 +1,10 @@
 // SPDX-FileCopyrightText: 2022 Profian Inc. <opensource@profian.com>
 // SPDX-License-Identifier: Apache-2.0
 
-use super::super::Store;
+use super::super::{Preconditions, Store};
 use crate::auth::assert_repository_read;
 
+use drawbridge_type::tag::{MISSING_NODES_HEADER, SEALED_HEADER};
 use drawbridge_type::TagContext;
 
 use async_std::sync::Arc;
@@ -16,6 +17,7 @@ use tracing::{debug, trace};
 pub async fn get(
     Extension(ref store): Extension<Arc<Store>>,
     cx: TagContext,
+    pre: Preconditions,
     req: Request<Body>,
 ) -> impl IntoResponse {
     trace!(target: "app::tags::get", "called for `{cx}`");
@@ -24,15 +26,22 @@ pub async fn get(
         .await
         .map_err(IntoResponse::into_response)?;
 
-    // TODO: Stream body
-    // https://github.com/profianinc/drawbridge/issues/56
-    let mut body = vec![];
-    repo.tag(&cx.name)
-        .get_to_writer(&mut body)
+    let tag = repo.tag(&cx.name);
+    let (meta, body) = tag.get_body().await.map_err(|e| {
+        debug!(target: "app::tags::get", "failed for `{cx}`: {:?}", e);
+        e.into_response()
+    })?;
+    tag.missing_nodes()
         .await
         .map_err(|e| {
-            debug!(target: "app::tags::get", "failed for `{cx}`: {:?}", e);
+            debug!(target: "app::tags::get", "failed to find missing nodes of `{cx}`: {:?}", e);
             e.into_response()
         })
-        .map(|meta| (meta, body))
+        .map(|missing| {
+            let completeness = [
+                (SEALED_HEADER, (missing == 0).to_string()),
+                (MISSING_NODES_HEADER, missing.to_string()),
+            ];
+            pre.respond(meta.hash.etag(), (completeness, meta, body))
+        })
 }
@@ -1,9 +1,10 @@
 // SPDX-FileCopyrightText: 2022 Profian Inc. <opensource@profian.com>
 // SPDX-License-Identifier: Apache-2.0
 
-use super::super::Store;
+use super::super::{Preconditions, Store};
 use crate::auth::assert_repository_read;
 
+use drawbridge_type::tag::{MISSING_NODES_HEADER, SEALED_HEADER};
 use drawbridge_type::TagContext;
 
 use async_std::sync::Arc;
@@ -16,20 +17,31 @@ use tracing::{debug, trace};
 pub async fn head(
     Extension(ref store): Extension<Arc<Store>>,
     cx: TagContext,
+    pre: Preconditions,
     req: Request<Body>,
 ) -> impl IntoResponse {
     trace!(target: "app::tags::head", "called for `{cx}`");
 
-    assert_repository_read(store, &cx.repository, req)
+    let tag = assert_repository_read(store, &cx.repository, req)
         .await
         .map_err(IntoResponse::into_response)
         .map(|(repo, _)| repo)?
-        .tag(&cx.name)
-        .get_meta()
+        .tag(&cx.name);
+    let meta = tag.get_meta().await.map_err(|e| {
+        debug!(target: "app::tags::head", "failed for `{cx}`: {:?}", e);
+        e.into_response()
+    })?;
+    tag.missing_nodes()
         .await
         .map_err(|e| {
-            debug!(target: "app::tags::head", "failed for `{cx}`: {:?}", e);
+            debug!(target: "app::tags::head", "failed to find missing nodes of `{cx}`: {:?}", e);
             e.into_response()
         })
-        .map(|meta| (meta, ()))
+        .map(|missing| {
+            let completeness = [
+                (SEALED_HEADER, (missing == 0).to_string()),
+                (MISSING_NODES_HEADER, missing.to_string()),
+            ];
+            pre.respond(meta.hash.etag(), (completeness, meta, ()))
+        })
 }
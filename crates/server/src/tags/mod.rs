@@ -1,11 +1,19 @@
 // SPDX-FileCopyrightText: 2022 Profian Inc. <opensource@profian.com>
 // SPDX-License-Identifier: Apache-2.0
+mod archive;
+mod delete;
 mod get;
 mod head;
 mod put;
 mod query;
+mod resolve;
+mod yank;
 
+pub use archive::*;
+pub use delete::*;
 pub use get::*;
 pub use head::*;
 pub use put::*;
 pub use query::*;
+pub use resolve::*;
+pub use yank::*;
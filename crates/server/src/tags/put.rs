@@ -49,6 +49,14 @@ pub async fn put(
         _ => return Err((StatusCode::BAD_REQUEST, "Invalid content type").into_response()),
     }
     .map_err(|e| (StatusCode::BAD_REQUEST, e).into_response())?;
+    if let Err(e) = entry.entry() {
+        debug!(target: "app::tags::put", "invalid signed entry for `{cx}`: {:?}", e);
+        return Err((
+            StatusCode::BAD_REQUEST,
+            "Signed tag payload must be a tree entry",
+        )
+            .into_response());
+    }
     user.repository(&cx.repository.name)
         .create_tag(&cx.name, meta, &entry)
         .await
@@ -1,44 +1,66 @@
 // SPDX-FileCopyrightText: 2022 Profian Inc. <opensource@profian.com>
 // SPDX-License-Identifier: Apache-2.0
 
-use super::super::Store;
+use super::super::{Preconditions, Store};
 use crate::auth::assert_repository_read;
 
-use drawbridge_type::{Meta, RepositoryContext};
+use drawbridge_type::{Meta, RepositoryContext, TagQuery};
 
 use async_std::sync::Arc;
 use axum::body::Body;
+use axum::extract::Query;
+use axum::http::header::LINK;
 use axum::http::Request;
-use axum::response::IntoResponse;
+use axum::response::{IntoResponse, Response};
 use axum::Extension;
 use mime::APPLICATION_JSON;
+use percent_encoding::{utf8_percent_encode, NON_ALPHANUMERIC};
 use tracing::{debug, trace};
 
 pub async fn query(
     Extension(store): Extension<Arc<Store>>,
     cx: RepositoryContext,
+    Query(query): Query<TagQuery>,
+    pre: Preconditions,
     req: Request<Body>,
 ) -> impl IntoResponse {
     trace!(target: "app::tags::query", "called for `{cx}`");
 
-    assert_repository_read(&store, &cx, req)
+    let path = req.uri().path().to_string();
+    let (hash, buf, next) = assert_repository_read(&store, &cx, req)
         .await
         .map_err(IntoResponse::into_response)
         .map(|(repo, _)| repo)?
-        .tags_json()
+        .tags_json(&query)
         .await
-        .map(|(hash, buf)| {
-            (
-                Meta {
-                    hash,
-                    size: buf.len() as _,
-                    mime: APPLICATION_JSON,
-                },
-                buf,
-            )
-        })
         .map_err(|e| {
             debug!(target: "app::tags::query", "failed: {:?}", e);
             e.into_response()
-        })
+        })?;
+
+    // The next page is requested by the same query continuing after the last listed tag.
+    let link = next.map(|after| {
+        let query = TagQuery {
+            after: Some(after),
+            ..query
+        }
+        .pairs()
+        .into_iter()
+        .map(|(k, v)| format!("{k}={}", utf8_percent_encode(&v, NON_ALPHANUMERIC)))
+        .collect::<Vec<_>>()
+        .join("&");
+        [(LINK, format!(r#"<{path}?{query}>; rel="next""#))]
+    });
+    Ok::<_, Response>(pre.respond(
+        hash.etag(),
+        (
+            Meta {
+                hash,
+                size: buf.len() as _,
+                mime: APPLICATION_JSON,
+            },
+            link,
+            buf,
+        ),
+    ))
 }
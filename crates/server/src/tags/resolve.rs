@@ -0,0 +1,61 @@
+// SPDX-FileCopyrightText: 2022 Profian Inc. <opensource@profian.com>
+// SPDX-License-Identifier: Apache-2.0
+
+use super::super::{Preconditions, Store};
+use crate::auth::assert_repository_read;
+
+use drawbridge_type::{Meta, RepositoryContext};
+
+use async_std::sync::Arc;
+use axum::body::Body;
+use axum::extract::Query;
+use axum::http::Request;
+use axum::response::IntoResponse;
+use axum::Extension;
+use mime::APPLICATION_JSON;
+use semver::VersionReq;
+use serde::Deserialize;
+use tracing::{debug, trace};
+
+/// Tag resolution query parameters.
+#[derive(Clone, Copy, Debug, Default, Deserialize)]
+pub struct ResolveQuery {
+    /// Whether pre-release tags may satisfy the requirement.
+    #[serde(default)]
+    pub pre: bool,
+}
+
+pub async fn resolve(
+    Extension(ref store): Extension<Arc<Store>>,
+    cx: RepositoryContext,
+    Extension(ref version_req): Extension<VersionReq>,
+    Query(ResolveQuery { pre: pre_release }): Query<ResolveQuery>,
+    pre: Preconditions,
+    req: Request<Body>,
+) -> impl IntoResponse {
+    trace!(target: "app::tags::resolve", "called for `{cx}` and `{version_req}`");
+
+    assert_repository_read(store, &cx, req)
+        .await
+        .map_err(IntoResponse::into_response)
+        .map(|(repo, _)| repo)?
+        .resolve_json(version_req, pre_release)
+        .await
+        .map_err(|e| {
+            debug!(target: "app::tags::resolve", "failed for `{cx}` and `{version_req}`: {:?}", e);
+            e.into_response()
+        })
+        .map(|(hash, buf)| {
+            pre.respond(
+                hash.etag(),
+                (
+                    Meta {
+                        hash,
+                        size: buf.len() as _,
+                        mime: APPLICATION_JSON,
+                    },
+                    buf,
+                ),
+            )
+        })
+}
@@ -0,0 +1,77 @@
+// SPDX-FileCopyrightText: 2022 Profian Inc. <opensource@profian.com>
+// SPDX-License-Identifier: Apache-2.0
+
+use super::super::{AuditAction, AuditEvent, OidcClaims, ScopeContext, ScopeLevel, Store};
+use crate::auth::assert_repository_read;
+
+use drawbridge_type::TagContext;
+
+use async_std::sync::Arc;
+use axum::body::Body;
+use axum::http::Request;
+use axum::response::IntoResponse;
+use axum::{Extension, Json};
+use tracing::{debug, trace};
+
+/// Yanks a tag, hiding it from tag listings by default, and responds with its yank state.
+pub async fn yank(
+    Extension(ref store): Extension<Arc<Store>>,
+    claims: OidcClaims,
+    cx: TagContext,
+) -> impl IntoResponse {
+    trace!(target: "app::tags::yank", "called for `{cx}`");
+
+    let user = claims
+        .assert_user(
+            store,
+            &cx.repository.owner,
+            ScopeContext::Tag,
+            ScopeLevel::Write,
+        )
+        .await
+        .map_err(IntoResponse::into_response)?;
+
+    let yank = user
+        .repository(&cx.repository.name)
+        .tag(&cx.name)
+        .yank(claims.subject())
+        .await
+        .map_err(|e| {
+            debug!(target: "app::tags::yank", "failed for `{cx}`: {:?}", e);
+            e.into_response()
+        })?;
+    store
+        .record(&AuditEvent::new(
+            AuditAction::YankTag,
+            &cx,
+            claims.subject(),
+        ))
+        .await
+        .map_err(|e| {
+            debug!(target: "app::tags::yank", "failed to record yank of `{cx}`: {:?}", e);
+            e.into_response()
+        })
+        .map(|()| Json(yank))
+}
+
+/// Returns the yank state of a tag.
+pub async fn get_yank(
+    Extension(ref store): Extension<Arc<Store>>,
+    cx: TagContext,
+    req: Request<Body>,
+) -> impl IntoResponse {
+    trace!(target: "app::tags::get_yank", "called for `{cx}`");
+
+    let (repo, _) = assert_repository_read(store, &cx.repository, req)
+        .await
+        .map_err(IntoResponse::into_response)?;
+
+    repo.tag(&cx.name)
+        .get_yank()
+        .await
+        .map_err(|e| {
+            debug!(target: "app::tags::get_yank", "failed for `{cx}`: {:?}", e);
+            e.into_response()
+        })
+        .map(Json)
+}
@@ -1,22 +1,27 @@
 // SPDX-FileCopyrightText: 2022 Profian Inc. <opensource@profian.com>
 // SPDX-License-Identifier: Apache-2.0
 
-use super::super::{Store, TrustedCertificate};
+use super::super::{Preconditions, Store, TrustedCertificate};
+use super::range;
 use crate::auth::assert_repository_read;
 
 use drawbridge_type::TreeContext;
 
 use async_std::sync::Arc;
 use axum::body::Body;
+use axum::headers::{IfRange, Range};
 use axum::http::Request;
 use axum::response::IntoResponse;
-use axum::Extension;
+use axum::{Extension, TypedHeader};
 use tracing::{debug, trace};
 
 pub async fn get(
     Extension(ref store): Extension<Arc<Store>>,
     cert: Option<Extension<TrustedCertificate>>,
     cx: TreeContext,
+    pre: Preconditions,
+    range: Option<TypedHeader<Range>>,
+    if_range: Option<TypedHeader<IfRange>>,
     req: Request<Body>,
 ) -> impl IntoResponse {
     trace!(target: "app::trees::get", "called for `{cx}`");
@@ -30,16 +35,25 @@ pub async fn get(
         store.repository(&cx.tag.repository)
     };
 
-    // TODO: Stream body
-    // https://github.com/profianinc/drawbridge/issues/56
-    let mut body = vec![];
     repo.tag(&cx.tag.name)
         .node(&cx.path)
-        .get_to_writer(&mut body)
+        .get()
         .await
         .map_err(|e| {
             debug!(target: "app::trees::get", "failed for `{cx}`: {:?}", e);
             e.into_response()
         })
-        .map(|meta| (meta, body))
+        .map(|(meta, rdr)| {
+            let etag = meta.hash.etag();
+            pre.respond(
+                etag.clone(),
+                range::response(
+                    meta,
+                    &etag,
+                    rdr,
+                    range.map(|TypedHeader(range)| range),
+                    if_range.map(|TypedHeader(if_range)| if_range),
+                ),
+            )
+        })
 }
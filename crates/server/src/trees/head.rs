@@ -1,22 +1,24 @@
 // SPDX-FileCopyrightText: 2022 Profian Inc. <opensource@profian.com>
 // SPDX-License-Identifier: Apache-2.0
 
-use super::super::{Store, TrustedCertificate};
+use super::super::{Preconditions, Store, TrustedCertificate};
 use crate::auth::assert_repository_read;
 
 use drawbridge_type::TreeContext;
 
 use async_std::sync::Arc;
 use axum::body::Body;
+use axum::headers::AcceptRanges;
 use axum::http::Request;
 use axum::response::IntoResponse;
-use axum::Extension;
+use axum::{Extension, TypedHeader};
 use tracing::{debug, trace};
 
 pub async fn head(
     Extension(ref store): Extension<Arc<Store>>,
     cert: Option<Extension<TrustedCertificate>>,
     cx: TreeContext,
+    pre: Preconditions,
     req: Request<Body>,
 ) -> impl IntoResponse {
     trace!(target: "app::trees::head", "called for `{cx}`");
@@ -37,5 +39,10 @@ pub async fn head(
         debug!(target: "app::trees::head", "failed for `{cx}`: {:?}", e);
         e.into_response()
     })
-    .map(|meta| (meta, ()))
+    .map(|meta| {
+        pre.respond(
+            meta.hash.etag(),
+            (TypedHeader(AcceptRanges::bytes()), meta, ()),
+        )
+    })
 }
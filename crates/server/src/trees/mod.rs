@@ -3,6 +3,7 @@
 mod get;
 mod head;
 mod put;
+mod range;
 
 pub use get::*;
 pub use head::*;
@@ -3,6 +3,7 @@
 
 use super::super::{OidcClaims, ScopeContext, ScopeLevel, Store};
 
+use drawbridge_type::digest::Algorithm;
 use drawbridge_type::{Meta, TreeContext, TreeDirectory};
 
 use async_std::sync::Arc;
@@ -23,10 +24,10 @@ pub async fn put(
 ) -> impl IntoResponse {
     trace!(target: "app::trees::put", "called for `{cx}`");
 
-    if meta.hash.is_empty() {
+    if !meta.hash.contains_key(&Algorithm::Sha256) {
         return Err((
             StatusCode::BAD_REQUEST,
-            "At least one content digest value must be specified",
+            "A SHA-256 content digest value must be specified",
         )
             .into_response());
     }
@@ -0,0 +1,187 @@
+// SPDX-FileCopyrightText: 2022 Profian Inc. <opensource@profian.com>
+// SPDX-License-Identifier: Apache-2.0
+
+use std::io::{self, SeekFrom};
+use std::ops::Bound::{Included, Unbounded};
+
+use drawbridge_type::Meta;
+
+use axum::body::{Bytes, StreamBody};
+use axum::headers::{AcceptRanges, ContentLength, ContentType, ETag, IfRange, Range};
+use axum::http::header::{CONTENT_RANGE, CONTENT_TYPE};
+use axum::http::StatusCode;
+use axum::response::{IntoResponse, Response};
+use axum::TypedHeader;
+use futures::{stream, AsyncRead, AsyncReadExt, AsyncSeek, AsyncSeekExt, Stream};
+use uuid::Uuid;
+
+/// Maximum amount of ranges served in a single response. Requests for more are served in full.
+const MAX_RANGES: usize = 16;
+
+/// Maximum size of a single chunk of the response body.
+const CHUNK_SIZE: u64 = 64 * 1024;
+
+/// Byte ranges of a node selected by a request.
+#[derive(Debug)]
+enum Selection {
+    Full,
+    /// Non-empty list of inclusive byte ranges.
+    Partial(Vec<(u64, u64)>),
+    Unsatisfiable,
+}
+
+fn select(size: u64, etag: &ETag, range: Option<&Range>, if_range: Option<&IfRange>) -> Selection {
+    let range = match (range, if_range) {
+        (None, _) => return Selection::Full,
+        (Some(_), Some(if_range)) if if_range.is_modified(Some(etag), None) => {
+            return Selection::Full
+        }
+        (Some(range), _) => range,
+    };
+
+    let mut specs = 0;
+    let mut ranges = vec![];
+    for bounds in range.iter() {
+        specs += 1;
+        let (start, end) = match bounds {
+            (Included(start), Included(end)) if start <= end => (start, end),
+            (Included(start), Unbounded) => (start, u64::MAX),
+            (Unbounded, Included(0)) => continue,
+            (Unbounded, Included(len)) => (size.saturating_sub(len), u64::MAX),
+            // A syntactically invalid range set is ignored altogether.
+            _ => return Selection::Full,
+        };
+        if start < size {
+            ranges.push((start, end.min(size - 1)));
+        }
+    }
+    match ranges.len() {
+        _ if specs == 0 || specs > MAX_RANGES => Selection::Full,
+        0 => Selection::Unsatisfiable,
+        _ => Selection::Partial(ranges),
+    }
+}
+
+/// A part of a partial response body.
+struct Part {
+    header: Bytes,
+    start: u64,
+    len: u64,
+}
+
+/// Streams `parts` of `rdr` followed by `trailer`.
+fn stream_parts(
+    rdr: impl 'static + Send + Unpin + AsyncRead + AsyncSeek,
+    parts: Vec<Part>,
+    trailer: Option<Bytes>,
+) -> impl Stream<Item = io::Result<Bytes>> {
+    stream::try_unfold(
+        (rdr, parts.into_iter(), 0, trailer),
+        |(mut rdr, mut parts, remaining, trailer)| async move {
+            if remaining > 0 {
+                let mut buf = vec![0; remaining.min(CHUNK_SIZE) as _];
+                let n = rdr.read(&mut buf).await?;
+                if n == 0 {
+                    return Err(io::ErrorKind::UnexpectedEof.into());
+                }
+                buf.truncate(n);
+                return Ok(Some((
+                    buf.into(),
+                    (rdr, parts, remaining - n as u64, trailer),
+                )));
+            }
+            if let Some(Part { header, start, len }) = parts.next() {
+                _ = rdr.seek(SeekFrom::Start(start)).await?;
+                return Ok(Some((header, (rdr, parts, len, trailer))));
+            }
+            Ok(trailer.map(|trailer| (trailer, (rdr, parts, 0, None))))
+        },
+    )
+}
+
+/// Returns a response serving a node with metadata `meta`, entity tag `etag` and contents `rdr`,
+/// honouring `Range` and `If-Range` request headers.
+pub(super) fn response(
+    meta: Meta,
+    etag: &ETag,
+    rdr: impl 'static + Send + Unpin + AsyncRead + AsyncSeek,
+    range: Option<Range>,
+    if_range: Option<IfRange>,
+) -> Response {
+    let size = meta.size;
+    let common = TypedHeader(AcceptRanges::bytes());
+    match select(size, etag, range.as_ref(), if_range.as_ref()) {
+        Selection::Full => {
+            let body = stream_parts(
+                rdr,
+                vec![Part {
+                    header: Bytes::new(),
+                    start: 0,
+                    len: size,
+                }],
+                None,
+            );
+            (common, meta, StreamBody::new(body)).into_response()
+        }
+        Selection::Unsatisfiable => (
+            StatusCode::RANGE_NOT_SATISFIABLE,
+            common,
+            [(CONTENT_RANGE, format!("bytes */{size}"))],
+        )
+            .into_response(),
+        Selection::Partial(ranges) if ranges.len() == 1 => {
+            let (start, end) = ranges[0];
+            let len = end - start + 1;
+            let body = stream_parts(
+                rdr,
+                vec![Part {
+                    header: Bytes::new(),
+                    start,
+                    len,
+                }],
+                None,
+            );
+            (
+                StatusCode::PARTIAL_CONTENT,
+                common,
+                [(CONTENT_RANGE, format!("bytes {start}-{end}/{size}"))],
+                TypedHeader(ContentLength(len)),
+                TypedHeader(ContentType::from(meta.mime)),
+                StreamBody::new(body),
+            )
+                .into_response()
+        }
+        Selection::Partial(ranges) => {
+            let boundary = Uuid::new_v4().simple().to_string();
+            let parts: Vec<_> = ranges
+                .into_iter()
+                .map(|(start, end)| Part {
+                    header: format!(
+                        "\r\n--{boundary}\r\n{CONTENT_TYPE}: {}\r\n{CONTENT_RANGE}: bytes {start}-{end}/{size}\r\n\r\n",
+                        meta.mime
+                    )
+                    .into(),
+                    start,
+                    len: end - start + 1,
+                })
+                .collect();
+            let trailer = Bytes::from(format!("\r\n--{boundary}--\r\n"));
+            let len = parts
+                .iter()
+                .map(|Part { header, len, .. }| header.len() as u64 + len)
+                .sum::<u64>()
+                + trailer.len() as u64;
+            (
+                StatusCode::PARTIAL_CONTENT,
+                common,
+                [(
+                    CONTENT_TYPE,
+                    format!("multipart/byteranges; boundary={boundary}"),
+                )],
+                TypedHeader(ContentLength(len)),
+                StreamBody::new(stream_parts(rdr, parts, Some(trailer))),
+            )
+                .into_response()
+        }
+    }
+}
@@ -0,0 +1,44 @@
+// SPDX-FileCopyrightText: 2022 Profian Inc. <opensource@profian.com>
+// SPDX-License-Identifier: Apache-2.0
+
+use super::super::{AuditAction, AuditEvent, OidcClaims, ScopeContext, ScopeLevel, Store};
+
+use drawbridge_type::UserContext;
+
+use async_std::sync::Arc;
+use axum::http::StatusCode;
+use axum::response::IntoResponse;
+use axum::Extension;
+use tracing::{debug, trace};
+
+/// Deletes the user, which fails with `409 Conflict` while the user owns repositories.
+pub async fn delete(
+    Extension(ref store): Extension<Arc<Store>>,
+    claims: OidcClaims,
+    cx: UserContext,
+) -> impl IntoResponse {
+    trace!(target: "app::users::delete", "called for `{cx}`");
+
+    claims
+        .assert_user(store, &cx, ScopeContext::User, ScopeLevel::Write)
+        .await
+        .map_err(IntoResponse::into_response)?
+        .delete()
+        .await
+        .map_err(|e| {
+            debug!(target: "app::users::delete", "failed for `{cx}`: {:?}", e);
+            e.into_response()
+        })?;
+    store
+        .record(&AuditEvent::new(
+            AuditAction::DeleteUser,
+            &cx,
+            claims.subject(),
+        ))
+        .await
+        .map_err(|e| {
+            debug!(target: "app::users::delete", "failed to record deletion of `{cx}`: {:?}", e);
+            e.into_response()
+        })
+        .map(|()| StatusCode::NO_CONTENT)
+}
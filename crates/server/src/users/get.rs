@@ -1,7 +1,7 @@
 // SPDX-FileCopyrightText: 2022 Profian Inc. <opensource@profian.com>
 // SPDX-License-Identifier: Apache-2.0
 
-use super::super::{OidcClaims, ScopeContext, ScopeLevel, Store};
+use super::super::{OidcClaims, Preconditions, ScopeContext, ScopeLevel, Store};
 
 use drawbridge_type::UserContext;
 
@@ -14,6 +14,7 @@ pub async fn get(
     Extension(store): Extension<Arc<Store>>,
     claims: OidcClaims,
     cx: UserContext,
+    pre: Preconditions,
 ) -> impl IntoResponse {
     trace!(target: "app::users::get", "called for `{cx}`");
 
@@ -22,14 +23,11 @@ pub async fn get(
         .await
         .map_err(IntoResponse::into_response)?;
 
-    // TODO: Stream body
-    // https://github.com/profianinc/drawbridge/issues/56
-    let mut body = vec![];
-    user.get_to_writer(&mut body)
+    user.get_body()
         .await
         .map_err(|e| {
             debug!(target: "app::users::get", "failed for `{cx}`: {:?}", e);
             e.into_response()
         })
-        .map(|meta| (meta, body))
+        .map(|(meta, body)| pre.respond(meta.hash.etag(), (meta, body)))
 }
@@ -0,0 +1,66 @@
+// SPDX-FileCopyrightText: 2022 Profian Inc. <opensource@profian.com>
+// SPDX-License-Identifier: Apache-2.0
+
+use super::super::{
+    OidcClaims, Preconditions, ScopeContext, ScopeLevel, Store, TrustedCertificate,
+};
+
+use drawbridge_type::{Meta, UserContext};
+
+use async_std::sync::Arc;
+use axum::body::Body;
+use axum::extract::RequestParts;
+use axum::http::header::AUTHORIZATION;
+use axum::http::Request;
+use axum::response::IntoResponse;
+use axum::Extension;
+use mime::APPLICATION_JSON;
+use tracing::{debug, trace};
+
+/// Lists repositories of the user, including private ones only for the authenticated owner and
+/// clients presenting a trusted certificate. Other authenticated users are listed public
+/// repositories only.
+pub async fn repos(
+    Extension(ref store): Extension<Arc<Store>>,
+    cert: Option<Extension<TrustedCertificate>>,
+    cx: UserContext,
+    pre: Preconditions,
+    req: Request<Body>,
+) -> impl IntoResponse {
+    trace!(target: "app::users::repos", "called for `{cx}`");
+
+    let private = if cert.is_some() {
+        true
+    } else if req.headers().contains_key(AUTHORIZATION) {
+        RequestParts::new(req)
+            .extract::<OidcClaims>()
+            .await?
+            .assert_user(store, &cx, ScopeContext::Repository, ScopeLevel::Read)
+            .await
+            .is_ok()
+    } else {
+        false
+    };
+
+    store
+        .user(&cx)
+        .repositories_json(private)
+        .await
+        .map_err(|e| {
+            debug!(target: "app::users::repos", "failed for `{cx}`: {:?}", e);
+            e.into_response()
+        })
+        .map(|(hash, buf)| {
+            pre.respond(
+                hash.etag(),
+                (
+                    Meta {
+                        hash,
+                        size: buf.len() as _,
+                        mime: APPLICATION_JSON,
+                    },
+                    buf,
+                ),
+            )
+        })
+}
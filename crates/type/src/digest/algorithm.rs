@@ -79,7 +79,7 @@ impl<'de> Deserialize<'de> for Algorithm {
 }
 
 impl Algorithm {
-    pub(crate) fn hasher(self) -> Box<dyn DynDigest> {
+    pub(crate) fn hasher(self) -> Box<dyn DynDigest + Send + Sync> {
         match self {
             Self::Sha224 => Box::new(Sha224::new()),
             Self::Sha256 => Box::new(Sha256::new()),
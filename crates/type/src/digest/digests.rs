@@ -11,7 +11,7 @@ use drawbridge_byte::Bytes;
 use serde::{Deserialize, Serialize};
 
 #[cfg(feature = "headers")]
-use headers::{Error as HeadErr, Header, HeaderName, HeaderValue};
+use headers::{ETag, Error as HeadErr, Header, HeaderName, HeaderValue};
 
 /// A set of hashes for the same contents
 #[derive(Clone, Default, Debug, Serialize, Deserialize)]
@@ -144,6 +144,22 @@ where
     }
 }
 
+#[cfg(feature = "headers")]
+impl<H> ContentDigest<H>
+where
+    H: AsRef<[u8]> + From<Vec<u8>>,
+{
+    /// Returns a strong [ETag] derived from the strongest hash in the set
+    pub fn etag(&self) -> ETag {
+        match self.iter().next_back() {
+            Some((algo, hash)) => format!(r#""{algo}=:{hash}:""#),
+            None => r#""""#.into(),
+        }
+        .parse()
+        .unwrap()
+    }
+}
+
 #[cfg(feature = "headers")]
 static CONTENT_DIGEST: HeaderName = HeaderName::from_static("content-digest");
 
@@ -197,4 +213,20 @@ mod tests {
         const STR: &str = "sha-224=:CAj2TmDViXn8tnbJbsk4Jw3qQkRa7vzTpOb42w==:,sha-256=:LCa0a2j/xo/5m0U8HTBBNBNCLXBkg7+g+YpeiGJm564=:,sha-384=:mMEf/f3VQGdrGhN8saIrKnA1DJpEFx1rEYDGvly7LuP3nVMsih3Z7y6OCOdSo7q7:,sha-512=:9/u6bgY2+JDlb7vzKD5STG+jIErimDgtYkdB0NxmODJuKCxBvl5CVNiCB3LFUYosWowMf37aGVlKfrU5RT4e1w==:";
         assert_eq!(STR.parse::<ContentDigest>().unwrap().to_string(), STR);
     }
+
+    #[cfg(feature = "headers")]
+    #[test]
+    fn etag() {
+        const STR: &str = "sha-224=:CAj2TmDViXn8tnbJbsk4Jw3qQkRa7vzTpOb42w==:,sha-256=:LCa0a2j/xo/5m0U8HTBBNBNCLXBkg7+g+YpeiGJm564=:";
+        assert_eq!(
+            STR.parse::<ContentDigest>().unwrap().etag(),
+            r#""sha-256=:LCa0a2j/xo/5m0U8HTBBNBNCLXBkg7+g+YpeiGJm564=:""#
+                .parse()
+                .unwrap()
+        );
+        assert_eq!(
+            ContentDigest::<Box<[u8]>>::default().etag(),
+            r#""""#.parse().unwrap()
+        );
+    }
 }
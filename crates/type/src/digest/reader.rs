@@ -15,7 +15,7 @@ use sha2::digest::DynDigest;
 #[allow(missing_debug_implementations)] // DynDigest does not implement Debug
 pub struct Reader<T> {
     reader: T,
-    digests: Vec<(Algorithm, Box<dyn DynDigest>)>,
+    digests: Vec<(Algorithm, Box<dyn DynDigest + Send + Sync>)>,
 }
 
 impl<T> Reader<T> {
@@ -35,7 +35,7 @@ impl<T> Reader<T> {
         let mut set = ContentDigest::default();
 
         for digest in &self.digests {
-            let _ = set.insert(digest.0, digest.1.clone().finalize().into());
+            let _ = set.insert(digest.0, digest.1.box_clone().finalize().into());
         }
 
         set
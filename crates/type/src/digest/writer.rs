@@ -15,15 +15,9 @@ use sha2::digest::DynDigest;
 #[allow(missing_debug_implementations)] // DynDigest does not implement Debug
 pub struct Writer<T> {
     writer: T,
-    digests: Vec<(Algorithm, Box<dyn DynDigest>)>,
+    digests: Vec<(Algorithm, Box<dyn DynDigest + Send + Sync>)>,
 }
 
-#[allow(unsafe_code)]
-unsafe impl<T> Sync for Writer<T> where T: Sync {}
-
-#[allow(unsafe_code)]
-unsafe impl<T> Send for Writer<T> where T: Send {}
-
 impl<T> Writer<T> {
     pub(crate) fn new(writer: T, digests: impl IntoIterator<Item = Algorithm>) -> Self {
         let digests = digests.into_iter().map(|a| (a, a.hasher())).collect();
@@ -41,7 +35,7 @@ impl<T> Writer<T> {
         let mut set = ContentDigest::default();
 
         for digest in &self.digests {
-            _ = set.insert(digest.0, digest.1.clone().finalize().into());
+            _ = set.insert(digest.0, digest.1.box_clone().finalize().into());
         }
 
         set
@@ -38,9 +38,13 @@ mod meta;
 
 pub use meta::*;
 pub use repository::{
-    Config as RepositoryConfig, Context as RepositoryContext, Name as RepositoryName,
+    Config as RepositoryConfig, Context as RepositoryContext, Entry as RepositoryEntry,
+    Name as RepositoryName,
+};
+pub use tag::{
+    Context as TagContext, Entry as TagEntry, Name as TagName, Query as TagQuery,
+    Resolution as TagResolution, Yank as TagYank,
 };
-pub use tag::{Context as TagContext, Entry as TagEntry, Name as TagName};
 pub use tree::{
     Content as TreeContent, Context as TreeContext, Directory as TreeDirectory, Entry as TreeEntry,
     Name as TreeName, Path as TreePath, Tree,
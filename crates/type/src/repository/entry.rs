@@ -0,0 +1,16 @@
+// SPDX-License-Identifier: Apache-2.0
+
+use super::{Config, Name};
+
+use serde::{Deserialize, Serialize};
+
+/// A repository listing entry
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+#[serde(deny_unknown_fields)]
+pub struct Entry {
+    /// Name of the repository
+    pub name: Name,
+
+    /// Config of the repository
+    pub config: Config,
+}
@@ -1,8 +1,14 @@
 // SPDX-License-Identifier: Apache-2.0
 mod config;
 mod context;
+mod entry;
 mod name;
 
 pub use config::*;
 pub use context::*;
+pub use entry::*;
 pub use name::*;
+
+/// Header confirming recursive deletion of a repository, which must be set to the full name of
+/// the repository, e.g. `user/repo`.
+pub const CONFIRM_DELETE_HEADER: &str = "Drawbridge-Confirm-Delete";
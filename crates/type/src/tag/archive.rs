@@ -0,0 +1,112 @@
+// SPDX-License-Identifier: Apache-2.0
+
+use std::fmt::{self, Display};
+use std::str::FromStr;
+
+use anyhow::bail;
+use serde::{Deserialize, Serialize};
+
+/// Media type of tag tree archives uploaded to the server.
+pub const ARCHIVE_TYPE: &str = "application/x-tar";
+
+/// Name of the member of a tag tree archive holding the root node of the tree. Other nodes are
+/// named by their tree path relative to it, e.g. `tree/dir/file`.
+pub const ARCHIVE_ROOT: &str = "tree";
+
+/// PAX extended header record holding the content digest of a tree node in downloaded archives.
+pub const ARCHIVE_DIGEST_KEY: &str = "DRAWBRIDGE.content-digest";
+
+/// PAX extended header record holding the media type of a tree node in downloaded archives.
+pub const ARCHIVE_MIME_KEY: &str = "DRAWBRIDGE.content-type";
+
+/// PAX extended header record holding the contents of a tree directory in downloaded archives.
+pub const ARCHIVE_DIRECTORY_KEY: &str = "DRAWBRIDGE.directory";
+
+/// A format of downloaded tag tree archives
+#[derive(Clone, Copy, Debug, Default, Deserialize, Eq, Hash, PartialEq, Serialize)]
+pub enum ArchiveFormat {
+    /// Uncompressed tar archive
+    #[default]
+    #[serde(rename = "tar")]
+    Tar,
+
+    /// Gzip-compressed tar archive
+    #[serde(rename = "tar.gz")]
+    TarGzip,
+
+    /// Zstandard-compressed tar archive
+    #[serde(rename = "tar.zst")]
+    TarZstd,
+}
+
+impl ArchiveFormat {
+    /// Returns the name of the format used in query parameters and file extensions.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Tar => "tar",
+            Self::TarGzip => "tar.gz",
+            Self::TarZstd => "tar.zst",
+        }
+    }
+
+    /// Returns the media type of archives in this format.
+    pub fn media_type(&self) -> &'static str {
+        match self {
+            Self::Tar => ARCHIVE_TYPE,
+            Self::TarGzip => "application/gzip",
+            Self::TarZstd => "application/zstd",
+        }
+    }
+}
+
+impl Display for ArchiveFormat {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl FromStr for ArchiveFormat {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "tar" => Ok(Self::Tar),
+            "tar.gz" => Ok(Self::TarGzip),
+            "tar.zst" => Ok(Self::TarZstd),
+            _ => bail!("unknown archive format `{s}`"),
+        }
+    }
+}
+
+/// A tag tree archive download query
+#[derive(Clone, Copy, Debug, Default, Deserialize, Eq, PartialEq, Serialize)]
+pub struct ArchiveQuery {
+    /// Format of the archive
+    #[serde(default)]
+    pub format: ArchiveFormat,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn format() {
+        for format in [
+            ArchiveFormat::Tar,
+            ArchiveFormat::TarGzip,
+            ArchiveFormat::TarZstd,
+        ] {
+            assert_eq!(format.as_str().parse::<ArchiveFormat>().unwrap(), format);
+            assert_eq!(
+                serde_json::to_value(format).unwrap(),
+                serde_json::json!(format.as_str())
+            );
+        }
+        assert!("zip".parse::<ArchiveFormat>().is_err());
+        assert_eq!(
+            serde_json::from_str::<ArchiveQuery>("{}").unwrap().format,
+            ArchiveFormat::Tar
+        );
+    }
+}
@@ -4,6 +4,8 @@ use super::super::TreeEntry;
 
 use drawbridge_jose::jws::Jws;
 
+use anyhow::{anyhow, Context};
+use serde::de::DeserializeOwned;
 use serde::{Deserialize, Serialize};
 
 #[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
@@ -12,3 +14,52 @@ pub enum Entry<E = TreeEntry> {
     Signed(Box<Jws>),
     Unsigned(E),
 }
+
+impl<E: Clone + DeserializeOwned> Entry<E> {
+    /// Returns the entry declared by the tag, decoding it from the payload of signed entries.
+    ///
+    /// Signatures are not verified.
+    pub fn entry(&self) -> anyhow::Result<E> {
+        let payload = match self {
+            Self::Unsigned(entry) => return Ok(entry.clone()),
+            Self::Signed(jws) => match jws.as_ref() {
+                Jws::General(jws) => jws.payload.as_ref(),
+                Jws::Flattened(jws) => jws.payload.as_ref(),
+            },
+        }
+        .ok_or_else(|| anyhow!("signed entry payload is detached"))?;
+        serde_json::from_slice(payload).context("failed to decode signed entry payload")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use serde_json::json;
+
+    #[test]
+    fn entry() {
+        let signed = |payload: &str| -> Entry<String> {
+            serde_json::from_value(json!({
+                "payload": payload,
+                "protected": "eyJhbGciOiJFUzI1NiJ9",
+                "signature": "DtEhU3ljbEg8L38VWAfUAqOyKAM6",
+            }))
+            .unwrap()
+        };
+
+        assert_eq!(Entry::Unsigned("test".to_string()).entry().unwrap(), "test");
+        // base64url of `"test"`
+        assert_eq!(signed("InRlc3Qi").entry().unwrap(), "test");
+        // base64url of `test`
+        assert!(signed("dGVzdA").entry().is_err());
+
+        let detached: Entry<String> = serde_json::from_value(json!({
+            "protected": "eyJhbGciOiJFUzI1NiJ9",
+            "signature": "DtEhU3ljbEg8L38VWAfUAqOyKAM6",
+        }))
+        .unwrap();
+        assert!(detached.entry().is_err());
+    }
+}
@@ -1,8 +1,24 @@
 // SPDX-License-Identifier: Apache-2.0
+mod archive;
 mod context;
 mod entry;
 mod name;
+mod query;
+mod resolution;
+mod yank;
 
+pub use archive::*;
 pub use context::*;
 pub use entry::*;
 pub use name::*;
+pub use query::*;
+pub use resolution::*;
+pub use yank::*;
+
+/// Header indicating whether all tree nodes declared by a tag are uploaded, i.e. whether the tag
+/// is sealed.
+pub const SEALED_HEADER: &str = "Drawbridge-Tag-Sealed";
+
+/// Header holding the amount of tree nodes, which are not uploaded yet, but declared by an
+/// uploaded directory of a tag or, for the root, by the tag entry.
+pub const MISSING_NODES_HEADER: &str = "Drawbridge-Tag-Missing-Nodes";
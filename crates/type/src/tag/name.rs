@@ -33,6 +33,49 @@ impl DerefMut for Name {
     }
 }
 
+impl Name {
+    /// Returns `true` if the version satisfies `req`.
+    ///
+    /// Pre-release versions only satisfy `req` if a comparator of it refers to a pre-release of
+    /// the same `major.minor.patch` version, unless `pre` is set, in which case they satisfy
+    /// `req` if they satisfy it extended by the comparator `>=major.minor.patch-0`. Pre-releases
+    /// preceding the lowest version admitted by `req`, like `1.5.0-rc.1` for `>=1.5.0`, never
+    /// satisfy it.
+    pub fn matches(&self, req: &semver::VersionReq, pre: bool) -> bool {
+        if req.matches(self) {
+            return true;
+        }
+        let lowest = match semver::Prerelease::new("0") {
+            Ok(lowest) if pre && !self.pre.is_empty() => lowest,
+            _ => return false,
+        };
+        let mut req = req.clone();
+        // Partial comparators order pre-releases before the lowest version they admit, e.g.
+        // `~0.2` rejects `0.2.1-rc.1`, hence they are completed where this retains their bounds.
+        for cmp in &mut req.comparators {
+            let complete = match cmp.op {
+                semver::Op::GreaterEq => true,
+                semver::Op::Tilde => cmp.minor.is_some(),
+                semver::Op::Caret => cmp.major > 0 || cmp.minor.is_some_and(|minor| minor > 0),
+                _ => false,
+            };
+            if complete {
+                _ = cmp.minor.get_or_insert(0);
+                _ = cmp.patch.get_or_insert(0);
+            }
+        }
+        // The lowest pre-release of the version opts into pre-releases without restricting `req`.
+        req.comparators.push(semver::Comparator {
+            op: semver::Op::GreaterEq,
+            major: self.major,
+            minor: Some(self.minor),
+            patch: Some(self.patch),
+            pre: lowest,
+        });
+        req.matches(self)
+    }
+}
+
 impl Display for Name {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         self.0.fmt(f)
@@ -83,4 +126,36 @@ mod tests {
             );
         }
     }
+
+    #[test]
+    fn matches() {
+        for (req, name, expected, expected_pre) in [
+            ("~1.4", "1.4.0", true, true),
+            ("~1.4", "1.4.7", true, true),
+            ("~1.4", "1.5.0", false, false),
+            ("^1.2", "1.9.3", true, true),
+            ("^1.2", "2.0.0", false, false),
+            ("^1.2", "1.5.0-rc.1", false, true),
+            ("^1.2", "2.0.0-rc.1", false, false),
+            ("^1.2.3-rc.1", "1.2.3-rc.2", true, true),
+            (">=1.2, <2", "1.2.0-rc.1", false, false),
+            (">=1.2, <2", "1.3.0-rc.1", false, true),
+            ("<1.5.0", "1.5.0-rc.1", false, true),
+            (">=1.5.0", "1.5.0-rc.1", false, false),
+            ("=1.5.0", "1.5.0-rc.1", false, false),
+            ("~0.2", "0.2.1-rc.1", false, true),
+            ("~0.2.1", "0.2.1-rc.1", false, false),
+            ("^0.0", "0.0.1-rc.1", false, true),
+            ("*", "0.1.0", true, true),
+        ] {
+            let req = req.parse().unwrap();
+            let name = name.parse::<Name>().unwrap();
+            assert_eq!(name.matches(&req, false), expected, "{name} matches {req}");
+            assert_eq!(
+                name.matches(&req, true),
+                expected_pre,
+                "{name} matches {req} including pre-releases"
+            );
+        }
+    }
 }
@@ -0,0 +1,131 @@
+// SPDX-License-Identifier: Apache-2.0
+
+use super::Name;
+
+use std::num::NonZeroUsize;
+
+use serde::{Deserialize, Serialize};
+
+/// A tag listing query
+///
+/// Tags are listed in semantic version order. Unset filters match all tags.
+#[derive(Clone, Debug, Default, Deserialize, Eq, PartialEq, Serialize)]
+pub struct Query {
+    /// Whether to include yanked tags
+    #[serde(default)]
+    pub yanked: bool,
+
+    /// Whether to include tags, which are not sealed yet, because some of their tree nodes are
+    /// not uploaded
+    #[serde(default)]
+    pub incomplete: bool,
+
+    /// Whether to only list pre-release tags (`true`) or release tags (`false`)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub pre: Option<bool>,
+
+    /// Major version of the tags to list
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub major: Option<u64>,
+
+    /// Only list tags created after this time in seconds since the Unix epoch
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub created_after: Option<u64>,
+
+    /// Only list tags ordered after this tag, used as the cursor of the next page
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub after: Option<Name>,
+
+    /// Maximum amount of tags to list
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub limit: Option<NonZeroUsize>,
+}
+
+impl Query {
+    /// Returns `true` if `name` matches the filters of the query, which do not require any
+    /// information about the tag other than its name.
+    pub fn matches_name(&self, name: &Name) -> bool {
+        self.pre.is_none_or(|pre| pre != name.pre.is_empty())
+            && self.major.is_none_or(|major| major == name.major)
+            && self.after.as_ref().is_none_or(|after| **name > **after)
+    }
+
+    /// Returns the query parameters as name-value pairs, omitting unset ones.
+    pub fn pairs(&self) -> Vec<(&'static str, String)> {
+        let mut pairs = vec![];
+        if self.yanked {
+            pairs.push(("yanked", true.to_string()));
+        }
+        if self.incomplete {
+            pairs.push(("incomplete", true.to_string()));
+        }
+        if let Some(pre) = self.pre {
+            pairs.push(("pre", pre.to_string()));
+        }
+        if let Some(major) = self.major {
+            pairs.push(("major", major.to_string()));
+        }
+        if let Some(created_after) = self.created_after {
+            pairs.push(("created_after", created_after.to_string()));
+        }
+        if let Some(ref after) = self.after {
+            pairs.push(("after", after.to_string()));
+        }
+        if let Some(limit) = self.limit {
+            pairs.push(("limit", limit.to_string()));
+        }
+        pairs
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn matches_name() {
+        let query = Query {
+            pre: Some(false),
+            major: Some(1),
+            after: Some("1.2.0".parse().unwrap()),
+            ..Default::default()
+        };
+        for (name, expected) in [
+            ("1.2.0", false),
+            ("1.2.1", true),
+            ("1.3.0-rc.1", false),
+            ("1.10.0", true),
+            ("2.0.0", false),
+            ("0.9.0", false),
+        ] {
+            assert_eq!(
+                query.matches_name(&name.parse().unwrap()),
+                expected,
+                "{name}"
+            );
+        }
+    }
+
+    #[test]
+    fn pairs() {
+        assert_eq!(Query::default().pairs(), vec![]);
+        assert_eq!(
+            Query {
+                yanked: true,
+                incomplete: true,
+                pre: Some(true),
+                after: Some("1.2.3+build".parse().unwrap()),
+                limit: NonZeroUsize::new(10),
+                ..Default::default()
+            }
+            .pairs(),
+            vec![
+                ("yanked", "true".into()),
+                ("incomplete", "true".into()),
+                ("pre", "true".into()),
+                ("after", "1.2.3+build".into()),
+                ("limit", "10".into()),
+            ]
+        );
+    }
+}
@@ -0,0 +1,16 @@
+// SPDX-License-Identifier: Apache-2.0
+
+use super::{Entry, Name};
+
+use serde::{Deserialize, Serialize};
+
+/// A tag resolved from a version requirement
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+#[serde(deny_unknown_fields)]
+pub struct Resolution {
+    /// Name of the highest tag satisfying the requirement
+    pub name: Name,
+
+    /// Entry the tag is associated with
+    pub entry: Entry,
+}
@@ -0,0 +1,16 @@
+// SPDX-License-Identifier: Apache-2.0
+
+use serde::{Deserialize, Serialize};
+
+/// A yank state of a tag
+///
+/// Yanked tags are omitted from tag listings by default, but remain available by their name.
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+#[serde(deny_unknown_fields)]
+pub struct Yank {
+    /// OpenID Connect identity subject of the user, who yanked the tag
+    pub subject: String,
+
+    /// Time the tag was yanked at in seconds since the Unix epoch
+    pub time: u64,
+}
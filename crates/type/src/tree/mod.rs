@@ -161,9 +161,11 @@ impl Tree<std::fs::File> {
                         )?
                     }
                     t if t.is_dir() => {
+                        // Descendants of `path` directly follow it in the tree
                         let dir: Directory<_> = tree
                             .range((Excluded(&path), Unbounded))
-                            .map_while(|(p, e)| match p.split_last() {
+                            .take_while(|(p, _)| p.starts_with(path.as_slice()))
+                            .filter_map(|(p, e)| match p.split_last() {
                                 Some((base, dir)) if dir == path.as_slice() => {
                                     // TODO: Remove the need for a clone, we probably should have
                                     // Path and PathBuf analogues for that
@@ -300,6 +302,17 @@ args = ["foo", "bar"]"#
                 ),
                 None
             );
+            assert_eq!(
+                m.insert(
+                    "test-file-foo".parse().unwrap(),
+                    Entry {
+                        meta: foo_meta.clone(),
+                        custom: Default::default(),
+                        content: (),
+                    },
+                ),
+                None
+            );
             m
         }))
         .unwrap();
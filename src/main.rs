@@ -28,70 +28,189 @@
     variant_size_differences
 )]
 
+use cli::{Args, Command, ServeArgs, StoreArgs};
+
+use std::env;
 use std::fs::File;
 use std::io::{self, BufRead, BufReader};
-use std::net::{IpAddr, Ipv4Addr, SocketAddr};
-use std::path::{Path, PathBuf};
+use std::path::Path;
+use std::process::ExitCode;
 
-use drawbridge_server::url::Url;
+use drawbridge_server::store::{Report, S3Config, StorageConfig, Store};
 use drawbridge_server::{App, OidcConfig, TlsConfig};
 
 use anyhow::Context as _;
 use async_std::net::TcpListener;
-use clap::Parser;
+use clap::error::ErrorKind;
+use clap::{CommandFactory, Parser};
 use confargs::{args, prefix_char_filter, Toml};
 use futures::StreamExt;
 use tracing::{debug, error};
 
-/// Server for hosting WebAssembly modules for use in Enarx keeps.
+/// Command-line interface.
 ///
-/// Any command-line options listed here may be specified by one or
-/// more configuration files, which can be used by passing the
-/// name of the file on the command-line with the syntax `@config.toml`.
-/// The configuration file must contain valid TOML table mapping argument
-/// names to their values.
-#[derive(Parser, Debug)]
-#[command(author, version, about)]
-struct Args {
-    /// Address to bind to.
-    #[arg(long, default_value_t = SocketAddr::new(IpAddr::V4(Ipv4Addr::UNSPECIFIED), 8080))]
-    addr: SocketAddr,
-
-    /// Path to the Drawbridge store.
-    #[arg(long)]
-    store: PathBuf,
-
-    /// Path to PEM-encoded server certificate.
-    #[arg(long)]
-    cert: PathBuf,
-
-    /// Path to PEM-encoded server certificate key.
-    #[arg(long)]
-    key: PathBuf,
-
-    /// Path to PEM-encoded trusted CA certificate.
+/// `clap` generates code triggering `unused_results` for optional flattened arguments, hence
+/// this is kept in a separate module.
+#[allow(unused_results)]
+mod cli {
+    use std::net::{IpAddr, Ipv4Addr, SocketAddr};
+    use std::path::PathBuf;
+
+    use drawbridge_server::url::Url;
+
+    use clap::{Parser, Subcommand};
+
+    /// Server for hosting WebAssembly modules for use in Enarx keeps.
     ///
-    /// Clients that present a valid certificate signed by this CA
-    /// are granted read-only access to all repositories in the store.
-    #[arg(long)]
-    ca: PathBuf,
-
-    /// OpenID Connect issuer URL.
-    #[arg(long)]
-    oidc_issuer: Url,
-
-    /// OpenID Connect audience.
-    #[arg(long)]
-    oidc_audience: String,
+    /// Any command-line options listed here may be specified by one or
+    /// more configuration files, which can be used by passing the
+    /// name of the file on the command-line with the syntax `@config.toml`.
+    /// The configuration file must contain valid TOML table mapping argument
+    /// names to their values.
+    #[derive(Parser, Debug)]
+    #[command(author, version, about, args_conflicts_with_subcommands = true)]
+    pub(super) struct Args {
+        #[command(subcommand)]
+        pub(super) command: Option<Command>,
+
+        #[command(flatten)]
+        pub(super) serve: Option<ServeArgs>,
+    }
+
+    #[derive(Subcommand, Debug)]
+    pub(super) enum Command {
+        /// Verify integrity of the store and print a JSON report of problems found.
+        ///
+        /// Exits with a non-zero status if unrepaired problems were found.
+        /// Must not be run while a server is using the store.
+        Fsck {
+            #[command(flatten)]
+            store: StoreArgs,
+
+            /// Remove data left behind by interrupted uploads and migrate an outdated store
+            /// layout.
+            #[arg(long)]
+            repair: bool,
+        },
+
+        /// Remove data not referenced by any tree node and print a JSON report of data removed.
+        ///
+        /// An outdated store layout is migrated first, which is reported as well.
+        ///
+        /// Must not be run while a server is using the store.
+        Gc {
+            #[command(flatten)]
+            store: StoreArgs,
+
+            /// Only report data, which would be removed, and an outdated store layout without
+            /// migrating it.
+            #[arg(long)]
+            dry_run: bool,
+        },
+    }
+
+    #[derive(clap::Args, Debug)]
+    pub(super) struct ServeArgs {
+        /// Address to bind to.
+        #[arg(long, default_value_t = SocketAddr::new(IpAddr::V4(Ipv4Addr::UNSPECIFIED), 8080))]
+        pub(super) addr: SocketAddr,
+
+        #[command(flatten)]
+        pub(super) store: StoreArgs,
+
+        /// Path to PEM-encoded server certificate.
+        #[arg(long)]
+        pub(super) cert: PathBuf,
+
+        /// Path to PEM-encoded server certificate key.
+        #[arg(long)]
+        pub(super) key: PathBuf,
+
+        /// Path to PEM-encoded trusted CA certificate.
+        ///
+        /// Clients that present a valid certificate signed by this CA
+        /// are granted read-only access to all repositories in the store.
+        #[arg(long)]
+        pub(super) ca: PathBuf,
+
+        /// OpenID Connect issuer URL.
+        #[arg(long)]
+        pub(super) oidc_issuer: Url,
+
+        /// OpenID Connect audience.
+        #[arg(long)]
+        pub(super) oidc_audience: String,
+    }
+
+    /// Location of the Drawbridge store.
+    ///
+    /// Credentials for the S3-compatible object store are read from the `AWS_ACCESS_KEY_ID`
+    /// and `AWS_SECRET_ACCESS_KEY` environment variables.
+    #[derive(clap::Args, Debug)]
+    pub(super) struct StoreArgs {
+        /// Path to the Drawbridge store.
+        #[arg(long, required_unless_present = "s3_bucket")]
+        pub(super) store: Option<PathBuf>,
+
+        /// Endpoint of the S3-compatible object store to keep the Drawbridge store in.
+        #[arg(long, requires = "s3_bucket", conflicts_with = "store")]
+        pub(super) s3_endpoint: Option<Url>,
+
+        /// Bucket of the S3-compatible object store to keep the Drawbridge store in.
+        #[arg(long, requires = "s3_endpoint")]
+        pub(super) s3_bucket: Option<String>,
+
+        /// Region of the S3-compatible object store.
+        #[arg(long, default_value = "us-east-1")]
+        pub(super) s3_region: String,
+    }
 }
 
 fn open_buffered(p: impl AsRef<Path>) -> io::Result<impl BufRead> {
     File::open(p).map(BufReader::new)
 }
 
+/// Returns the storage backend configuration specified by `args`.
+fn storage_config(args: StoreArgs) -> anyhow::Result<StorageConfig> {
+    match args {
+        StoreArgs {
+            store: Some(path), ..
+        } => Ok(path.into()),
+        StoreArgs {
+            s3_endpoint: Some(endpoint),
+            s3_bucket: Some(bucket),
+            s3_region: region,
+            ..
+        } => Ok(S3Config {
+            endpoint,
+            region,
+            bucket,
+            access_key: env::var("AWS_ACCESS_KEY_ID")
+                .context("Failed to read `AWS_ACCESS_KEY_ID` environment variable")?,
+            secret_key: env::var("AWS_SECRET_ACCESS_KEY")
+                .context("Failed to read `AWS_SECRET_ACCESS_KEY` environment variable")?,
+        }
+        .into()),
+        _ => Err(anyhow::anyhow!(
+            "Either a store path or an S3 endpoint and bucket must be specified"
+        )),
+    }
+}
+
+/// Opens the store specified by `args`.
+async fn open_store(args: StoreArgs) -> anyhow::Result<Store> {
+    Store::open(storage_config(args)?).await
+}
+
+fn print_report(report: &Report) -> anyhow::Result<()> {
+    serde_json::to_writer_pretty(io::stdout().lock(), report).context("Failed to write report")?;
+    println!();
+    Ok(())
+}
+
 #[async_std::main]
-async fn main() -> anyhow::Result<()> {
-    if std::env::var("RUST_LOG_JSON").is_ok() {
+async fn main() -> anyhow::Result<ExitCode> {
+    if env::var("RUST_LOG_JSON").is_ok() {
         tracing_subscriber::fmt::fmt()
             .json()
             .with_env_filter(tracing_subscriber::EnvFilter::from_default_env())
@@ -100,7 +219,10 @@ async fn main() -> anyhow::Result<()> {
         tracing_subscriber::fmt::init();
     }
 
-    let Args {
+    let args = args::<Toml>(prefix_char_filter::<'@'>)
+        .context("Failed to parse config")
+        .map(Args::parse_from)?;
+    let ServeArgs {
         addr,
         store,
         cert,
@@ -108,9 +230,37 @@ async fn main() -> anyhow::Result<()> {
         ca,
         oidc_audience,
         oidc_issuer,
-    } = args::<Toml>(prefix_char_filter::<'@'>)
-        .context("Failed to parse config")
-        .map(Args::parse_from)?;
+    } = match args {
+        Args {
+            command: Some(Command::Fsck { store, repair }),
+            ..
+        } => {
+            let report = open_store(store).await?.fsck(repair).await?;
+            print_report(&report)?;
+            return Ok(if report.is_ok() {
+                ExitCode::SUCCESS
+            } else {
+                ExitCode::FAILURE
+            });
+        }
+        Args {
+            command: Some(Command::Gc { store, dry_run }),
+            ..
+        } => {
+            let report = open_store(store).await?.gc(dry_run).await?;
+            print_report(&report)?;
+            return Ok(ExitCode::SUCCESS);
+        }
+        Args {
+            serve: Some(serve), ..
+        } => serve,
+        Args { serve: None, .. } => Args::command()
+            .error(
+                ErrorKind::MissingRequiredArgument,
+                "server options or a subcommand must be specified",
+            )
+            .exit(),
+    };
 
     let cert = open_buffered(cert).context("Failed to open server certificate file")?;
     let key = open_buffered(key).context("Failed to open server key file")?;
@@ -118,7 +268,7 @@ async fn main() -> anyhow::Result<()> {
     let tls = TlsConfig::read(cert, key, ca).context("Failed to construct server TLS config")?;
 
     let app = App::new(
-        store,
+        storage_config(store)?,
         tls,
         OidcConfig {
             audience: oidc_audience,
@@ -150,5 +300,5 @@ async fn main() -> anyhow::Result<()> {
             }
         })
         .await;
-    Ok(())
+    Ok(ExitCode::SUCCESS)
 }
@@ -1,23 +1,44 @@
 // SPDX-FileCopyrightText: 2022 Profian Inc. <opensource@profian.com>
 // SPDX-License-Identifier: Apache-2.0
 
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap};
+use std::io::{Read, Seek, SeekFrom, Write};
+use std::num::NonZeroUsize;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
 use std::time::{Duration, SystemTime};
 
-use drawbridge_client::mime::APPLICATION_OCTET_STREAM;
-use drawbridge_client::types::{RepositoryConfig, TreePath, UserRecord};
-use drawbridge_client::Client;
+use drawbridge_client::jose::jws::{Flattened, Jws, Signature};
+use drawbridge_client::jose::MediaTyped;
+use drawbridge_client::mime::{Mime, APPLICATION_JSON, APPLICATION_OCTET_STREAM, TEXT_PLAIN};
+use drawbridge_client::types::{
+    RepositoryConfig, RepositoryEntry, TagEntry, TagQuery, TagResolution, TreeEntry, TreePath,
+    UserRecord,
+};
+use drawbridge_client::{
+    Cache, Client, LimitExceeded, Limits, Progress, Repository, RetryPolicy, TreeErrors,
+    UnexpectedMediaType, Url,
+};
+use drawbridge_server::store::{
+    AuditAction, AuditEvent, CreateError, DeleteError, Finding, Problem, S3Config, StorageConfig,
+    Store,
+};
 use drawbridge_server::{App, OidcConfig, TlsConfig};
 
 use async_std::fs::{create_dir, write};
 use async_std::net::{Ipv4Addr, TcpListener};
 use async_std::task::{spawn, spawn_blocking};
+use camino::Utf8Path;
 use drawbridge_type::digest::Algorithms;
+use drawbridge_type::tag::{ArchiveFormat, ARCHIVE_DIRECTORY_KEY, ARCHIVE_ROOT};
 use drawbridge_type::Meta;
+use drawbridge_type::{
+    RepositoryContext, RepositoryName, TagName, Tree, TreeContent, TreeDirectory, UserContext,
+};
 use futures::channel::oneshot::channel;
-use futures::{join, try_join, StreamExt};
+use futures::{join, try_join, AsyncReadExt, AsyncSeekExt, StreamExt, TryStreamExt};
 use http_types::convert::{json, Serialize};
-use http_types::{Body, Response, StatusCode};
+use http_types::{Body, Method, Request, Response, StatusCode};
 use jsonwebtoken::{encode, EncodingKey, Header};
 use openidconnect::core::{
     CoreJwsSigningAlgorithm, CoreProviderMetadata, CoreResponseType, CoreSubjectIdentifierType,
@@ -32,7 +53,7 @@ use rustls::RootCertStore;
 use rustls_pemfile::Item::{Pkcs1Key, Pkcs8Key, Sec1Key};
 use rustls_pki_types::CertificateDer;
 use rustls_pki_types::PrivateKeyDer;
-use tempfile::tempdir;
+use tempfile::{tempdir, tempfile};
 
 #[derive(Debug, Clone, serde::Serialize)]
 struct TokenClaims {
@@ -49,6 +70,46 @@ struct TokenClaims {
     scope: String,
 }
 
+/// Records progress of tree transfers, keyed by path of the node.
+#[derive(Debug, Default)]
+struct RecordedProgress {
+    total: Mutex<Option<(usize, u64)>>,
+    nodes: Mutex<BTreeMap<TreePath, (u64, bool)>>,
+}
+
+impl Progress for RecordedProgress {
+    fn total(&self, nodes: usize, bytes: u64) {
+        *self.total.lock().unwrap() = Some((nodes, bytes));
+    }
+
+    fn start(&self, path: &TreePath, _: &Meta) {
+        assert!(self
+            .nodes
+            .lock()
+            .unwrap()
+            .insert(path.clone(), (0, false))
+            .is_none());
+    }
+
+    fn transferred(&self, path: &TreePath, bytes: u64) {
+        self.nodes
+            .lock()
+            .unwrap()
+            .get_mut(path)
+            .expect("node transferred before it was started")
+            .0 += bytes;
+    }
+
+    fn finish(&self, path: &TreePath) {
+        let mut nodes = self.nodes.lock().unwrap();
+        let (_, finished) = nodes
+            .get_mut(path)
+            .expect("node finished before it was started");
+        assert!(!*finished);
+        *finished = true;
+    }
+}
+
 #[async_std::test]
 async fn app() {
     tracing_subscriber::fmt::init();
@@ -150,6 +211,11 @@ async fn app() {
         }),
     ]);
     let oidc_token_valid = oidc_tokens.remove("valid").unwrap();
+    let oidc_token_other = {
+        let mut payload = jwt_payload.clone();
+        payload.subject = format!("{SUBJECT}other");
+        encode(&jwt_header, &payload, &oidc_key).expect("failed to sign token")
+    };
 
     let (oidc_tx, oidc_rx) = channel::<()>();
     let oidc = spawn(async move {
@@ -206,6 +272,7 @@ async fn app() {
     let store = tempdir().expect("failed to create temporary store directory");
 
     let (srv_tx, srv_rx) = channel::<()>();
+    let srv_store = store.path().to_path_buf();
     let srv = spawn(async move {
         let tls = TlsConfig::read(
             include_bytes!("../testdata/server.crt").as_slice(),
@@ -214,7 +281,7 @@ async fn app() {
         )
         .unwrap();
         let app = App::new(
-            store.path(),
+            srv_store,
             tls,
             OidcConfig {
                 audience: oidc_audience.to_string(),
@@ -235,20 +302,33 @@ async fn app() {
     });
 
     let cl = spawn_blocking(move || async move {
+        let roots = {
+            let mut roots = RootCertStore::empty();
+            rustls_pemfile::certs(&mut std::io::BufReader::new(
+                include_bytes!("../testdata/ca.crt").as_slice(),
+            ))
+            .for_each(|c| {
+                if let Ok(cert) = c {
+                    roots.add(cert).expect("failed to add cert to root store");
+                }
+            });
+            roots
+        };
+
+        // Agent used to exercise HTTP semantics of the server, which clients do not expose
+        let raw_cl = ureq::AgentBuilder::new()
+            .tls_config(Arc::new(
+                rustls::ClientConfig::builder()
+                    .with_root_certificates(roots.clone())
+                    .with_no_client_auth(),
+            ))
+            .build();
+        let raw_url = |path: &str| format!("https://localhost:{srv_port}/api/v0.1.0/{path}");
+
         let (anon_cl, cert_cl, oidc_valid_cl, blank_cl) = {
             let cl = Client::builder(format!("https://localhost:{srv_port}").parse().unwrap())
-                .roots({
-                    let mut roots = RootCertStore::empty();
-                    rustls_pemfile::certs(&mut std::io::BufReader::new(
-                        include_bytes!("../testdata/ca.crt").as_slice(),
-                    ))
-                    .for_each(|c| {
-                        if let Ok(cert) = c {
-                            roots.add(cert).expect("failed to add cert to root store");
-                        }
-                    });
-                    roots
-                });
+                .parallelism(NonZeroUsize::new(4).unwrap())
+                .roots(roots);
 
             let cert = rustls_pemfile::certs(&mut std::io::BufReader::new(
                 include_bytes!("../testdata/client.crt").as_slice(),
@@ -271,7 +351,7 @@ async fn app() {
             (
                 cl.clone().build().unwrap(),
                 cl.clone().credentials(cert, key).build().unwrap(),
-                cl.clone().token(oidc_token_valid).build().unwrap(),
+                cl.clone().token(oidc_token_valid.clone()).build().unwrap(),
                 cl,
             )
         };
@@ -379,6 +459,48 @@ async fn app() {
         assert_eq!(cert_pub_repo.tags().expect("failed to get tags"), vec![]);
         assert_eq!(oidc_pub_repo.tags().expect("failed to get tags"), vec![]);
 
+        let prv_repo_entry = RepositoryEntry {
+            name: prv_repo_name.clone(),
+            config: prv_repo_conf.clone(),
+        };
+        let pub_repo_entry = RepositoryEntry {
+            name: pub_repo_name.clone(),
+            config: pub_repo_conf.clone(),
+        };
+        assert_eq!(
+            anon_user
+                .repositories()
+                .expect("failed to list repositories"),
+            vec![pub_repo_entry.clone()]
+        );
+        assert_eq!(
+            cert_user
+                .repositories()
+                .expect("failed to list repositories"),
+            vec![prv_repo_entry.clone(), pub_repo_entry.clone()]
+        );
+        assert_eq!(
+            blank_cl
+                .clone()
+                .token(oidc_token_other)
+                .build()
+                .unwrap()
+                .user(&user_name)
+                .repositories()
+                .expect("failed to list repositories"),
+            vec![pub_repo_entry.clone()]
+        );
+        assert_eq!(
+            oidc_user
+                .repositories()
+                .expect("failed to list repositories"),
+            vec![prv_repo_entry, pub_repo_entry]
+        );
+        assert!(anon_cl
+            .user(&"nobody".parse().unwrap())
+            .repositories()
+            .is_err());
+
         let pkg = tempdir().expect("failed to create temporary package directory");
 
         try_join!(
@@ -455,7 +577,241 @@ async fn app() {
             oidc_pub_tag
                 .create_from_path_unsigned(pkg.path())
                 .expect("failed to create a tag and upload the tree"),
-            (prv_tag_created, prv_tree_created)
+            (prv_tag_created, prv_tree_created.clone())
+        );
+        // Pushing an existing tree again reuses all nodes
+        assert_eq!(
+            oidc_prv_tag
+                .create_from_path_unsigned(pkg.path())
+                .expect("failed to push the tree again"),
+            (
+                false,
+                prv_tree_created
+                    .keys()
+                    .map(|path| (path.clone(), false))
+                    .collect()
+            )
+        );
+
+        // Checkouts skip files, which are already present with matching contents
+        let co = tempdir().expect("failed to create temporary checkout directory");
+        let dest = co.path().join("checkout");
+        assert!(anon_prv_tag.checkout(&dest).is_err());
+        assert_eq!(
+            anon_pub_tag
+                .checkout(&dest)
+                .expect("failed to check out tag"),
+            prv_tree_created
+        );
+        assert_eq!(
+            std::fs::read_to_string(dest.join("test-dir-1/test-subdir-2/test-file")).unwrap(),
+            "test"
+        );
+        std::fs::write(dest.join("test-file.txt"), "changed").unwrap();
+        std::fs::remove_file(dest.join("test-dir-1/test-file")).unwrap();
+        assert_eq!(
+            anon_pub_tag
+                .checkout(&dest)
+                .expect("failed to resume checkout")
+                .into_iter()
+                .filter(|(_, written)| *written)
+                .map(|(path, _)| path)
+                .collect::<Vec<_>>(),
+            vec![
+                "test-dir-1/test-file".parse().unwrap(),
+                "test-file.txt".parse().unwrap(),
+            ]
+        );
+        assert_eq!(
+            std::fs::read_to_string(dest.join("test-file.txt")).unwrap(),
+            "text"
+        );
+        assert_eq!(std::fs::read_dir(&dest).unwrap().count(), 5);
+
+        // Failures are reported for each node
+        let dest = co.path().join("conflicting");
+        std::fs::create_dir_all(dest.join("test-file")).unwrap();
+        std::fs::create_dir_all(dest.join("test-file.txt")).unwrap();
+        let err = anon_pub_tag
+            .checkout(&dest)
+            .expect_err("checkout into conflicting directory succeeded");
+        assert_eq!(
+            err.downcast_ref::<TreeErrors>()
+                .expect("checkout failure does not list nodes")
+                .0
+                .keys()
+                .collect::<Vec<_>>(),
+            vec![
+                &"test-file".parse::<TreePath>().unwrap(),
+                &"test-file.txt".parse().unwrap(),
+            ]
+        );
+
+        // Cached contents are verified on read and evicted least recently used first
+        let cache_dir = tempdir().expect("failed to create temporary cache directory");
+        let cached_cl = blank_cl
+            .clone()
+            .cache(Cache::new(cache_dir.path(), 6).expect("failed to open cache"))
+            .build()
+            .unwrap();
+        // Asynchronous clients do not support caches
+        assert!(blank_cl
+            .clone()
+            .cache(Cache::new(cache_dir.path(), 6).expect("failed to open cache"))
+            .build_async()
+            .is_err());
+        let cached_pub_repo = cached_cl.user(&user_name).repository(&pub_repo_name);
+        let cached_pub_tag = cached_pub_repo.tag(&tag_name);
+        let cached_contents = || {
+            std::fs::read_dir(cache_dir.path().join("contents"))
+                .unwrap()
+                .map(|entry| std::fs::read_to_string(entry.unwrap().path()).unwrap())
+                .collect::<Vec<_>>()
+        };
+        let get_cached = |path: &str| {
+            cached_pub_tag
+                .path(&path.parse().unwrap())
+                .get_bytes(4)
+                .expect("failed to get cached file")
+                .1
+        };
+        assert_eq!(get_cached("test-file.txt"), b"text");
+        assert_eq!(cached_contents(), vec!["text"]);
+        let entry = std::fs::read_dir(cache_dir.path().join("contents"))
+            .unwrap()
+            .next()
+            .unwrap()
+            .unwrap()
+            .path();
+        std::fs::write(&entry, "tex!").unwrap();
+        assert_eq!(get_cached("test-file.txt"), b"text");
+        assert_eq!(cached_contents(), vec!["text"]);
+        assert_eq!(get_cached("test-dir-1/test-subdir-2/test-file"), b"test");
+        assert_eq!(cached_contents(), vec!["test"]);
+
+        // Validators of cached listings are shared by clients using the same cache, count
+        // towards its maximum size and are evicted along with contents
+        let cached_validators =
+            |dir: &std::path::Path| std::fs::read_dir(dir.join("validators")).unwrap().count();
+        assert_eq!(
+            cached_pub_repo.tags().expect("failed to get tags"),
+            vec![tag_name.clone()]
+        );
+        assert_eq!(cached_validators(cache_dir.path()), 0);
+        assert_eq!(cached_contents(), vec!["test"]);
+        let validator_dir = tempdir().expect("failed to create temporary cache directory");
+        let validator_cl = |max_size| {
+            blank_cl
+                .clone()
+                .cache(Cache::new(validator_dir.path(), max_size).expect("failed to open cache"))
+                .build()
+                .unwrap()
+        };
+        for _ in 0..2 {
+            assert_eq!(
+                validator_cl(1024)
+                    .user(&user_name)
+                    .repository(&pub_repo_name)
+                    .tags()
+                    .expect("failed to revalidate tags"),
+                vec![tag_name.clone()]
+            );
+            assert_eq!(cached_validators(validator_dir.path()), 1);
+        }
+        assert_eq!(
+            validator_cl(4)
+                .user(&user_name)
+                .repository(&pub_repo_name)
+                .tag(&tag_name)
+                .path(&"test-file.txt".parse().unwrap())
+                .get_bytes(4)
+                .expect("failed to get cached file")
+                .1,
+            b"text"
+        );
+        assert_eq!(cached_validators(validator_dir.path()), 0);
+        assert_eq!(
+            std::fs::read_dir(validator_dir.path().join("contents"))
+                .unwrap()
+                .count(),
+            1
+        );
+
+        // Tree nodes exceeding the limit of the client are not downloaded
+        let err = blank_cl
+            .clone()
+            .limits(Limits {
+                node: 4,
+                ..Default::default()
+            })
+            .build()
+            .unwrap()
+            .user(&user_name)
+            .repository(&pub_repo_name)
+            .tag(&tag_name)
+            .checkout(co.path().join("limited"))
+            .expect_err("checkout of nodes exceeding the limit succeeded");
+        assert_eq!(
+            err.downcast_ref::<TreeErrors>()
+                .expect("checkout failure does not list nodes")
+                .0[&TreePath::ROOT]
+                .downcast_ref::<LimitExceeded>(),
+            Some(&LimitExceeded {
+                size: anon_pub_tag
+                    .path(&TreePath::ROOT)
+                    .head()
+                    .expect("failed to get root metadata")
+                    .size,
+                limit: 4
+            })
+        );
+
+        // Progress of tree transfers is reported for each node
+        let progress = Arc::new(RecordedProgress::default());
+        let progress_cl = blank_cl
+            .clone()
+            .token(oidc_token_valid.clone())
+            .progress(progress.clone())
+            .build()
+            .unwrap();
+        let progress_tag = progress_cl
+            .user(&user_name)
+            .repository(&pub_repo_name)
+            .tag(&tag_name);
+        let tree = Tree::from_path_sync(pkg.path()).unwrap();
+        assert_eq!(
+            progress_tag
+                .checkout(co.path().join("progress"))
+                .expect("failed to check out tag"),
+            prv_tree_created
+        );
+        assert_eq!(*progress.total.lock().unwrap(), None);
+        assert_eq!(
+            *progress.nodes.lock().unwrap(),
+            tree.iter()
+                .map(|(path, entry)| (path.clone(), (entry.meta.size, true)))
+                .collect()
+        );
+        progress.nodes.lock().unwrap().clear();
+        assert_eq!(
+            progress_tag
+                .create_from_path_unsigned(pkg.path())
+                .expect("failed to push the tree again"),
+            (
+                false,
+                prv_tree_created
+                    .keys()
+                    .map(|path| (path.clone(), false))
+                    .collect()
+            )
+        );
+        assert_eq!(
+            *progress.total.lock().unwrap(),
+            Some((tree.len(), tree.values().map(|entry| entry.meta.size).sum()))
+        );
+        assert_eq!(
+            *progress.nodes.lock().unwrap(),
+            tree.keys().map(|path| (path.clone(), (0, true))).collect()
         );
 
         assert!(anon_prv_repo.tags().is_err());
@@ -477,6 +833,63 @@ async fn app() {
             oidc_pub_repo.tags().expect("failed to get tags"),
             vec![tag_name.clone()]
         );
+        // Revalidated listing
+        assert_eq!(
+            oidc_pub_repo.tags().expect("failed to get tags"),
+            vec![tag_name.clone()]
+        );
+
+        let tags_url = raw_url(&format!("{user_name}/{pub_repo_name}/_tag"));
+        let res = raw_cl.get(&tags_url).call().expect("failed to get tags");
+        let tags_etag = res
+            .header("ETag")
+            .expect("tag listing lacks an ETag")
+            .to_string();
+        assert_eq!(
+            res.into_json::<Vec<TagName>>().unwrap(),
+            vec![tag_name.clone()]
+        );
+
+        let res = raw_cl
+            .get(&tags_url)
+            .set("If-None-Match", &tags_etag)
+            .call()
+            .expect("failed to revalidate tags");
+        assert_eq!(res.status(), 304);
+        assert_eq!(res.header("ETag"), Some(tags_etag.as_str()));
+
+        let res = raw_cl
+            .get(&tags_url)
+            .set("If-None-Match", "\"mismatched\"")
+            .call()
+            .expect("failed to revalidate tags");
+        assert_eq!(res.status(), 200);
+        assert_eq!(
+            res.into_json::<Vec<TagName>>().unwrap(),
+            vec![tag_name.clone()]
+        );
+
+        let res = raw_cl
+            .get(&tags_url)
+            .set("If-Match", &tags_etag)
+            .call()
+            .expect("failed to get tags");
+        assert_eq!(res.status(), 200);
+        assert_eq!(
+            res.into_json::<Vec<TagName>>().unwrap(),
+            vec![tag_name.clone()]
+        );
+
+        match raw_cl
+            .get(&tags_url)
+            .set("If-Match", "\"mismatched\"")
+            .call()
+        {
+            Err(ureq::Error::Status(412, res)) => {
+                assert_eq!(res.into_string().unwrap(), "Precondition failed")
+            }
+            res => panic!("mismatched `If-Match` not rejected: {res:?}"),
+        }
 
         let file_name = "test-file.txt".parse().unwrap();
         let file_meta = Algorithms::default()
@@ -519,6 +932,673 @@ async fn app() {
             oidc_pub_file.get_string(5).expect("failed to get file"),
             file_expected,
         );
+
+        assert!(anon_prv_file
+            .resume_to(5, &mut tempfile().unwrap())
+            .is_err());
+        for prefix in ["", "te", "text"] {
+            let mut file = tempfile().expect("failed to create temporary file");
+            file.write_all(prefix.as_bytes()).unwrap();
+            assert_eq!(
+                cert_prv_file
+                    .resume_to(5, &mut file)
+                    .expect("failed to resume file download"),
+                file_expected.0,
+            );
+            let mut buf = String::new();
+            file.rewind().unwrap();
+            assert_eq!(file.read_to_string(&mut buf).unwrap(), "text".len());
+            assert_eq!(buf, "text");
+        }
+        for prefix in ["tx", "texts"] {
+            let mut file = tempfile().expect("failed to create temporary file");
+            file.write_all(prefix.as_bytes()).unwrap();
+            assert!(anon_pub_file.resume_to(5, &mut file).is_err());
+        }
+
+        let file_url = raw_url(&format!(
+            "{user_name}/{pub_repo_name}/_tag/{tag_name}/tree/{file_name}"
+        ));
+        let res = raw_cl.get(&file_url).call().expect("failed to get file");
+        let file_etag = res
+            .header("ETag")
+            .expect("file response lacks an ETag")
+            .to_string();
+        assert_eq!(res.into_string().unwrap(), "text");
+
+        let res = raw_cl
+            .get(&file_url)
+            .set("Range", "bytes=1-2")
+            .call()
+            .expect("failed to get file range");
+        assert_eq!(res.status(), 206);
+        assert_eq!(res.header("Content-Range"), Some("bytes 1-2/4"));
+        assert_eq!(res.into_string().unwrap(), "ex");
+
+        let res = raw_cl
+            .get(&file_url)
+            .set("Range", "bytes=0-0,-2")
+            .call()
+            .expect("failed to get file ranges");
+        assert_eq!(res.status(), 206);
+        let boundary = res
+            .header("Content-Type")
+            .and_then(|typ| typ.strip_prefix("multipart/byteranges; boundary="))
+            .expect("multiple ranges not served as `multipart/byteranges`")
+            .to_string();
+        assert_eq!(
+            res.into_string().unwrap(),
+            format!(
+                "\r\n--{boundary}\r\ncontent-type: {APPLICATION_OCTET_STREAM}\r\ncontent-range: bytes 0-0/4\r\n\r\nt\
+                 \r\n--{boundary}\r\ncontent-type: {APPLICATION_OCTET_STREAM}\r\ncontent-range: bytes 2-3/4\r\n\r\nxt\
+                 \r\n--{boundary}--\r\n"
+            )
+        );
+
+        match raw_cl.get(&file_url).set("Range", "bytes=4-").call() {
+            Err(ureq::Error::Status(416, res)) => {
+                assert_eq!(res.header("Content-Range"), Some("bytes */4"))
+            }
+            res => panic!("unsatisfiable range not rejected: {res:?}"),
+        }
+
+        let res = raw_cl
+            .get(&file_url)
+            .set("Range", "bytes=1-2")
+            .set("If-Range", &file_etag)
+            .call()
+            .expect("failed to get file range");
+        assert_eq!(res.status(), 206);
+        assert_eq!(res.into_string().unwrap(), "ex");
+
+        let res = raw_cl
+            .get(&file_url)
+            .set("Range", "bytes=1-2")
+            .set("If-Range", "\"mismatched\"")
+            .call()
+            .expect("failed to get file");
+        assert_eq!(res.status(), 200);
+        assert_eq!(res.header("Content-Range"), None);
+        assert_eq!(res.into_string().unwrap(), "text");
+
+        assert!(anon_prv_repo.has_blob(&file_expected.0.hash).is_err());
+        assert!(oidc_prv_repo
+            .has_blob(&file_expected.0.hash)
+            .expect("failed to query blob"));
+        assert!(anon_pub_repo
+            .has_blob(&file_expected.0.hash)
+            .expect("failed to query blob"));
+        assert!(!anon_pub_repo
+            .has_blob(
+                &Algorithms::default()
+                    .read_sync("other".as_bytes())
+                    .unwrap()
+                    .1
+            )
+            .expect("failed to query blob"));
+
+        // Contents already stored in the repository are linked instead of uploaded again
+        let next_tag = oidc_pub_repo.tag(&"0.2.0".parse().unwrap());
+        assert_eq!(
+            next_tag
+                .create_from_path_unsigned(pkg.path())
+                .expect("failed to create a tag and upload the tree"),
+            (prv_tag_created, prv_tree_created)
+        );
+        assert_eq!(
+            next_tag
+                .path(&file_name)
+                .get_string(5)
+                .expect("failed to get file"),
+            file_expected,
+        );
+
+        // Failed uploads do not occupy the node
+        let fresh_meta = Algorithms::default()
+            .read_sync("fresh".as_bytes())
+            .map(|(size, hash)| Meta {
+                hash,
+                size,
+                mime: APPLICATION_OCTET_STREAM,
+            })
+            .unwrap();
+        let fresh_tag = oidc_pub_repo.tag(&"0.3.0".parse().unwrap());
+        assert!(fresh_tag
+            .create(&TagEntry::Unsigned(TreeEntry {
+                meta: fresh_meta.clone(),
+                custom: Default::default(),
+                content: (),
+            }))
+            .expect("failed to create tag"));
+
+        // Tags are hidden from listings until sealed by uploading the whole tree
+        assert!(!fresh_tag.is_sealed().expect("failed to check tag"));
+        assert_eq!(fresh_tag.missing_nodes().expect("failed to check tag"), 1);
+        assert!(!oidc_pub_repo
+            .tags()
+            .expect("failed to get tags")
+            .contains(&"0.3.0".parse().unwrap()));
+        assert!(oidc_pub_repo
+            .query_tags(&TagQuery {
+                incomplete: true,
+                ..Default::default()
+            })
+            .collect::<Result<Vec<_>, _>>()
+            .expect("failed to query tags")
+            .contains(&"0.3.0".parse().unwrap()));
+
+        let fresh_root = fresh_tag.path(&TreePath::ROOT);
+        assert!(fresh_root
+            .create_from(&fresh_meta, "frehs".as_bytes())
+            .is_err());
+
+        // Nodes must match the entries declared by their parents
+        assert!(fresh_root
+            .create_bytes(&APPLICATION_OCTET_STREAM, "other")
+            .is_err());
+        assert!(fresh_root.create_bytes(&TEXT_PLAIN, "fresh").is_err());
+        assert!(fresh_tag
+            .path(&"fresh/undeclared".parse().unwrap())
+            .create_bytes(&APPLICATION_OCTET_STREAM, "fresh")
+            .is_err());
+        assert!(fresh_root
+            .create_from(&fresh_meta, "fresh".as_bytes())
+            .expect("failed to upload file"));
+        assert_eq!(
+            fresh_root.get_string(5).expect("failed to get file"),
+            (fresh_meta.clone(), "fresh".into()),
+        );
+        assert!(fresh_tag.is_sealed().expect("failed to check tag"));
+        assert_eq!(fresh_tag.missing_nodes().expect("failed to check tag"), 0);
+
+        // Nodes of signed tags are verified against the entry in the signature payload
+        let signed = |payload: &[u8]| {
+            TagEntry::<TreeEntry>::Signed(Box::new(Jws::Flattened(Flattened {
+                payload: Some(payload.to_vec().into()),
+                signature: Signature {
+                    protected: None,
+                    header: None,
+                    signature: vec![0].into(),
+                },
+            })))
+        };
+        let signed_repo = oidc_user.repository(&"test-repo-signed".parse().unwrap());
+        assert!(signed_repo
+            .create(&RepositoryConfig { public: false })
+            .expect("failed to create repository"));
+        let signed_tag = signed_repo.tag(&tag_name);
+        assert!(signed_tag.create(&signed(b"invalid")).is_err());
+        let payload = serde_json::to_vec(&TreeEntry {
+            meta: fresh_meta.clone(),
+            custom: Default::default(),
+            content: (),
+        })
+        .unwrap();
+        assert!(signed_tag
+            .create(&signed(&payload))
+            .expect("failed to create tag"));
+        let signed_root = signed_tag.path(&TreePath::ROOT);
+        assert!(signed_root
+            .create_bytes(&APPLICATION_OCTET_STREAM, "other")
+            .is_err());
+        assert!(signed_root
+            .create_from(&fresh_meta, "fresh".as_bytes())
+            .expect("failed to upload file"));
+        assert!(signed_tag.is_sealed().expect("failed to check tag"));
+
+        // Version requirements resolve to the highest matching tag
+        let req = ">=0.2".parse().unwrap();
+        assert_eq!(
+            anon_pub_repo.resolve(&req).expect("failed to resolve tag"),
+            TagResolution {
+                name: "0.3.0".parse().unwrap(),
+                entry: fresh_tag.get().expect("failed to get tag"),
+            }
+        );
+        assert!(anon_prv_repo.resolve(&req).is_err());
+        let pre_tag = oidc_prv_repo.tag(&"0.2.1-rc.1".parse().unwrap());
+        assert!(
+            pre_tag
+                .create_from_path_unsigned(pkg.path())
+                .expect("failed to create a tag and upload the tree")
+                .0
+        );
+        let req = "~0.2".parse().unwrap();
+        assert!(oidc_prv_repo.resolve(&req).is_err());
+        assert_eq!(
+            oidc_prv_repo
+                .resolve_pre_release(&req)
+                .expect("failed to resolve tag"),
+            TagResolution {
+                name: "0.2.1-rc.1".parse().unwrap(),
+                entry: pre_tag.get().expect("failed to get tag"),
+            }
+        );
+        assert_eq!(
+            oidc_prv_repo
+                .resolve(&"^0.1".parse().unwrap())
+                .expect("failed to resolve tag")
+                .name,
+            tag_name
+        );
+
+        // Tags are listed in semantic version order and paginated
+        let query_tags = |repo: &Repository<'_, _>, query| {
+            repo.query_tags(&query)
+                .collect::<Result<Vec<_>, _>>()
+                .expect("failed to query tags")
+        };
+        let names = |names: &[&str]| -> Vec<TagName> {
+            names.iter().map(|name| name.parse().unwrap()).collect()
+        };
+        assert_eq!(
+            query_tags(
+                &anon_pub_repo,
+                TagQuery {
+                    limit: NonZeroUsize::new(1),
+                    ..Default::default()
+                }
+            ),
+            names(&["0.1.0", "0.2.0", "0.3.0"])
+        );
+        assert_eq!(
+            query_tags(
+                &anon_pub_repo,
+                TagQuery {
+                    after: Some(tag_name.clone()),
+                    limit: NonZeroUsize::new(5),
+                    created_after: Some(0),
+                    ..Default::default()
+                }
+            ),
+            names(&["0.2.0", "0.3.0"])
+        );
+        assert_eq!(
+            query_tags(
+                &oidc_prv_repo,
+                TagQuery {
+                    pre: Some(true),
+                    ..Default::default()
+                }
+            ),
+            names(&["0.2.1-rc.1"])
+        );
+        assert_eq!(
+            query_tags(
+                &oidc_prv_repo,
+                TagQuery {
+                    pre: Some(false),
+                    limit: NonZeroUsize::new(1),
+                    ..Default::default()
+                }
+            ),
+            names(&["0.1.0"])
+        );
+        assert_eq!(
+            query_tags(
+                &oidc_prv_repo,
+                TagQuery {
+                    major: Some(1),
+                    ..Default::default()
+                }
+            ),
+            names(&[])
+        );
+        assert_eq!(
+            query_tags(
+                &oidc_prv_repo,
+                TagQuery {
+                    created_after: Some(u64::MAX),
+                    ..Default::default()
+                }
+            ),
+            names(&[])
+        );
+        assert!(anon_prv_repo
+            .query_tags(&Default::default())
+            .next()
+            .expect("no result")
+            .is_err());
+
+        // Yanked tags are not listed, but remain available
+        let fresh_tag_name = "0.3.0".parse().unwrap();
+        assert!(anon_pub_repo.tag(&fresh_tag_name).yank().is_err());
+        let yank = fresh_tag.yank().expect("failed to yank tag");
+        assert_eq!(yank.subject, SUBJECT);
+        let tags = oidc_pub_repo.tags().expect("failed to get tags");
+        assert!(tags.contains(&tag_name));
+        assert!(!tags.contains(&fresh_tag_name));
+        assert_eq!(
+            query_tags(
+                &anon_pub_repo,
+                TagQuery {
+                    yanked: true,
+                    limit: NonZeroUsize::new(2),
+                    ..Default::default()
+                }
+            ),
+            names(&["0.1.0", "0.2.0", "0.3.0"])
+        );
+        assert!(fresh_tag.get().is_ok());
+        assert_eq!(
+            anon_pub_repo
+                .resolve(&">=0.2".parse().unwrap())
+                .expect("failed to resolve tag")
+                .name,
+            "0.2.0".parse().unwrap()
+        );
+        assert_eq!(
+            anon_pub_repo
+                .tag(&fresh_tag_name)
+                .path(&TreePath::ROOT)
+                .get_string(5)
+                .expect("failed to get file"),
+            (fresh_meta.clone(), "fresh".into()),
+        );
+
+        // Deleted tags are gone
+        assert!(anon_pub_repo
+            .tag(&"0.2.0".parse().unwrap())
+            .delete()
+            .is_err());
+        next_tag.delete().expect("failed to delete tag");
+        assert!(next_tag.get().is_err());
+        assert!(next_tag.delete().is_err());
+        assert!(!oidc_pub_repo
+            .tags()
+            .expect("failed to get tags")
+            .contains(&"0.2.0".parse().unwrap()));
+        assert_eq!(
+            oidc_pub_tag
+                .path(&file_name)
+                .get_string(5)
+                .expect("failed to get file"),
+            file_expected,
+        );
+
+        // Repository configuration can be updated
+        assert!(anon_prv_repo.update(&pub_repo_conf).is_err());
+        assert!(oidc_user
+            .repository(&"test-repo-missing".parse().unwrap())
+            .update(&pub_repo_conf)
+            .is_err());
+        oidc_prv_repo
+            .update(&pub_repo_conf)
+            .expect("failed to update repository");
+        assert_eq!(
+            oidc_prv_repo.get().expect("failed to get repository"),
+            pub_repo_conf
+        );
+        assert!(anon_prv_repo.tags().is_ok());
+        oidc_prv_repo
+            .update(&prv_repo_conf)
+            .expect("failed to update repository");
+        assert!(anon_prv_repo.tags().is_err());
+
+        // Repositories are deleted recursively and users only once they own no repositories
+        let other_user = oidc_valid_cl.user(&format!("{user_name}other").parse().unwrap());
+        let other_repo = other_user.repository(&"test-repo-scratch".parse().unwrap());
+        assert!(other_repo
+            .create(&pub_repo_conf)
+            .expect("failed to create repository"));
+        let (tag_created, _) = other_repo
+            .tag(&tag_name)
+            .create_from_path_unsigned(pkg.path())
+            .expect("failed to create tag");
+        assert!(tag_created);
+
+        // Archives are committed atomically and only if they match the tag entry
+        let tree = Tree::from_path_sync(pkg.path()).expect("failed to read tree");
+        let archive_tag = other_repo.tag(&"0.2.0".parse().unwrap());
+        assert!(archive_tag.create_archive(&tree).is_err());
+        assert!(archive_tag
+            .create(&TagEntry::Unsigned(tree.root()))
+            .expect("failed to create tag"));
+        assert_eq!(
+            archive_tag
+                .create_archive(&tree)
+                .expect("failed to upload archive"),
+            Some(true)
+        );
+        assert!(archive_tag.is_sealed().expect("failed to check tag"));
+        assert!(archive_tag.create_archive(&tree).is_err());
+        let mismatch_tag = other_repo.tag(&"0.3.0".parse().unwrap());
+        assert!(mismatch_tag
+            .create(&TagEntry::Unsigned(TreeEntry {
+                meta: fresh_meta,
+                custom: Default::default(),
+                content: (),
+            }))
+            .expect("failed to create tag"));
+        assert!(mismatch_tag.create_archive(&tree).is_err());
+        assert_eq!(
+            mismatch_tag.missing_nodes().expect("failed to check tag"),
+            1
+        );
+
+        // Archives of sealed tags are verified against the tree while unpacking
+        assert!(mismatch_tag.create_from_path_unsigned(pkg.path()).is_err());
+
+        // Interrupted pushes are resumed by reusing present nodes
+        let resume_tag = other_repo.tag(&"0.4.0".parse().unwrap());
+        assert!(resume_tag
+            .create(&TagEntry::Unsigned(tree.root()))
+            .expect("failed to create tag"));
+        let TreeEntry { meta, content, .. } = tree.root();
+        let TreeContent::Directory(buf) = content else {
+            panic!("root is not a directory")
+        };
+        assert!(resume_tag
+            .path(&TreePath::ROOT)
+            .create_from(meta, buf.as_slice())
+            .expect("failed to create root node"));
+        let (tag_created, tree_created) = resume_tag
+            .create_from_path_unsigned(pkg.path())
+            .expect("failed to resume push");
+        assert!(!tag_created);
+        assert_eq!(
+            tree_created
+                .into_iter()
+                .filter(|(_, created)| !created)
+                .map(|(path, _)| path)
+                .collect::<Vec<_>>(),
+            vec![TreePath::ROOT]
+        );
+        assert!(resume_tag.is_sealed().expect("failed to check tag"));
+
+        // The asynchronous client exposes the same scopes and streams contents
+        let async_cl = blank_cl
+            .clone()
+            .token(oidc_token_valid)
+            .build_async()
+            .expect("failed to build asynchronous client");
+        let async_pub_repo = async_cl.user(&user_name).repository(&pub_repo_name);
+        assert_eq!(
+            async_pub_repo
+                .get()
+                .await
+                .expect("failed to get repository"),
+            oidc_pub_repo.get().expect("failed to get repository")
+        );
+        assert_eq!(
+            async_pub_repo.tags().await.expect("failed to get tags"),
+            vec![tag_name.clone()]
+        );
+        let async_pub_tag = async_pub_repo.tag(&tag_name);
+        assert_eq!(
+            async_pub_tag.get().await.expect("failed to get tag"),
+            oidc_pub_tag.get().expect("failed to get tag")
+        );
+        assert_eq!(
+            async_pub_tag
+                .path(&"test-dir-1/test-file.txt".parse().unwrap())
+                .get_string(u64::MAX)
+                .await
+                .expect("failed to get file")
+                .1,
+            "text"
+        );
+        let async_tag = async_cl
+            .user(&format!("{user_name}other").parse().unwrap())
+            .repository(&"test-repo-scratch".parse().unwrap())
+            .tag(&"0.5.0".parse().unwrap());
+        assert!(async_tag
+            .create(&TagEntry::Unsigned(tree.root()))
+            .await
+            .expect("failed to create tag"));
+        let TreeEntry { meta, content, .. } = tree.root();
+        let TreeContent::Directory(buf) = content else {
+            panic!("root is not a directory")
+        };
+        let root_entries = serde_json::from_slice::<TreeDirectory<TreeEntry>>(buf)
+            .expect("failed to decode root directory")
+            .len();
+        assert!(async_tag
+            .path(&TreePath::ROOT)
+            .create_bytes(&meta.mime, buf)
+            .await
+            .expect("failed to create root node"));
+        let file_path = "test-file.txt".parse().unwrap();
+        assert!(async_tag
+            .path(&file_path)
+            .create_from(
+                &tree[&file_path].meta,
+                async_std::fs::File::open(pkg.path().join("test-file.txt"))
+                    .await
+                    .unwrap(),
+            )
+            .await
+            .expect("failed to create file node"));
+        // Only nodes declared by uploaded directories count as missing
+        assert_eq!(
+            other_repo
+                .tag(&"0.5.0".parse().unwrap())
+                .missing_nodes()
+                .expect("failed to check tag"),
+            root_entries - 1
+        );
+        assert_eq!(
+            async_tag
+                .missing_nodes()
+                .await
+                .expect("failed to check tag"),
+            root_entries - 1
+        );
+        assert!(!async_tag.is_sealed().await.expect("failed to check tag"));
+        let req = "^0.1".parse().unwrap();
+        assert_eq!(
+            async_pub_repo
+                .resolve(&req)
+                .await
+                .expect("failed to resolve tag"),
+            anon_pub_repo.resolve(&req).expect("failed to resolve tag")
+        );
+        assert_eq!(
+            async_pub_repo
+                .query_tags(&Default::default())
+                .try_collect::<Vec<_>>()
+                .await
+                .expect("failed to query tags"),
+            anon_pub_repo
+                .query_tags(&Default::default())
+                .collect::<Result<Vec<_>, _>>()
+                .expect("failed to query tags")
+        );
+        let co = tempdir().expect("failed to create temporary checkout directory");
+        let written = async_pub_tag
+            .checkout(co.path())
+            .await
+            .expect("failed to check out tag");
+        assert!(written[&"test-dir-1/test-file.txt".parse().unwrap()]);
+        assert_eq!(
+            std::fs::read_to_string(co.path().join("test-dir-1/test-file.txt")).unwrap(),
+            "text"
+        );
+        let async_upload_tag = async_cl
+            .user(&format!("{user_name}other").parse().unwrap())
+            .repository(&"test-repo-scratch".parse().unwrap())
+            .tag(&"0.6.0".parse().unwrap());
+        let (created, nodes) = async_upload_tag
+            .create_from_path_unsigned(pkg.path())
+            .await
+            .expect("failed to upload tree");
+        assert!(created);
+        assert_eq!(
+            nodes.keys().collect::<Vec<_>>(),
+            tree.keys().collect::<Vec<_>>()
+        );
+        assert!(nodes.values().all(|created| *created));
+        assert!(async_upload_tag
+            .is_sealed()
+            .await
+            .expect("failed to check tag"));
+
+        let dl = tempdir().expect("failed to create temporary download directory");
+        let dest = dl.path().join("async");
+        let unpacked = async_upload_tag
+            .download_archive(&dest, ArchiveFormat::TarZstd)
+            .await
+            .expect("failed to download archive");
+        assert_eq!(
+            unpacked.keys().collect::<Vec<_>>(),
+            tree.keys().collect::<Vec<_>>()
+        );
+        assert_eq!(
+            std::fs::read_to_string(dest.join("test-dir-1/test-subdir-2/test-file")).unwrap(),
+            "test"
+        );
+        assert!(async_upload_tag
+            .download_archive(&dest, ArchiveFormat::Tar)
+            .await
+            .is_err());
+        for format in [
+            ArchiveFormat::Tar,
+            ArchiveFormat::TarGzip,
+            ArchiveFormat::TarZstd,
+        ] {
+            let dest = dl.path().join(format.as_str());
+            let unpacked = archive_tag
+                .download_archive(&dest, format)
+                .expect("failed to download archive");
+            assert_eq!(
+                unpacked.keys().collect::<Vec<_>>(),
+                tree.keys().collect::<Vec<_>>()
+            );
+            assert_eq!(
+                std::fs::read_to_string(dest.join("test-file.txt")).unwrap(),
+                "text"
+            );
+            assert_eq!(
+                std::fs::read_to_string(dest.join("test-dir-1/test-subdir-2/test-file")).unwrap(),
+                "test"
+            );
+            assert!(dest.join("test-dir-1/test-subdir-1").is_dir());
+            assert!(archive_tag.download_archive(&dest, format).is_err());
+        }
+        let dest = dl.path().join("mismatch");
+        assert!(mismatch_tag
+            .download_archive(&dest, ArchiveFormat::Tar)
+            .is_err());
+        assert!(!dest.exists());
+        assert!(other_user.delete().is_err());
+        let other_repo_cx: RepositoryContext = format!("{user_name}other/test-repo-scratch")
+            .parse()
+            .unwrap();
+        assert!(anon_cl
+            .repository(&other_repo_cx)
+            .delete(&other_repo_cx)
+            .is_err());
+        assert!(other_repo
+            .delete(&format!("{user_name}other/test-repo-other").parse().unwrap())
+            .is_err());
+        other_repo
+            .delete(&other_repo_cx)
+            .expect("failed to delete repository");
+        assert!(other_repo.get().is_err());
+        assert!(other_repo.delete(&other_repo_cx).is_err());
+        other_user.delete().expect("failed to delete user");
+        assert!(other_user.get().is_err());
+        assert!(other_user.delete().is_err());
     });
     assert!(matches!(cl.await.await, ()));
 
@@ -528,4 +1608,967 @@ async fn app() {
     // Stop server
     assert_eq!(srv_tx.send(()), Ok(()));
     assert!(matches!(join!(oidc, srv), ((), ())));
+
+    // Verify integrity of the store and inject problems
+    let store_path = store.path();
+    let store = Store::open(store_path).await.expect("failed to open store");
+    let report = store.fsck(false).await.expect("failed to check store");
+    assert!(report.checked > 0);
+    assert!(report.findings.is_empty(), "{report:?}");
+
+    // Outdated layouts are reported, but only migrated on repair
+    let version_path = store_path.join("version");
+    std::fs::remove_file(&version_path).expect("failed to remove layout version");
+    let report = store.fsck(false).await.expect("failed to check store");
+    assert!(
+        matches!(
+            &report.findings[..],
+            [Finding {
+                problem: Problem::OutdatedLayout { version: 0 },
+                repaired: false,
+                ..
+            }]
+        ),
+        "{report:?}"
+    );
+    assert!(!version_path.exists());
+    let report = store.fsck(true).await.expect("failed to check store");
+    assert!(
+        matches!(
+            &report.findings[..],
+            [Finding {
+                problem: Problem::OutdatedLayout { version: 0 },
+                repaired: true,
+                ..
+            }]
+        ),
+        "{report:?}"
+    );
+    assert!(version_path.exists());
+
+    // Garbage collection migrates outdated layouts, unless it is a dry run
+    std::fs::remove_file(&version_path).expect("failed to remove layout version");
+    for dry_run in [true, false] {
+        let report = store.gc(dry_run).await.expect("failed to collect garbage");
+        assert!(
+            matches!(
+                &report.findings[..],
+                [Finding {
+                    problem: Problem::OutdatedLayout { version: 0 },
+                    repaired,
+                    ..
+                }] if *repaired == !dry_run
+            ),
+            "{report:?}"
+        );
+        assert_eq!(version_path.exists(), !dry_run);
+    }
+
+    let report = store.gc(false).await.expect("failed to collect garbage");
+    assert!(report.findings.is_empty(), "{report:?}");
+    assert!(matches!(
+        &store.audit().await.expect("failed to read audit trail")[..],
+        [
+            AuditEvent {
+                action: AuditAction::YankTag,
+                target: yanked,
+                subject: yanker,
+                ..
+            },
+            AuditEvent {
+                action: AuditAction::DeleteTag,
+                target: deleted,
+                subject: deleter,
+                ..
+            },
+            AuditEvent {
+                action: AuditAction::UpdateRepository,
+                target: published,
+                ..
+            },
+            AuditEvent {
+                action: AuditAction::UpdateRepository,
+                target: unpublished,
+                ..
+            },
+            AuditEvent {
+                action: AuditAction::DeleteRepository,
+                target: deleted_repo,
+                ..
+            },
+            AuditEvent {
+                action: AuditAction::DeleteUser,
+                target: deleted_user,
+                ..
+            },
+        ] if yanked == "testuser/test-repo-public:0.3.0"
+            && deleted == "testuser/test-repo-public:0.2.0"
+            && yanker == SUBJECT
+            && deleter == SUBJECT
+            && published == "testuser/test-repo-private"
+            && unpublished == "testuser/test-repo-private"
+            && deleted_repo == "testuserother/test-repo-scratch"
+            && deleted_user == "testuserother"
+    ));
+
+    let blobs = store_path.join("users/testuser/repos/test-repo-public/blobs");
+    let blob = std::fs::read_dir(&blobs)
+        .expect("failed to read blobs")
+        .map(|entry| entry.unwrap().path())
+        .find(|path| path.join("content").exists())
+        .expect("no blobs stored");
+    std::fs::write(blob.join("content"), "corrupt").unwrap();
+    std::fs::create_dir(store_path.join(".tmp/staged")).unwrap();
+    std::fs::create_dir(blobs.join("unreferenced")).unwrap();
+
+    let report = store.fsck(true).await.expect("failed to check store");
+    assert!(!report.is_ok());
+    assert!(
+        matches!(
+            &report.findings[..],
+            [
+                Finding {
+                    problem: Problem::SizeMismatch { .. } | Problem::DigestMismatch { .. },
+                    repaired: false,
+                    ..
+                },
+                Finding {
+                    problem: Problem::MissingMeta,
+                    repaired: true,
+                    ..
+                }
+            ]
+        ),
+        "{report:?}"
+    );
+    std::fs::create_dir(blobs.join("unreferenced")).unwrap();
+
+    let report = store.gc(true).await.expect("failed to collect garbage");
+    assert_eq!(report.findings.len(), 2, "{report:?}");
+    assert!(report.findings.iter().all(|f| !f.repaired));
+    let report = store.gc(false).await.expect("failed to collect garbage");
+    assert_eq!(report.findings.len(), 2, "{report:?}");
+    assert!(report.is_ok());
+    assert!(!store_path.join(".tmp/staged").exists());
+    assert!(!blobs.join("unreferenced").exists());
+}
+
+fn meta(buf: &[u8], mime: Mime) -> Meta {
+    Algorithms::default()
+        .read_sync(buf)
+        .map(|(size, hash)| Meta { hash, size, mime })
+        .unwrap()
+}
+
+/// Exercises the store in the storage backend configured by `conf`.
+/// Checks, that directories are moved onto missing or empty directories only.
+async fn check_rename_dir(conf: StorageConfig) {
+    let backend = conf.open().await.expect("failed to open backend");
+    for path in ["rename", "rename/src", "rename/empty", "rename/full"] {
+        backend
+            .create_dir(Utf8Path::new(path))
+            .await
+            .expect("failed to create directory");
+    }
+    for path in ["rename/src/file", "rename/full/file"] {
+        backend
+            .write(Utf8Path::new(path), b"text".to_vec())
+            .await
+            .expect("failed to write file");
+    }
+
+    assert_eq!(
+        backend
+            .rename_dir(Utf8Path::new("rename/src"), Utf8Path::new("rename/full"))
+            .await
+            .map_err(|e| e.kind()),
+        Err(std::io::ErrorKind::DirectoryNotEmpty)
+    );
+    backend
+        .rename_dir(Utf8Path::new("rename/src"), Utf8Path::new("rename/empty"))
+        .await
+        .expect("failed to move directory onto an empty one");
+    backend
+        .rename_dir(Utf8Path::new("rename/empty"), Utf8Path::new("rename/moved"))
+        .await
+        .expect("failed to move directory");
+    assert_eq!(
+        backend
+            .read_dir(Utf8Path::new("rename"))
+            .await
+            .expect("failed to read directory"),
+        vec!["full", "moved"]
+    );
+    assert_eq!(
+        backend
+            .read_dir(Utf8Path::new("rename/moved"))
+            .await
+            .expect("failed to read directory"),
+        vec!["file"]
+    );
+    backend
+        .remove_dir_all(Utf8Path::new("rename"))
+        .await
+        .expect("failed to remove directory");
+}
+
+async fn check_store(conf: impl Into<StorageConfig>) {
+    let conf = conf.into();
+    check_rename_dir(conf.clone()).await;
+
+    let store = Store::open(conf).await.expect("failed to open store");
+    store.migrate().await.expect("failed to migrate store");
+
+    let user_cx: UserContext = "testuser".parse().unwrap();
+    let user_rec = UserRecord {
+        subject: "test|subject".into(),
+    };
+    let user_meta = meta(&serde_json::to_vec(&user_rec).unwrap(), APPLICATION_JSON);
+    let user = store
+        .create_user(&user_cx, user_meta.clone(), &user_rec)
+        .await
+        .expect("failed to create user");
+    assert!(matches!(
+        store.create_user(&user_cx, user_meta, &user_rec).await,
+        Err(CreateError::Occupied)
+    ));
+
+    let repo_name: RepositoryName = "test-repo".parse().unwrap();
+    let repo_conf = RepositoryConfig { public: false };
+    let repo = user
+        .create_repository(
+            &repo_name,
+            meta(&serde_json::to_vec(&repo_conf).unwrap(), APPLICATION_JSON),
+            &repo_conf,
+        )
+        .await
+        .expect("failed to create repository");
+    let repo_conf = RepositoryConfig { public: true };
+    let repo_meta = meta(&serde_json::to_vec(&repo_conf).unwrap(), APPLICATION_JSON);
+    assert!(matches!(
+        repo.update(&repo_meta, &repo_conf, |_| false).await,
+        Err(CreateError::PreconditionFailed)
+    ));
+    repo.update(&repo_meta, &repo_conf, |current| current != &repo_meta)
+        .await
+        .expect("failed to update repository");
+    assert_eq!(
+        repo.get_json().await.expect("failed to get repository"),
+        repo_conf
+    );
+    assert_eq!(
+        repo.get_meta().await.expect("failed to get repository"),
+        repo_meta
+    );
+    assert_eq!(
+        repo.get_bytes_replaced()
+            .await
+            .expect("failed to get repository"),
+        (repo_meta, serde_json::to_vec(&repo_conf).unwrap())
+    );
+    assert!(matches!(user.delete().await, Err(DeleteError::NotEmpty)));
+    assert!(store.user(&user_cx).get_meta().await.is_ok());
+
+    let file_path: TreePath = "test-file.txt".parse().unwrap();
+    let file_meta = meta(b"text", APPLICATION_OCTET_STREAM);
+    // Large enough to be uploaded in multiple parts to object stores
+    let large_path: TreePath = "test-large.bin".parse().unwrap();
+    let large_buf: Vec<u8> = (0..9 * 1024 * 1024).map(|i| (i % 251) as u8).collect();
+    let large_meta = meta(&large_buf, APPLICATION_OCTET_STREAM);
+    let dir: TreeDirectory<TreeEntry> = [
+        (
+            "test-file.txt".parse().unwrap(),
+            TreeEntry {
+                meta: file_meta.clone(),
+                custom: Default::default(),
+                content: (),
+            },
+        ),
+        (
+            "test-large.bin".parse().unwrap(),
+            TreeEntry {
+                meta: large_meta.clone(),
+                custom: Default::default(),
+                content: (),
+            },
+        ),
+    ]
+    .into_iter()
+    .collect();
+    let dir_meta = meta(
+        &serde_json::to_vec(&dir).unwrap(),
+        TreeDirectory::<()>::TYPE.parse().unwrap(),
+    );
+    let tag_name: TagName = "0.1.0".parse().unwrap();
+    let tag_entry = TagEntry::Unsigned(TreeEntry {
+        meta: dir_meta.clone(),
+        custom: Default::default(),
+        content: (),
+    });
+    let tag = repo
+        .create_tag(
+            &tag_name,
+            meta(&serde_json::to_vec(&tag_entry).unwrap(), APPLICATION_JSON),
+            &tag_entry,
+        )
+        .await
+        .expect("failed to create tag");
+    _ = tag
+        .create_directory_node(&TreePath::ROOT, dir_meta, &dir)
+        .await
+        .expect("failed to create directory node");
+    let file = tag
+        .create_file_node(&file_path, file_meta.clone(), b"text".as_slice())
+        .await
+        .expect("failed to create file node");
+    assert!(matches!(
+        tag.create_file_node(&file_path, file_meta.clone(), b"text".as_slice())
+            .await,
+        Err(CreateError::Occupied)
+    ));
+
+    let (meta, mut rdr) = file.get().await.expect("failed to get file node");
+    let mut buf = String::new();
+    assert_eq!(rdr.read_to_string(&mut buf).await.unwrap(), 4);
+    assert_eq!((meta, buf), (file_meta, "text".into()));
+
+    let large_file = tag
+        .create_file_node(&large_path, large_meta.clone(), large_buf.as_slice())
+        .await
+        .expect("failed to create large file node");
+    let (meta, mut rdr) = large_file.get().await.expect("failed to get file node");
+    assert_eq!(meta, large_meta);
+    let mut buf = vec![];
+    assert_eq!(rdr.read_to_end(&mut buf).await.unwrap(), large_buf.len());
+    assert!(buf == large_buf);
+    for pos in [8 * 1024 * 1024 - 2, 3, large_buf.len() as u64 - 1] {
+        assert_eq!(rdr.seek(SeekFrom::Start(pos)).await.unwrap(), pos);
+        let mut buf = [0; 2];
+        let n = rdr.read(&mut buf).await.unwrap();
+        assert!(n > 0);
+        assert_eq!(buf[..n], large_buf[pos as usize..][..n]);
+    }
+    assert_eq!(
+        repo.tags().await.expect("failed to get tags"),
+        vec![tag_name]
+    );
+
+    let report = store.fsck(false).await.expect("failed to check store");
+    assert!(report.checked > 0);
+    assert!(report.findings.is_empty(), "{report:?}");
+    let report = store.gc(false).await.expect("failed to collect garbage");
+    assert!(report.findings.is_empty(), "{report:?}");
+}
+
+#[async_std::test]
+async fn storage_backends() {
+    check_store(StorageConfig::Memory).await;
+    let dir = tempdir().expect("failed to create temporary store directory");
+    check_store(dir.path()).await;
+
+    // Minimal stand-in for an S3-compatible object store
+    const BUCKET: &str = "test-bucket";
+    let s3_lis = TcpListener::bind((Ipv4Addr::LOCALHOST, 0))
+        .await
+        .expect("failed to bind to address");
+    let s3_addr = s3_lis.local_addr().unwrap();
+    let objects = Arc::new(Mutex::new(BTreeMap::<String, Vec<u8>>::new()));
+    let uploads = Arc::new(Mutex::new(
+        HashMap::<String, BTreeMap<usize, Vec<u8>>>::new(),
+    ));
+    let upload_ids = Arc::new(AtomicUsize::new(0));
+    let started_uploads = upload_ids.clone();
+    _ = spawn(async move {
+        s3_lis
+            .incoming()
+            .for_each_concurrent(None, |stream| async {
+                let objects = &objects;
+                let uploads = &uploads;
+                let upload_ids = &upload_ids;
+
+                if let Err(e) = async_h1::accept(
+                    stream.expect("failed to initialize stream"),
+                    |mut req| async move {
+                        assert!(req
+                            .header("authorization")
+                            .expect("request is not signed")
+                            .as_str()
+                            .starts_with("AWS4-HMAC-SHA256 Credential=test-key/"));
+                        let key = req
+                            .url()
+                            .path()
+                            .strip_prefix(&format!("/{BUCKET}/"))
+                            .expect("request does not address the bucket")
+                            .to_string();
+                        let query: HashMap<_, _> = req.url().query_pairs().into_owned().collect();
+                        let body = req.body_bytes().await?;
+                        let mut objects = objects.lock().unwrap();
+                        let mut uploads = uploads.lock().unwrap();
+                        let mut res = Response::new(StatusCode::Ok);
+                        match req.method() {
+                            Method::Post if query.contains_key("uploads") => {
+                                let id = upload_ids.fetch_add(1, Ordering::SeqCst).to_string();
+                                res.set_body(format!(
+                                    "<InitiateMultipartUploadResult><UploadId>{id}</UploadId></InitiateMultipartUploadResult>"
+                                ));
+                                _ = uploads.insert(id, BTreeMap::new());
+                            }
+                            Method::Put if query.contains_key("partNumber") => {
+                                let number = query["partNumber"].parse().unwrap();
+                                _ = uploads
+                                    .get_mut(&query["uploadId"])
+                                    .expect("upload does not exist")
+                                    .insert(number, body);
+                                res.insert_header("ETag", format!("\"{number}\""));
+                            }
+                            Method::Post if query.contains_key("uploadId") => {
+                                let parts = uploads
+                                    .remove(&query["uploadId"])
+                                    .expect("upload does not exist");
+                                assert_eq!(
+                                    String::from_utf8(body).unwrap(),
+                                    format!(
+                                        "<CompleteMultipartUpload>{}</CompleteMultipartUpload>",
+                                        parts
+                                            .keys()
+                                            .map(|n| format!("<Part><PartNumber>{n}</PartNumber><ETag>&quot;{n}&quot;</ETag></Part>"))
+                                            .collect::<String>()
+                                    )
+                                );
+                                if req.header("if-none-match").is_some() && objects.contains_key(&key) {
+                                    res.set_status(StatusCode::PreconditionFailed);
+                                } else {
+                                    _ = objects.insert(key, parts.into_values().flatten().collect());
+                                }
+                            }
+                            Method::Delete if query.contains_key("uploadId") => {
+                                _ = uploads.remove(&query["uploadId"]);
+                                res.set_status(StatusCode::NoContent);
+                            }
+                            Method::Get if query.contains_key("list-type") => {
+                                let prefix = &query["prefix"];
+                                let mut contents = vec![];
+                                let mut prefixes = vec![];
+                                for key in objects.keys().filter(|key| key.starts_with(prefix)) {
+                                    match query
+                                        .get("delimiter")
+                                        .and_then(|d| key[prefix.len()..].find(d.as_str()))
+                                    {
+                                        Some(i) => prefixes.push(&key[..prefix.len() + i + 1]),
+                                        None => contents.push(key),
+                                    }
+                                }
+                                prefixes.dedup();
+                                res.set_body(format!(
+                                    "<ListBucketResult><Prefix>{prefix}</Prefix>{}{}<IsTruncated>false</IsTruncated></ListBucketResult>",
+                                    contents
+                                        .iter()
+                                        .map(|key| format!("<Contents><Key>{key}</Key></Contents>"))
+                                        .collect::<String>(),
+                                    prefixes
+                                        .iter()
+                                        .map(|p| format!("<CommonPrefixes><Prefix>{p}</Prefix></CommonPrefixes>"))
+                                        .collect::<String>(),
+                                ));
+                            }
+                            method @ (Method::Get | Method::Head) => match objects.get(&key) {
+                                Some(buf) if method == Method::Get => match req.header("range") {
+                                    Some(range) => {
+                                        let start: usize = range
+                                            .as_str()
+                                            .strip_prefix("bytes=")
+                                            .and_then(|range| range.strip_suffix('-'))
+                                            .and_then(|start| start.parse().ok())
+                                            .expect("unsupported range requested");
+                                        res.set_status(StatusCode::PartialContent);
+                                        res.set_body(buf[start..].to_vec());
+                                    }
+                                    None => res.set_body(buf.clone()),
+                                },
+                                Some(_) => {}
+                                None => res.set_status(StatusCode::NotFound),
+                            },
+                            Method::Put => {
+                                let body = match req.header("x-amz-copy-source") {
+                                    Some(src) => objects
+                                        .get(src.as_str().strip_prefix(&format!("/{BUCKET}/")).unwrap())
+                                        .cloned()
+                                        .expect("copy source does not exist"),
+                                    None => body,
+                                };
+                                if req.header("if-none-match").is_some() && objects.contains_key(&key) {
+                                    res.set_status(StatusCode::PreconditionFailed);
+                                } else {
+                                    _ = objects.insert(key, body);
+                                }
+                            }
+                            Method::Delete => {
+                                _ = objects.remove(&key);
+                                res.set_status(StatusCode::NoContent);
+                            }
+                            m => panic!("Unsupported method requested: `{m}`"),
+                        }
+                        Ok(res)
+                    },
+                )
+                .await
+                {
+                    // Responses are aborted by readers of objects, which are seeked
+                    assert!(
+                        matches!(
+                            e.downcast_ref::<std::io::Error>().map(|e| e.kind()),
+                            Some(std::io::ErrorKind::BrokenPipe | std::io::ErrorKind::ConnectionReset)
+                        ),
+                        "failed to handle S3 connection: {e}"
+                    );
+                }
+            })
+            .await
+    });
+    check_store(S3Config {
+        endpoint: format!("http://{s3_addr}").parse().unwrap(),
+        region: "us-east-1".into(),
+        bucket: BUCKET.into(),
+        access_key: "test-key".into(),
+        secret_key: "test-secret".into(),
+    })
+    .await;
+    assert!(started_uploads.load(Ordering::SeqCst) > 0);
+}
+
+/// Spawns a stub server responding to every request by `respond` and returns its URL.
+///
+/// Failures of connections are ignored, since clients may close connections without reading
+/// responses exceeding their limits or while still sending requests.
+async fn stub_server(respond: impl 'static + Clone + Send + Sync + Fn(Request) -> Response) -> Url {
+    let lis = TcpListener::bind((Ipv4Addr::LOCALHOST, 0))
+        .await
+        .expect("failed to bind to address");
+    let addr = lis.local_addr().unwrap();
+    _ = spawn(async move {
+        lis.incoming()
+            .for_each_concurrent(None, |stream| {
+                let respond = respond.clone();
+                async move {
+                    _ = async_h1::accept(stream.expect("failed to initialize stream"), |req| {
+                        let res = respond(req);
+                        async move { Ok(res) }
+                    })
+                    .await;
+                }
+            })
+            .await
+    });
+    format!("http://{addr}").parse().unwrap()
+}
+
+/// Returns a successful response of a stub server with `body` of media type `mime`.
+fn stub_response(mime: &str, body: Vec<u8>) -> Response {
+    let (_, hash) = Algorithms::default().read_sync(&body[..]).unwrap();
+    let mut res = Response::new(StatusCode::Ok);
+    res.insert_header("Content-Type", mime);
+    res.insert_header("Content-Digest", hash.to_string());
+    res.set_body(body);
+    res
+}
+
+#[async_std::test]
+async fn client_retries() {
+    // Server failing two of every three requests with a transient error
+    let attempts = Arc::new(AtomicUsize::new(0));
+    let srv_attempts = attempts.clone();
+    let url = stub_server(move |_| {
+        if srv_attempts.fetch_add(1, Ordering::SeqCst) % 3 < 2 {
+            let mut res = Response::new(StatusCode::ServiceUnavailable);
+            res.insert_header("Retry-After", "0");
+            return res;
+        }
+        let body = serde_json::to_vec(&UserRecord {
+            subject: "test-subject".into(),
+        })
+        .unwrap();
+        stub_response("application/json", body)
+    })
+    .await;
+
+    let user: UserContext = "testuser".parse().unwrap();
+    let get_user = |retry| {
+        let cl = Client::builder(url.clone())
+            .retry_policy(retry)
+            .timeout_connect(Duration::from_secs(5))
+            .timeout_read(Duration::from_secs(5))
+            .build()
+            .expect("failed to build client");
+        let user = user.clone();
+        spawn_blocking(move || cl.user(&user).get())
+    };
+
+    let record = get_user(RetryPolicy {
+        max_retries: 2,
+        ..Default::default()
+    })
+    .await
+    .expect("failed to get user with retries");
+    assert_eq!(record.subject, "test-subject");
+    assert_eq!(attempts.load(Ordering::SeqCst), 3);
+
+    assert!(get_user(RetryPolicy::never()).await.is_err());
+    assert_eq!(attempts.load(Ordering::SeqCst), 4);
+
+    // Asynchronous clients share the retry policy of the builder
+    let cl = Client::builder(url.clone())
+        .retry_policy(RetryPolicy {
+            max_retries: 2,
+            ..Default::default()
+        })
+        .build_async()
+        .expect("failed to build asynchronous client");
+    assert_eq!(
+        cl.user(&user)
+            .get()
+            .await
+            .expect("failed to get user with retries")
+            .subject,
+        "test-subject"
+    );
+    assert_eq!(attempts.load(Ordering::SeqCst), 6);
+}
+
+#[async_std::test]
+async fn client_retried_conflicts() {
+    // Server creating nodes, but failing the first upload of every node with a transient error
+    // asking for a delay of an hour, and reporting a conflict for further uploads. Node `a` is
+    // stored with the uploaded contents, node `b` with different ones.
+    let created = Arc::new(Mutex::new(HashMap::new()));
+    let srv_created = created.clone();
+    let url = stub_server(move |req| {
+        let path = req.url().path().to_string();
+        let mut created = srv_created.lock().unwrap();
+        match (req.method(), created.get(&path)) {
+            (Method::Put, None) => {
+                _ = created.insert(path, ());
+                let mut res = Response::new(StatusCode::ServiceUnavailable);
+                res.insert_header("Retry-After", "3600");
+                res
+            }
+            (Method::Put, Some(_)) => Response::new(StatusCode::Conflict),
+            (Method::Head, Some(_)) if path.ends_with("/a") => {
+                stub_response(APPLICATION_OCTET_STREAM.as_ref(), b"a".to_vec())
+            }
+            (Method::Head, Some(_)) => {
+                stub_response(APPLICATION_OCTET_STREAM.as_ref(), b"other".to_vec())
+            }
+            _ => Response::new(StatusCode::NotFound),
+        }
+    })
+    .await;
+
+    // Delays requested by the server are capped by the maximum backoff of the policy
+    let cl = Client::builder(url)
+        .token("test-token")
+        .retry_policy(RetryPolicy {
+            max_retries: 1,
+            max_backoff: Duration::from_millis(10),
+            ..Default::default()
+        })
+        .build()
+        .expect("failed to build client");
+    let tag = "testuser/test-repo:0.1.0".parse().unwrap();
+    let (a, b) = spawn_blocking(move || {
+        let tag = cl.tag(&tag);
+        (
+            tag.path(&"a".parse().unwrap())
+                .create_bytes(&APPLICATION_OCTET_STREAM, "a"),
+            tag.path(&"b".parse().unwrap())
+                .create_bytes(&APPLICATION_OCTET_STREAM, "b"),
+        )
+    })
+    .await;
+    assert!(a.expect("failed to create node"));
+    assert!(b.is_err());
+    assert_eq!(created.lock().unwrap().len(), 2);
+}
+
+#[async_std::test]
+async fn client_limits() {
+    // Server responding to every request with a large user record
+    let body = serde_json::to_vec(&UserRecord {
+        subject: "a".repeat(1024 * 1024),
+    })
+    .unwrap();
+    let size = body.len() as u64;
+    let url = stub_server(move |_| stub_response("application/json", body.clone())).await;
+
+    let user: UserContext = "testuser".parse().unwrap();
+    let repo: RepositoryContext = "testuser/test-repo".parse().unwrap();
+    let cl = |limits| {
+        Client::builder(url.clone())
+            .limits(limits)
+            .build()
+            .expect("failed to build client")
+    };
+
+    let default_cl = cl(Limits::default());
+    let err = {
+        let user = user.clone();
+        spawn_blocking(move || default_cl.user(&user).get())
+            .await
+            .expect_err("user record exceeding the limit was accepted")
+    };
+    assert_eq!(
+        err.downcast_ref::<LimitExceeded>(),
+        Some(&LimitExceeded {
+            size,
+            limit: Limits::default().user,
+        })
+    );
+
+    let default_cl = cl(Limits::default());
+    let err = spawn_blocking(move || default_cl.repository(&repo).get())
+        .await
+        .expect_err("repository config exceeding the limit was accepted");
+    assert_eq!(
+        err.downcast_ref::<LimitExceeded>(),
+        Some(&LimitExceeded {
+            size,
+            limit: Limits::default().repository,
+        })
+    );
+
+    let raised_cl = cl(Limits {
+        user: size,
+        ..Default::default()
+    });
+    let record = spawn_blocking(move || raised_cl.user(&user).get())
+        .await
+        .expect("failed to get user within the limit");
+    assert_eq!(record.subject.len(), 1024 * 1024);
+}
+
+#[async_std::test]
+async fn client_media_types() {
+    // Server responding to every request with a user record of media type `text/plain`,
+    // recording the `Accept` headers of requests
+    let accepted = Arc::new(Mutex::new(vec![]));
+    let srv_accepted = accepted.clone();
+    let url = stub_server(move |req| {
+        srv_accepted
+            .lock()
+            .unwrap()
+            .push(req.header("Accept").map(|v| v.as_str().to_string()));
+        let body = serde_json::to_vec(&UserRecord {
+            subject: "test-subject".into(),
+        })
+        .unwrap();
+        stub_response("text/plain", body)
+    })
+    .await;
+
+    let cl = Client::builder(url)
+        .build()
+        .expect("failed to build client");
+    let (tag_err, user_err) = spawn_blocking(move || {
+        (
+            cl.tag(&"testuser/test-repo:0.1.0".parse().unwrap())
+                .get()
+                .expect_err("tag entry of unexpected media type was accepted"),
+            cl.user(&"testuser".parse().unwrap())
+                .get()
+                .expect_err("user record of unexpected media type was accepted"),
+        )
+    })
+    .await;
+    assert_eq!(
+        tag_err.downcast_ref::<UnexpectedMediaType>(),
+        Some(&UnexpectedMediaType {
+            mime: TEXT_PLAIN,
+            expected: vec![TreeEntry::<()>::TYPE.into(), Jws::TYPE.into()],
+        })
+    );
+    assert_eq!(
+        user_err.downcast_ref::<UnexpectedMediaType>(),
+        Some(&UnexpectedMediaType {
+            mime: TEXT_PLAIN,
+            expected: vec![APPLICATION_JSON.to_string()],
+        })
+    );
+    assert_eq!(
+        *accepted.lock().unwrap(),
+        vec![
+            Some(format!("{}, {}", TreeEntry::<()>::TYPE, Jws::TYPE)),
+            Some(APPLICATION_JSON.to_string()),
+        ]
+    );
+}
+
+#[async_std::test]
+async fn client_archive_fallback() {
+    // Server lacking the archive endpoint, which knows only tag `0.1.0` and responds to archive
+    // uploads with 405 for tag `0.3.0` and 404 otherwise
+    let url = stub_server(|req| {
+        let path = req.url().path();
+        match req.method() {
+            Method::Head if path.ends_with("/_tag/0.1.0") => {
+                stub_response(TreeEntry::<()>::TYPE, b"{}".to_vec())
+            }
+            Method::Put if path.ends_with("/_tag/0.3.0/archive") => {
+                Response::new(StatusCode::MethodNotAllowed)
+            }
+            _ => Response::new(StatusCode::NotFound),
+        }
+    })
+    .await;
+
+    let pkg = tempdir().expect("failed to create temporary package directory");
+    std::fs::write(pkg.path().join("test-file.txt"), "text").unwrap();
+    let cl = Client::builder(url)
+        .token("test-token")
+        .build()
+        .expect("failed to build client");
+    let create_archive = |tag: &str| {
+        let cl = cl.clone();
+        let tag = format!("testuser/test-repo:{tag}").parse().unwrap();
+        let tree = Tree::from_path_sync(pkg.path()).expect("failed to read tree");
+        spawn_blocking(move || cl.tag(&tag).create_archive(&tree))
+    };
+
+    assert_eq!(
+        create_archive("0.1.0")
+            .await
+            .expect("failed to check archive support"),
+        None
+    );
+    assert!(create_archive("0.2.0").await.is_err());
+    assert_eq!(
+        create_archive("0.3.0")
+            .await
+            .expect("failed to check archive support"),
+        None
+    );
+}
+
+/// Encodes `records` as the contents of a PAX extended header.
+fn pax_records(records: &[(&str, &[u8])]) -> Vec<u8> {
+    let mut buf = vec![];
+    for (key, value) in records {
+        // The length prefix of each record includes its own decimal digits.
+        let rest = key.len() + value.len() + 3;
+        let mut len = rest;
+        while rest + len.to_string().len() != len {
+            len = rest + len.to_string().len();
+        }
+        buf.extend_from_slice(format!("{len} {key}=").as_bytes());
+        buf.extend_from_slice(value);
+        buf.push(b'\n');
+    }
+    buf
+}
+
+#[async_std::test]
+async fn client_archive_limits() {
+    // Server holding a tree with a file of a long name, which responds to archive downloads of
+    // tag `0.1.0` with a valid archive, of tag `0.2.0` with an oversized PAX extended header and
+    // of tag `0.3.0` with an oversized directory member
+    let pkg = tempdir().expect("failed to create temporary package directory");
+    let name = "a".repeat(120);
+    std::fs::write(pkg.path().join(&name), "text").unwrap();
+    let tree = Tree::from_path_sync(pkg.path()).expect("failed to read tree");
+    let root = tree.root().meta.clone();
+    let TreeContent::Directory(ref listing) = tree.root().content else {
+        panic!("root is not a directory")
+    };
+    let listing = listing.clone();
+    let append_pax = |archive: &mut tar::Builder<Vec<u8>>, records: &[(&str, &[u8])]| {
+        let buf = pax_records(records);
+        let mut header = tar::Header::new_ustar();
+        header.set_entry_type(tar::EntryType::XHeader);
+        header.set_size(buf.len() as _);
+        header.set_cksum();
+        archive.append(&header, buf.as_slice()).unwrap();
+    };
+    let archive = |pax_padding: usize, dir_size: usize| {
+        let mut archive = tar::Builder::new(vec![]);
+        append_pax(
+            &mut archive,
+            &[
+                (ARCHIVE_DIRECTORY_KEY, &listing),
+                ("comment", &vec![b'a'; pax_padding]),
+            ],
+        );
+        let mut header = tar::Header::new_gnu();
+        header.set_entry_type(tar::EntryType::Directory);
+        header.set_size(dir_size as _);
+        archive
+            .append_data(&mut header, ARCHIVE_ROOT, vec![0; dir_size].as_slice())
+            .unwrap();
+        append_pax(&mut archive, &[]);
+        let mut header = tar::Header::new_gnu();
+        header.set_entry_type(tar::EntryType::Regular);
+        header.set_size(4);
+        archive
+            .append_data(
+                &mut header,
+                format!("{ARCHIVE_ROOT}/{name}"),
+                "text".as_bytes(),
+            )
+            .unwrap();
+        archive.into_inner().unwrap()
+    };
+    let archives = HashMap::from([
+        ("0.1.0", archive(0, 0)),
+        ("0.2.0", archive(64 * 1024, 0)),
+        ("0.3.0", archive(0, 1024 * 1024)),
+    ]);
+    let entry = serde_json::to_vec(&TagEntry::Unsigned(TreeEntry {
+        meta: root,
+        custom: Default::default(),
+        content: (),
+    }))
+    .unwrap();
+    let url = stub_server(move |req| {
+        let path = req.url().path();
+        match path.rsplit_once("/_tag/") {
+            Some((_, tag)) => match tag.strip_suffix("/archive") {
+                Some(tag) => {
+                    let mut res = Response::new(StatusCode::Ok);
+                    res.insert_header("Content-Type", ArchiveFormat::Tar.media_type());
+                    res.set_body(archives[tag].clone());
+                    res
+                }
+                None => stub_response(TreeEntry::<()>::TYPE, entry.clone()),
+            },
+            None => Response::new(StatusCode::NotFound),
+        }
+    })
+    .await;
+
+    let cl = Client::builder(url)
+        .limits(Limits {
+            listing: 1024,
+            ..Default::default()
+        })
+        .build()
+        .expect("failed to build client");
+    let dl = tempdir().expect("failed to create temporary download directory");
+    let download = |tag: &str| {
+        let cl = cl.clone();
+        let dest = dl.path().join(tag);
+        let tag = format!("testuser/test-repo:{tag}").parse().unwrap();
+        spawn_blocking(move || {
+            let res = cl.tag(&tag).download_archive(&dest, ArchiveFormat::Tar);
+            (res, dest)
+        })
+    };
+
+    let (unpacked, dest) = download("0.1.0").await;
+    assert_eq!(
+        unpacked.expect("failed to download archive").len(),
+        tree.len()
+    );
+    assert_eq!(std::fs::read_to_string(dest.join(&name)).unwrap(), "text");
+    for tag in ["0.2.0", "0.3.0"] {
+        let (unpacked, dest) = download(tag).await;
+        assert!(unpacked.is_err());
+        assert!(!dest.exists());
+    }
 }